@@ -0,0 +1,6 @@
+CREATE TABLE flow_secrets (
+    flow_id INTEGER NOT NULL REFERENCES flows (id) ON DELETE CASCADE,
+    secret_key TEXT NOT NULL,
+    secret_value TEXT NOT NULL,
+    PRIMARY KEY (flow_id, secret_key)
+)
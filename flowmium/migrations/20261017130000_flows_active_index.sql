@@ -0,0 +1 @@
+CREATE INDEX flows_active_idx ON flows (id) WHERE status IN ('running', 'pending')
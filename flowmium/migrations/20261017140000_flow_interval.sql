@@ -0,0 +1,4 @@
+ALTER TABLE flows
+    ADD COLUMN interval_seconds BIGINT,
+    ADD COLUMN rerun_at TIMESTAMPTZ,
+    ADD COLUMN rerun_flow_id INTEGER
@@ -0,0 +1,24 @@
+CREATE TABLE flow_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    flow_id INTEGER NOT NULL REFERENCES flows (id) ON DELETE CASCADE,
+    task_id INTEGER,
+    from_status flow_status,
+    to_status flow_status NOT NULL,
+    transitioned_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX flow_transitions_flow_id_idx ON flow_transitions (flow_id, transitioned_at);
+
+CREATE INDEX flow_transitions_transitioned_at_idx ON flow_transitions (transitioned_at);
+
+-- Joins the flow's name so that the view is not updatable, for BI tools connecting to Postgres
+CREATE VIEW flow_transitions_view AS
+SELECT
+    transitions.flow_id,
+    flows.flow_name,
+    transitions.task_id,
+    transitions.from_status,
+    transitions.to_status,
+    transitions.transitioned_at
+FROM flow_transitions AS transitions
+JOIN flows ON flows.id = transitions.flow_id;
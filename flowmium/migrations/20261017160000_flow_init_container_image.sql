@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN init_container_image TEXT
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN on_failure_task INTEGER
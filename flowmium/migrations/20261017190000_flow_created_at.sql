@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now()
@@ -0,0 +1,3 @@
+ALTER TABLE flows
+    ADD COLUMN retried_tasks INTEGER[] NOT NULL DEFAULT '{}',
+    ADD COLUMN retry_at JSONB NOT NULL DEFAULT '{}'
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN timed_out_tasks INTEGER[] NOT NULL DEFAULT '{}'
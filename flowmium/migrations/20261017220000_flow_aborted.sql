@@ -0,0 +1 @@
+ALTER TYPE flow_status ADD VALUE 'aborted'
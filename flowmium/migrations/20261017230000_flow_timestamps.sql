@@ -0,0 +1,21 @@
+ALTER TABLE flows
+    ADD COLUMN finished_at TIMESTAMPTZ,
+    ADD COLUMN task_timestamps JSONB NOT NULL DEFAULT '{}';
+
+UPDATE flows
+SET finished_at = (SELECT max(transitioned_at) FROM flow_transitions WHERE flow_id = flows.id)
+WHERE status IN ('success', 'failed', 'aborted');
+
+CREATE FUNCTION set_flow_finished_at() RETURNS trigger AS $$
+BEGIN
+    IF NEW.status IN ('success', 'failed', 'aborted') AND NEW.status IS DISTINCT FROM OLD.status THEN
+        NEW.finished_at = now();
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER flows_finished_at
+    BEFORE UPDATE OF status ON flows
+    FOR EACH ROW EXECUTE FUNCTION set_flow_finished_at();
@@ -0,0 +1,10 @@
+CREATE TABLE flow_templates (
+    id SERIAL PRIMARY KEY,
+    flow_name TEXT NOT NULL,
+    schedule TEXT NOT NULL,
+    definition JSONB NOT NULL,
+    next_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX flow_templates_next_run_at ON flow_templates (next_run_at);
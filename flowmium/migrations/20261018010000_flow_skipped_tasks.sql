@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN skipped_tasks INTEGER[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN max_parallel_tasks INTEGER;
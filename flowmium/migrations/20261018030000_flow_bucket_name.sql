@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN bucket_name TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN idempotency_key TEXT UNIQUE;
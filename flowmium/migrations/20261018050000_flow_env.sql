@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN env JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN deadline_seconds BIGINT;
@@ -0,0 +1,2 @@
+ALTER TABLE flows
+    ADD COLUMN task_failures JSONB NOT NULL DEFAULT '{}';
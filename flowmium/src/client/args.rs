@@ -1,11 +1,25 @@
 use argh::FromArgs;
+use std::time::Duration;
+
+use crate::server::record::FlowStatus;
+
+use super::requests::EventsSince;
 
 #[derive(FromArgs, PartialEq, Debug)]
 /// flowctl, CLI tool for interacting with the Flowmium server
 pub struct FlowCtlOptions {
-    #[argh(option, default = "String::from(\"http://localhost:8080\")")]
-    /// flowmium server url
-    pub url: String,
+    #[argh(option)]
+    /// flowmium server url, overrides the profile and the FLOWCTL_URL environment variable,
+    /// default is http://localhost:8080
+    pub url: Option<String>,
+
+    #[argh(option)]
+    /// name of the profile in ~/.flowctl.toml to use the server url and token from
+    pub profile: Option<String>,
+
+    #[argh(option)]
+    /// bearer token sent with every request, overrides the profile and the FLOWCTL_TOKEN environment variable
+    pub token: Option<String>,
 
     #[argh(subcommand)]
     pub command: Command,
@@ -14,18 +28,97 @@ pub struct FlowCtlOptions {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand)]
 pub enum Command {
+    Cancel(CancelOpts),
     List(LsOpts),
+    ListSchedules(ListSchedulesOpts),
     Describe(DescribeOpts),
+    Diff(DiffOpts),
+    Dot(DotOpts),
     Download(DownloadOpts),
+    Logs(LogsOpts),
+    Purge(PurgeOpts),
+    Rerun(RerunOpts),
+    Resubmit(ResubmitOpts),
+    Retry(RetryOpts),
     Secret(SecretOpts),
+    Simulate(SimulateOpts),
     Subscribe(SubscribeOpts),
     Submit(SubmitOpts),
+    Unschedule(UnscheduleOpts),
+    Usage(UsageOpts),
+    Validate(ValidateOpts),
+    Wait(WaitOpts),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "list")]
 /// list all workflows
-pub struct LsOpts {}
+pub struct LsOpts {
+    #[argh(switch)]
+    /// only list workflows that are running or yet to run, other filters are ignored
+    pub running: bool,
+
+    #[argh(option, from_str_fn(parse_flow_status))]
+    /// only list workflows with this status, one of pending, running, success, failed or aborted
+    pub status: Option<FlowStatus>,
+
+    #[argh(option)]
+    /// only list workflows whose name contains this string
+    pub name_contains: Option<String>,
+
+    #[argh(option)]
+    /// number of matching workflows to skip
+    pub offset: Option<u32>,
+
+    #[argh(option)]
+    /// maximum number of workflows to list, at most 1000
+    pub limit: Option<u32>,
+
+    #[argh(switch)]
+    /// refresh the list periodically until interrupted with Ctrl-C
+    pub watch: bool,
+
+    #[argh(option, default = "2")]
+    /// seconds between refreshes of the list with --watch, default is 2
+    pub interval: u64,
+}
+
+fn parse_flow_status(value: &str) -> Result<FlowStatus, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("invalid status {}", value))
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "list-schedules")]
+/// list workflows registered to run on a cron schedule and their next run time
+pub struct ListSchedulesOpts {}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "unschedule")]
+/// unregister a workflow from running on a cron schedule, runs that were already created are kept
+pub struct UnscheduleOpts {
+    #[argh(positional)]
+    /// id of the recurring workflow, as listed by list-schedules
+    pub id: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "cancel")]
+/// abort a running or pending workflow and delete its tasks
+pub struct CancelOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "purge")]
+/// delete a terminated workflow along with its artefacts
+pub struct PurgeOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+}
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "describe")]
@@ -34,6 +127,36 @@ pub struct DescribeOpts {
     #[argh(positional)]
     /// id of the workflow
     pub id: String,
+
+    #[argh(switch)]
+    /// include storage used by artefacts of the workflow
+    pub usage: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "usage")]
+/// show storage used by artefacts of each workflow and the total
+pub struct UsageOpts {}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "diff")]
+/// show how tasks changed between two workflows
+pub struct DiffOpts {
+    #[argh(positional)]
+    /// id of the first workflow
+    pub id1: String,
+    #[argh(positional)]
+    /// id of the second workflow
+    pub id2: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "dot")]
+/// print task dependency graph of a workflow in graphviz dot format
+pub struct DotOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -53,6 +176,85 @@ pub struct DownloadOpts {
     pub local_dir_path: String,
 }
 
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "logs")]
+/// print combined logs of all tasks of a workflow prefixed with task names, or logs of a single task
+pub struct LogsOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+
+    #[argh(positional)]
+    /// id of a task of the workflow to only print its logs
+    pub task_id: Option<String>,
+
+    #[argh(switch, short = 'f')]
+    /// keep streaming logs of running tasks until they terminate
+    pub follow: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "retry")]
+/// retry a failed workflow, only running tasks that failed or did not run and reusing outputs of finished tasks
+pub struct RetryOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "wait")]
+/// wait for a workflow to succeed, fail or be aborted and print its status, exits with a failure unless it succeeded
+pub struct WaitOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+
+    #[argh(option, default = "300")]
+    /// seconds to wait for the workflow to terminate before giving up, default is 300
+    pub timeout: u64,
+
+    #[argh(switch)]
+    /// use wss:// scheme instead of ws:// scheme
+    pub secure: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "resubmit")]
+/// submit a copy of an existing workflow as a new workflow, without needing its definition file
+pub struct ResubmitOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "rerun")]
+/// submit a new workflow from an existing workflow's definition with environment variables overridden
+pub struct RerunOpts {
+    #[argh(positional)]
+    /// id of the workflow
+    pub id: String,
+
+    #[argh(option, from_str_fn(parse_env_override))]
+    /// environment variable to override like `LOG_LEVEL=debug`, can be repeated
+    pub env: Vec<(String, String)>,
+
+    #[argh(option)]
+    /// name of the task to apply overrides to, can be repeated, defaults to all tasks
+    pub task: Vec<String>,
+}
+
+fn parse_env_override(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(format!(
+            "invalid environment variable, expected KEY=VALUE: {}",
+            value
+        )),
+    }
+}
+
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "secret")]
 /// manage secrets stored in the server
@@ -64,11 +266,17 @@ pub struct SecretOpts {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand)]
 pub enum SecretCommand {
+    List(SecretListOpts),
     Create(SecretCreateOpts),
     Delete(SecretDeleteOpts),
     Update(SecretUpdateOpts),
 }
 
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "list")]
+/// list secret keys with when they were created and last updated, values are never shown
+pub struct SecretListOpts {}
+
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "create")]
 /// create a secret
@@ -109,13 +317,127 @@ pub struct SubscribeOpts {
     #[argh(switch)]
     /// use wss:// scheme instead of ws:// scheme
     pub secure: bool,
+
+    #[argh(option, from_str_fn(parse_events_since))]
+    /// replay recent events before live events, either since an event sequence number like `42`
+    /// or within a duration like `30s`, `5m` or `1h`
+    pub since: Option<EventsSince>,
+
+    #[argh(option)]
+    /// only print events of the workflow with this id
+    pub flow_id: Option<i32>,
+}
+
+fn parse_events_since(value: &str) -> Result<EventsSince, String> {
+    if let Ok(seq) = value.parse::<u64>() {
+        return Ok(EventsSince::Seq(seq));
+    }
+
+    let invalid = || format!("invalid sequence number or duration: {}", value);
+
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+    let seconds = match unit {
+        "s" => Some(amount),
+        "m" => amount.checked_mul(60),
+        "h" => amount.checked_mul(60 * 60),
+        _ => return Err(invalid()),
+    };
+
+    match seconds {
+        Some(seconds) => Ok(EventsSince::Duration(Duration::from_secs(seconds))),
+        None => Err(format!("duration too long: {}", value)),
+    }
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "submit")]
-/// submit workflow yaml definition file
+/// submit workflow yaml or json definition file
 pub struct SubmitOpts {
     #[argh(positional)]
-    /// path to the yaml definition file
+    /// path to the yaml or json definition file
     pub file_path: String,
+
+    #[argh(option)]
+    /// key that makes submitting again with the same key return the workflow created first
+    /// instead of creating a duplicate
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "simulate")]
+/// project peak concurrency and stages if workflow yaml definition files were submitted together
+pub struct SimulateOpts {
+    #[argh(positional)]
+    /// paths to the yaml or json definition files
+    pub file_paths: Vec<String>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "validate")]
+/// validate a workflow without submitting it and print the stages its tasks would run in
+pub struct ValidateOpts {
+    #[argh(positional)]
+    /// path to the yaml or json definition file
+    pub file_path: String,
+
+    #[argh(switch)]
+    /// list which tasks each task depends on, to explain why tasks are grouped into stages
+    pub explain: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flow_status() {
+        assert_eq!(parse_flow_status("running"), Ok(FlowStatus::Running));
+        assert_eq!(parse_flow_status("Failed"), Ok(FlowStatus::Failed));
+        assert!(parse_flow_status("done").is_err());
+    }
+
+    #[test]
+    fn test_parse_env_override() {
+        assert_eq!(
+            parse_env_override("LOG_LEVEL=debug"),
+            Ok(("LOG_LEVEL".to_owned(), "debug".to_owned()))
+        );
+        assert_eq!(
+            parse_env_override("QUERY=a=b"),
+            Ok(("QUERY".to_owned(), "a=b".to_owned()))
+        );
+        assert_eq!(
+            parse_env_override("EMPTY="),
+            Ok(("EMPTY".to_owned(), "".to_owned()))
+        );
+        assert!(parse_env_override("LOG_LEVEL").is_err());
+        assert!(parse_env_override("=debug").is_err());
+    }
+
+    #[test]
+    fn test_parse_events_since() {
+        assert_eq!(parse_events_since("42"), Ok(EventsSince::Seq(42)));
+        assert_eq!(
+            parse_events_since("30s"),
+            Ok(EventsSince::Duration(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            parse_events_since("5m"),
+            Ok(EventsSince::Duration(Duration::from_secs(300)))
+        );
+        assert_eq!(
+            parse_events_since("1h"),
+            Ok(EventsSince::Duration(Duration::from_secs(3600)))
+        );
+        assert!(parse_events_since("5d").is_err());
+        assert_eq!(
+            parse_events_since("18446744073709551615m"),
+            Err("duration too long: 18446744073709551615m".to_owned())
+        );
+        assert!(parse_events_since("5124095576030432h").is_err());
+        assert!(parse_events_since("m").is_err());
+        assert!(parse_events_since("").is_err());
+    }
 }
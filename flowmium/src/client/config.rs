@@ -0,0 +1,188 @@
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::PathBuf};
+use thiserror::Error;
+
+/// Server URL used if it is not set by a flag, profile or environment variable.
+pub const DEFAULT_URL: &str = "http://localhost:8080";
+
+/// Environment variable to set the server URL.
+pub const URL_ENV: &str = "FLOWCTL_URL";
+
+/// Environment variable to set the bearer token sent to the server.
+pub const TOKEN_ENV: &str = "FLOWCTL_TOKEN";
+
+/// Environment variable to override the path to the config file, default is `~/.flowctl.toml`.
+pub const CONFIG_PATH_ENV: &str = "FLOWCTL_CONFIG";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("unable to read config file {0}: {1}")]
+    UnableToRead(String, #[source] std::io::Error),
+    #[error("invalid config file {0}: {1}")]
+    Invalid(String, #[source] toml::de::Error),
+    #[error("profile {0} does not exist in config file")]
+    ProfileDoesNotExist(String),
+    #[error(
+        "no config file found for profile {0}, set {CONFIG_PATH_ENV} or create ~/.flowctl.toml"
+    )]
+    NoConfigFile(String),
+}
+
+/// Named profile for an environment like dev or prod.
+#[derive(Deserialize, Debug, PartialEq, Default)]
+pub struct Profile {
+    /// URL of the flowmium server.
+    pub url: Option<String>,
+    /// Bearer token sent with every request to the server, like for a server behind an authenticating proxy.
+    pub token: Option<String>,
+}
+
+/// Contents of the `flowctl` config file, profiles are defined in tables like `[profiles.dev]`.
+#[derive(Deserialize, Debug, PartialEq, Default)]
+pub struct FlowCtlConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+fn get_config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".flowctl.toml"))
+}
+
+fn parse_config(path: &str, contents: &str) -> Result<FlowCtlConfig, ConfigError> {
+    toml::from_str(contents).map_err(|error| ConfigError::Invalid(path.to_owned(), error))
+}
+
+/// Load the profile from the config file.
+pub fn load_profile(name: &str) -> Result<Profile, ConfigError> {
+    let Some(path) = get_config_path() else {
+        return Err(ConfigError::NoConfigFile(name.to_owned()));
+    };
+
+    let path = path.to_string_lossy().to_string();
+
+    let contents = std::fs::read_to_string(&path).map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => ConfigError::NoConfigFile(name.to_owned()),
+        _ => ConfigError::UnableToRead(path.clone(), error),
+    })?;
+
+    parse_config(&path, &contents)?
+        .profiles
+        .remove(name)
+        .ok_or_else(|| ConfigError::ProfileDoesNotExist(name.to_owned()))
+}
+
+/// Resolve the server URL, in the order of precedence `--url` flag, profile, environment variable and default.
+pub fn resolve_url(
+    flag_url: Option<String>,
+    profile: Option<&Profile>,
+    env_url: Option<String>,
+) -> String {
+    flag_url
+        .or(profile.and_then(|profile| profile.url.clone()))
+        .or(env_url)
+        .unwrap_or_else(|| DEFAULT_URL.to_owned())
+}
+
+/// Resolve the bearer token, in the order of precedence `--token` flag, profile and environment variable.
+/// No token is sent by default.
+pub fn resolve_token(
+    flag_token: Option<String>,
+    profile: Option<&Profile>,
+    env_token: Option<String>,
+) -> Option<String> {
+    flag_token
+        .or(profile.and_then(|profile| profile.token.clone()))
+        .or(env_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse_config(
+            "flowctl.toml",
+            r#"
+            [profiles.dev]
+            url = "http://localhost:8080"
+
+            [profiles.prod]
+            url = "https://flowmium.example.com"
+            token = "prod-token"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.profiles["prod"],
+            Profile {
+                url: Some("https://flowmium.example.com".to_owned()),
+                token: Some("prod-token".to_owned()),
+            }
+        );
+        assert_eq!(config.profiles.len(), 2);
+
+        assert!(matches!(
+            parse_config("flowctl.toml", "[profiles.dev]\nurl = 8080"),
+            Err(ConfigError::Invalid(..))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let profile = Profile {
+            url: Some("http://profile:8080".to_owned()),
+            token: None,
+        };
+        let flag = || Some("http://flag:8080".to_owned());
+        let env = || Some("http://env:8080".to_owned());
+
+        assert_eq!(
+            resolve_url(flag(), Some(&profile), env()),
+            "http://flag:8080"
+        );
+        assert_eq!(
+            resolve_url(None, Some(&profile), env()),
+            "http://profile:8080"
+        );
+        assert_eq!(
+            resolve_url(None, Some(&Profile::default()), env()),
+            "http://env:8080"
+        );
+        assert_eq!(resolve_url(None, None, env()), "http://env:8080");
+        assert_eq!(resolve_url(None, None, None), DEFAULT_URL);
+    }
+
+    #[test]
+    fn test_resolve_token() {
+        let profile = Profile {
+            url: None,
+            token: Some("profile-token".to_owned()),
+        };
+        let flag = || Some("flag-token".to_owned());
+        let env = || Some("env-token".to_owned());
+
+        assert_eq!(
+            resolve_token(flag(), Some(&profile), env()).as_deref(),
+            Some("flag-token")
+        );
+        assert_eq!(
+            resolve_token(None, Some(&profile), env()).as_deref(),
+            Some("profile-token")
+        );
+        assert_eq!(
+            resolve_token(None, Some(&Profile::default()), env()).as_deref(),
+            Some("env-token")
+        );
+        assert_eq!(
+            resolve_token(None, None, env()).as_deref(),
+            Some("env-token")
+        );
+        assert_eq!(resolve_token(None, None, None), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::server::model::Task;
+
+use super::requests::{FieldChange, TaskChange};
+
+fn task_fields(task: &Task) -> BTreeMap<String, serde_json::Value> {
+    // SAFETY: Task doesn't implement custom serializer methods or have non string keys
+    match serde_json::to_value(task).unwrap() {
+        serde_json::Value::Object(fields) => fields.into_iter().collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn diff_task(old: &Task, new: &Task) -> Vec<FieldChange> {
+    let old_fields = task_fields(old);
+    let new_fields = task_fields(new);
+
+    let names: BTreeSet<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old_value = old_fields.get(name).unwrap_or(&serde_json::Value::Null);
+            let new_value = new_fields.get(name).unwrap_or(&serde_json::Value::Null);
+
+            (old_value != new_value).then(|| FieldChange {
+                field: name.to_owned(),
+                old: old_value.clone(),
+                new: new_value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Changes to tasks from `old` to `new`, tasks are matched by name since the position of a task can change between
+/// flows. Removed and changed tasks are in the order they are defined in `old`, followed by added tasks in the order
+/// they are defined in `new`.
+pub(crate) fn diff_tasks(old: &[Task], new: &[Task]) -> Vec<TaskChange> {
+    let new_tasks: BTreeMap<&String, &Task> = new.iter().map(|task| (&task.name, task)).collect();
+    let old_names: BTreeSet<&String> = old.iter().map(|task| &task.name).collect();
+
+    let removed_or_changed = old.iter().filter_map(|old_task| {
+        let Some(new_task) = new_tasks.get(&old_task.name) else {
+            return Some(TaskChange::Removed(old_task.name.clone()));
+        };
+
+        let fields = diff_task(old_task, new_task);
+
+        (!fields.is_empty()).then(|| TaskChange::Changed {
+            name: old_task.name.clone(),
+            fields,
+        })
+    });
+
+    let added = new
+        .iter()
+        .filter(|new_task| !old_names.contains(&new_task.name))
+        .map(|new_task| TaskChange::Added(new_task.name.clone()));
+
+    removed_or_changed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::server::model::Flow;
+
+    use super::*;
+
+    fn create_fake_task(name: &str, image: &str, depends: &[&str]) -> Task {
+        Task {
+            name: name.to_string(),
+            image: image.to_string(),
+            depends: depends.iter().map(|dep| dep.to_string()).collect(),
+            cmd: vec!["python3".to_string(), "main.py".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_tasks() {
+        let old = Flow {
+            name: "hello-world".to_string(),
+            tasks: vec![
+                create_fake_task("task-a", "python:3.11", &[]),
+                create_fake_task("task-b", "python:3.11", &["task-a"]),
+                create_fake_task("task-c", "python:3.11", &["task-a"]),
+            ],
+            ..Default::default()
+        };
+
+        let new = Flow {
+            name: "hello-world".to_string(),
+            tasks: vec![
+                create_fake_task("task-d", "python:3.12", &["task-a"]),
+                create_fake_task("task-a", "python:3.11", &[]),
+                Task {
+                    cmd: vec!["python3".to_string()],
+                    ..create_fake_task("task-b", "python:3.12", &["task-a", "task-d"])
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            diff_tasks(&old.tasks, &new.tasks),
+            vec![
+                TaskChange::Changed {
+                    name: "task-b".to_string(),
+                    fields: vec![
+                        FieldChange {
+                            field: "cmd".to_string(),
+                            old: json!(["python3", "main.py"]),
+                            new: json!(["python3"]),
+                        },
+                        FieldChange {
+                            field: "depends".to_string(),
+                            old: json!(["task-a"]),
+                            new: json!(["task-a", "task-d"]),
+                        },
+                        FieldChange {
+                            field: "image".to_string(),
+                            old: json!("python:3.11"),
+                            new: json!("python:3.12"),
+                        },
+                    ],
+                },
+                TaskChange::Removed("task-c".to_string()),
+                TaskChange::Added("task-d".to_string()),
+            ]
+        );
+
+        assert_eq!(diff_tasks(&new.tasks, &new.tasks), vec![]);
+        assert_eq!(
+            diff_tasks(&[], &old.tasks[..1]),
+            vec![TaskChange::Added("task-a".to_string())]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use crate::server::{model::Task, record::TaskStatus};
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn status_to_color(status: Option<&TaskStatus>) -> &'static str {
+    match status {
+        None => "white",
+        Some(TaskStatus::Running) => "lightskyblue",
+        Some(TaskStatus::Finished) => "palegreen",
+        Some(TaskStatus::Failed) => "lightcoral",
+        Some(TaskStatus::Skipped) => "lightgray",
+    }
+}
+
+/// Render the task dependency graph of a flow in Graphviz DOT format. Each task is a node filled with a
+/// color based on its status in `task_status` (tasks not present are pending), and edges point from a task
+/// to the tasks that depend on it.
+pub(crate) fn flow_to_dot(
+    flow_name: &str,
+    tasks: &[Task],
+    task_status: &BTreeMap<usize, TaskStatus>,
+) -> String {
+    let task_id_map: BTreeMap<&String, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(index, task)| (&task.name, index))
+        .collect();
+
+    let mut dot = format!("digraph \"{}\" {{\n", escape(flow_name));
+    dot.push_str("    node [shape=box, style=filled];\n");
+
+    for (task_id, task) in tasks.iter().enumerate() {
+        dot.push_str(&format!(
+            "    task_{} [label=\"{}\", fillcolor=\"{}\"];\n",
+            task_id,
+            escape(&task.name),
+            status_to_color(task_status.get(&task_id))
+        ));
+    }
+
+    for (task_id, task) in tasks.iter().enumerate() {
+        for dep in task.depends.iter() {
+            if let Some(dep_id) = task_id_map.get(dep) {
+                dot.push_str(&format!("    task_{} -> task_{};\n", dep_id, task_id));
+            }
+        }
+    }
+
+    dot.push_str("}\n");
+
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_fake_task(name: &str, depends: &[&str]) -> Task {
+        Task {
+            name: name.to_string(),
+            image: "".to_string(),
+            depends: depends.iter().map(|dep| dep.to_string()).collect(),
+            cmd: vec![],
+            env: vec![],
+            inputs: None,
+            outputs: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flow_to_dot() {
+        let tasks = vec![
+            create_fake_task("task-a", &[]),
+            create_fake_task("task-b", &["task-a"]),
+            create_fake_task("task-c", &["task-a"]),
+            create_fake_task("task-\"d\"", &["task-b", "task-c"]),
+        ];
+
+        let task_status = BTreeMap::from([
+            (0, TaskStatus::Finished),
+            (1, TaskStatus::Running),
+            (2, TaskStatus::Failed),
+        ]);
+
+        let expected = r#"digraph "hello-world" {
+    node [shape=box, style=filled];
+    task_0 [label="task-a", fillcolor="palegreen"];
+    task_1 [label="task-b", fillcolor="lightskyblue"];
+    task_2 [label="task-c", fillcolor="lightcoral"];
+    task_3 [label="task-\"d\"", fillcolor="white"];
+    task_0 -> task_1;
+    task_0 -> task_2;
+    task_1 -> task_3;
+    task_2 -> task_3;
+}
+"#;
+
+        assert_eq!(flow_to_dot("hello-world", &tasks, &task_status), expected);
+    }
+}
@@ -1,13 +1,20 @@
 use std::future::Future;
 use std::process::ExitCode;
+use std::time::Duration;
 
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 use tokio_stream::StreamExt;
 
 use crate::client::args;
+use crate::client::config;
 use crate::client::requests;
 
-use crate::client::requests::ClientError;
-use crate::server::model::Flow;
+use crate::client::requests::{ClientError, ClientWebsocketError};
+use crate::server::event::SchedulerEvent;
+use crate::server::model::{EnvOverrides, Flow};
+use crate::server::record::FlowStatus;
+use crate::server::scheduler::FlowListFilter;
 
 async fn make_request<T, F>(req_func: impl Fn() -> F) -> ExitCode
 where
@@ -26,8 +33,101 @@ where
     }
 }
 
+/// Print the response of the request again every `interval` until interrupted with Ctrl-C, clearing the screen
+/// before each response. Errors are printed in place of the response and the request is made again on the next refresh.
+async fn watch_request<T, F>(req_func: impl Fn() -> F, interval: Duration) -> ExitCode
+where
+    F: Future<Output = Result<T, ClientError>>,
+    T: std::fmt::Display,
+{
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        let output = tokio::select! {
+            resp = req_func() => match resp {
+                Ok(resp) => resp.to_string(),
+                Err(error) => error.to_string(),
+            },
+            _ = &mut ctrl_c => return ExitCode::SUCCESS,
+        };
+
+        // Clear the screen and move the cursor to the top left corner before printing
+        println!("\x1B[2J\x1B[H{}", output);
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => (),
+            _ = &mut ctrl_c => return ExitCode::SUCCESS,
+        }
+    }
+}
+
+/// Error parsing a flow definition file.
+#[derive(Error, Debug, PartialEq)]
+enum FlowDefinitionError {
+    /// Malformed definition, line and column of the error in the file start from one.
+    #[error("line {line} column {column}: {message}")]
+    Malformed {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Malformed definition where the location of the error is not known.
+    #[error("{0}")]
+    Unlocated(String),
+}
+
+impl FlowDefinitionError {
+    fn new(error: String, location: Option<(usize, usize)>) -> Self {
+        let Some((line, column)) = location else {
+            return Self::Unlocated(error);
+        };
+
+        // Messages of serde errors end with the location, it is printed before the message instead
+        let message = error.replacen(&format!(" at line {} column {}", line, column), "", 1);
+
+        Self::Malformed {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for FlowDefinitionError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let location = error
+            .location()
+            .map(|location| (location.line(), location.column()));
+
+        Self::new(error.to_string(), location)
+    }
+}
+
+impl From<serde_json::Error> for FlowDefinitionError {
+    fn from(error: serde_json::Error) -> Self {
+        let location = match error.line() {
+            0 => None,
+            line => Some((line, error.column())),
+        };
+
+        Self::new(error.to_string(), location)
+    }
+}
+
+fn parse_flow(file_path: &str, contents: &str) -> Result<Flow, FlowDefinitionError> {
+    let is_json = std::path::Path::new(file_path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+
+    match is_json {
+        true => Ok(serde_json::from_str(contents)?),
+        false => Ok(serde_yaml::from_str(contents)?),
+    }
+}
+
 async fn get_flow_from_file(file_path: String) -> Result<Flow, ExitCode> {
-    let contents = tokio::fs::read_to_string(file_path).await;
+    let contents = tokio::fs::read_to_string(&file_path).await;
 
     let contents = match contents {
         Ok(contents) => contents,
@@ -37,7 +137,7 @@ async fn get_flow_from_file(file_path: String) -> Result<Flow, ExitCode> {
         }
     };
 
-    let flow = serde_yaml::from_str(&contents);
+    let flow = parse_flow(&file_path, &contents);
 
     let flow = match flow {
         Ok(flow) => flow,
@@ -50,72 +150,329 @@ async fn get_flow_from_file(file_path: String) -> Result<Flow, ExitCode> {
     Ok(flow)
 }
 
+async fn print_logs(url: &str, id: &str, task_id: Option<&str>, follow: bool) -> ExitCode {
+    let response = match task_id {
+        Some(task_id) => requests::fetch_logs(url, id, task_id, follow).await,
+        None => requests::get_logs(url, id, follow).await,
+    };
+
+    let mut response = match response {
+        Ok(response) => response,
+        Err(error) => {
+            eprint!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut stdout = tokio::io::stdout();
+
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                if let Err(error) = stdout.write_all(&chunk).await {
+                    eprint!("{}", error);
+                    return ExitCode::FAILURE;
+                }
+            }
+            Ok(None) => return ExitCode::SUCCESS,
+            Err(error) => {
+                eprint!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+}
+
+async fn print_events(
+    stream: Result<
+        impl StreamExt<Item = Result<SchedulerEvent, ClientWebsocketError>>,
+        ClientError,
+    >,
+) -> ExitCode {
+    let stream = match stream {
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+        Ok(stream) => stream,
+    };
+
+    tokio::pin!(stream);
+
+    loop {
+        match stream.next().await {
+            Some(Err(error)) => {
+                eprintln!("{}", error);
+                break ExitCode::FAILURE;
+            }
+            Some(Ok(event)) => {
+                println!("{}", serde_json::to_string(&event).unwrap())
+            }
+            None => break ExitCode::SUCCESS,
+        }
+    }
+}
+
 /// Parse CLI arguments and run `flowctl`.
 pub async fn run() -> ExitCode {
     let args: args::FlowCtlOptions = argh::from_env();
 
+    let profile = match args
+        .profile
+        .as_deref()
+        .map(config::load_profile)
+        .transpose()
+    {
+        Ok(profile) => profile,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let url = config::resolve_url(
+        args.url,
+        profile.as_ref(),
+        std::env::var(config::URL_ENV).ok(),
+    );
+
+    let token = config::resolve_token(
+        args.token,
+        profile.as_ref(),
+        std::env::var(config::TOKEN_ENV).ok(),
+    );
+
+    if let Err(error) = token.as_deref().map(requests::set_token).transpose() {
+        eprintln!("{}", error);
+        return ExitCode::FAILURE;
+    }
+
     match args.command {
-        args::Command::List(_) => make_request(|| requests::list_workflows(&args.url)).await,
-        args::Command::Describe(describe_opts) => {
-            make_request(|| requests::get_status(&args.url, &describe_opts.id)).await
+        args::Command::List(list_opts) => {
+            let filter = FlowListFilter {
+                status: list_opts.status,
+                name_contains: list_opts.name_contains,
+                offset: list_opts.offset,
+                limit: list_opts.limit,
+            };
+
+            let list = || async {
+                match list_opts.running {
+                    true => requests::list_active_workflows(&url).await,
+                    false => requests::list_workflows(&url, &filter).await,
+                }
+            };
+
+            match list_opts.watch {
+                true => watch_request(list, Duration::from_secs(list_opts.interval.max(1))).await,
+                false => make_request(list).await,
+            }
+        }
+        args::Command::ListSchedules(_) => make_request(|| requests::list_schedules(&url)).await,
+        args::Command::Unschedule(unschedule_opts) => {
+            make_request(|| requests::delete_schedule(&url, &unschedule_opts.id)).await
+        }
+        args::Command::Describe(describe_opts) => match describe_opts.usage {
+            true => make_request(|| requests::describe_with_usage(&url, &describe_opts.id)).await,
+            false => make_request(|| requests::get_status(&url, &describe_opts.id)).await,
+        },
+        args::Command::Usage(_) => make_request(|| requests::get_storage_usage_report(&url)).await,
+        args::Command::Diff(diff_opts) => {
+            make_request(|| requests::diff_workflows(&url, &diff_opts.id1, &diff_opts.id2)).await
+        }
+        args::Command::Dot(dot_opts) => {
+            make_request(|| requests::get_dot(&url, &dot_opts.id)).await
         }
         args::Command::Secret(secret_opts) => match secret_opts.command {
+            args::SecretCommand::List(_) => make_request(|| requests::list_secrets(&url)).await,
             args::SecretCommand::Create(create_opts) => {
-                make_request(|| {
-                    requests::create_secret(&args.url, &create_opts.key, &create_opts.value)
-                })
-                .await
+                make_request(|| requests::create_secret(&url, &create_opts.key, &create_opts.value))
+                    .await
             }
             args::SecretCommand::Update(update_opts) => {
-                make_request(|| {
-                    requests::update_secret(&args.url, &update_opts.key, &update_opts.value)
-                })
-                .await
+                make_request(|| requests::update_secret(&url, &update_opts.key, &update_opts.value))
+                    .await
             }
             args::SecretCommand::Delete(delete_opts) => {
-                make_request(|| requests::delete_secret(&args.url, &delete_opts.key)).await
+                make_request(|| requests::delete_secret(&url, &delete_opts.key)).await
             }
         },
         args::Command::Download(download_opts) => {
-            make_request(|| {
+            let download = make_request(|| {
                 requests::download_artefact_to_path(
-                    &args.url,
+                    &url,
                     &download_opts.id,
                     &download_opts.name,
                     &download_opts.local_dir_path,
                 )
-            })
+            });
+
+            // Dropping the download future on Ctrl-C removes the partially downloaded file
+            tokio::select! {
+                exit_code = download => exit_code,
+                _ = tokio::signal::ctrl_c() => {
+                    eprintln!("download cancelled");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        args::Command::Logs(logs_opts) => {
+            print_logs(
+                &url,
+                &logs_opts.id,
+                logs_opts.task_id.as_deref(),
+                logs_opts.follow,
+            )
             .await
         }
+        args::Command::Cancel(cancel_opts) => {
+            make_request(|| requests::cancel_workflow(&url, &cancel_opts.id)).await
+        }
+        args::Command::Purge(purge_opts) => {
+            make_request(|| requests::purge_workflow(&url, &purge_opts.id)).await
+        }
+        args::Command::Retry(retry_opts) => {
+            make_request(|| requests::retry_workflow(&url, &retry_opts.id)).await
+        }
+        args::Command::Rerun(rerun_opts) => {
+            let overrides = EnvOverrides {
+                env: rerun_opts.env.into_iter().collect(),
+                tasks: rerun_opts.task,
+            };
+
+            make_request(|| requests::rerun(&url, &rerun_opts.id, &overrides)).await
+        }
+        args::Command::Resubmit(resubmit_opts) => {
+            make_request(|| requests::resubmit(&url, &resubmit_opts.id)).await
+        }
         args::Command::Submit(submit_opts) => {
             let flow = match get_flow_from_file(submit_opts.file_path).await {
                 Err(exit_code) => return exit_code,
                 Ok(flow) => flow,
             };
 
-            make_request(|| requests::submit(&args.url, &flow)).await
+            make_request(|| requests::submit(&url, &flow, submit_opts.idempotency_key.as_deref()))
+                .await
         }
-        args::Command::Subscribe(subscribe_opts) => {
-            let stream = requests::subscribe(&args.url, subscribe_opts.secure).await;
+        args::Command::Validate(validate_opts) => {
+            let flow = match get_flow_from_file(validate_opts.file_path).await {
+                Err(exit_code) => return exit_code,
+                Ok(flow) => flow,
+            };
+
+            match validate_opts.explain {
+                true => make_request(|| requests::explain_workflow(&url, &flow)).await,
+                false => make_request(|| requests::validate_workflow(&url, &flow)).await,
+            }
+        }
+        args::Command::Simulate(simulate_opts) => {
+            let mut flows = vec![];
+
+            for file_path in simulate_opts.file_paths {
+                match get_flow_from_file(file_path).await {
+                    Err(exit_code) => return exit_code,
+                    Ok(flow) => flows.push(flow),
+                }
+            }
+
+            make_request(|| requests::simulate(&url, &flows)).await
+        }
+        args::Command::Wait(wait_opts) => {
+            let timeout = Duration::from_secs(wait_opts.timeout);
+
+            match requests::wait_for_completion(&url, &wait_opts.id, wait_opts.secure, timeout)
+                .await
+            {
+                Ok(status) => {
+                    println!("{}", status);
 
-            match stream {
+                    match status {
+                        FlowStatus::Success => ExitCode::SUCCESS,
+                        _ => ExitCode::FAILURE,
+                    }
+                }
                 Err(error) => {
-                    eprintln!("{}", error);
+                    eprint!("{}", error);
                     ExitCode::FAILURE
                 }
-                Ok(mut stream) => loop {
-                    match stream.next().await {
-                        Some(Err(error)) => {
-                            eprintln!("{}", error);
-                            break ExitCode::FAILURE;
-                        }
-                        Some(Ok(event)) => {
-                            println!("{}", serde_json::to_string(&event).unwrap())
-                        }
-                        None => break ExitCode::SUCCESS,
-                    }
-                },
             }
         }
+        args::Command::Subscribe(subscribe_opts) => {
+            let secure = subscribe_opts.secure;
+            let flow_id = subscribe_opts.flow_id;
+
+            match subscribe_opts.since {
+                None => print_events(requests::subscribe(&url, secure, flow_id).await).await,
+                Some(since) => {
+                    print_events(requests::subscribe_since(&url, secure, flow_id, &since).await)
+                        .await
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flow_yaml_and_json() {
+        let yaml_flow = parse_flow(
+            "my_flow.yaml",
+            include_str!("../../../examples/yaml_flow_definition/my_flow.yaml"),
+        )
+        .unwrap();
+
+        let json_flow = parse_flow(
+            "my_flow.JSON",
+            include_str!("../../../examples/yaml_flow_definition/my_flow.json"),
+        )
+        .unwrap();
+
+        assert_eq!(yaml_flow.name, "testing");
+        assert_eq!(yaml_flow.tasks.len(), 2);
+        assert_eq!(yaml_flow, json_flow);
+    }
+
+    #[test]
+    fn test_parse_flow_json_errors() {
+        assert!(parse_flow("flow.json", "name: testing\ntasks: []\n").is_err());
+
+        let error = parse_flow(
+            "flow.json",
+            "{\n  \"name\": \"testing\",\n  \"tasks\": [}\n}",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            FlowDefinitionError::Malformed {
+                line: 3,
+                column: 13,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_flow_yaml_error_location() {
+        let contents =
+            "name: testing\ntasks:\n  - name: task-0\n    image: [ubuntu]\n    cmd: []\n";
+
+        let error = parse_flow("flow.yaml", contents).unwrap_err();
+
+        assert_eq!(
+            error,
+            FlowDefinitionError::Malformed {
+                line: 4,
+                column: 12,
+                message: "tasks[0].image: invalid type: sequence, expected a string".to_owned()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 4 column 12: tasks[0].image: invalid type: sequence, expected a string"
+        );
     }
 }
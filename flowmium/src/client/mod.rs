@@ -1,4 +1,7 @@
 mod args;
+mod config;
+mod diff;
+mod dot;
 pub mod driver;
 mod pretty;
 pub mod requests;
@@ -1,11 +1,16 @@
 use core::fmt;
 
+use k8s_openapi::chrono::{DateTime, Utc};
+
 use crate::server::{
     record::FlowRecord,
-    record::{FlowListRecord, FlowStatus},
+    record::{FlowListRecord, FlowStatus, ScheduleProjection, StorageUsageReport},
 };
 
-use super::requests::{BytesDownloaded, FlowList, Okay};
+use super::requests::{
+    BytesDownloaded, FlowDescription, FlowDiff, FlowDot, FlowList, FlowPlanExplanation, FlowStages,
+    Okay, ScheduleList, SecretList, TaskChange,
+};
 
 impl fmt::Display for Okay {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -21,12 +26,14 @@ impl fmt::Display for BytesDownloaded {
 
 impl fmt::Display for FlowStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            FlowStatus::Pending => write!(f, "PENDING"),
-            FlowStatus::Running => write!(f, "RUNNING"),
-            FlowStatus::Success => write!(f, "SUCCESS"),
-            FlowStatus::Failed => write!(f, "FAILED"),
-        }
+        // Padded so that the status is aligned in tables
+        f.pad(match self {
+            FlowStatus::Pending => "PENDING",
+            FlowStatus::Running => "RUNNING",
+            FlowStatus::Success => "SUCCESS",
+            FlowStatus::Failed => "FAILED",
+            FlowStatus::Aborted => "ABORTED",
+        })
     }
 }
 
@@ -45,22 +52,93 @@ fn get_progress_string_from_rec(rec: &FlowListRecord) -> String {
     )
 }
 
+/// Time the flow has been running for, or took if it has finished, like `1h2m3s`.
+fn get_duration_string_from_rec(rec: &FlowListRecord, now: DateTime<Utc>) -> String {
+    let Ok(created_at) = rec.created_at.parse::<DateTime<Utc>>() else {
+        return "-".to_owned();
+    };
+
+    let finished_at = match &rec.finished_at {
+        None => now,
+        Some(finished_at) => match finished_at.parse::<DateTime<Utc>>() {
+            Ok(finished_at) => finished_at,
+            Err(_) => return "-".to_owned(),
+        },
+    };
+
+    let seconds = (finished_at - created_at).num_seconds().max(0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m{}s", minutes, seconds),
+        _ => format!("{}h{}m{}s", hours, minutes, seconds),
+    }
+}
+
+/// Table of flows with their progress, status and how long they have been running for as of `now`.
+pub(crate) fn render_flow_list(records: &[FlowListRecord], now: DateTime<Utc>) -> String {
+    let mut table = format!(
+        "{: <8} {: <40} {: <12} {: <8} {: <12}\n",
+        "ID", "NAME", "PROGRESS", "STATUS", "DURATION"
+    );
+
+    for rec in records {
+        table.push_str(&format!(
+            "{: <8} {: <40} {: <12} {: <8} {: <12}\n",
+            rec.id,
+            rec.flow_name,
+            get_progress_string_from_rec(rec),
+            rec.status,
+            get_duration_string_from_rec(rec, now)
+        ));
+    }
+
+    table
+}
+
 impl fmt::Display for FlowList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render_flow_list(self.list(), Utc::now()))
+    }
+}
+
+impl fmt::Display for ScheduleList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
-            "{: <8} {: <40} {: <12} {: <8}",
-            "ID", "NAME", "PROGRESS", "STATUS"
+            "{: <8} {: <40} {: <24} {: <28}",
+            "ID", "NAME", "SCHEDULE", "NEXT RUN"
         )?;
 
-        for rec in self {
+        for rec in self.list() {
             writeln!(
                 f,
-                "{: <8} {: <40} {: <12} {: <8}",
+                "{: <8} {: <40} {: <24} {: <28}",
                 rec.id,
                 rec.flow_name,
-                get_progress_string_from_rec(rec),
-                rec.status
+                rec.schedule,
+                rec.next_run_at.as_deref().unwrap_or("-")
+            )?
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SecretList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{: <40} {: <28} {: <28}",
+            "KEY", "CREATED AT", "UPDATED AT"
+        )?;
+
+        for rec in self.list() {
+            writeln!(
+                f,
+                "{: <40} {: <28} {: <28}",
+                rec.key, rec.created_at, rec.updated_at
             )?
         }
 
@@ -77,3 +155,169 @@ impl fmt::Display for FlowRecord {
         )
     }
 }
+
+impl fmt::Display for FlowDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string_pretty(self).expect("Cannot serialize response to JSON")
+        )
+    }
+}
+
+impl fmt::Display for StorageUsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{: <8} {: <16} {: <8}", "ID", "BYTES", "OBJECTS")?;
+
+        for (id, usage) in &self.flows {
+            writeln!(f, "{: <8} {: <16} {: <8}", id, usage.bytes, usage.objects)?
+        }
+
+        writeln!(
+            f,
+            "{: <8} {: <16} {: <8}",
+            "TOTAL", self.total.bytes, self.total.objects
+        )
+    }
+}
+
+impl fmt::Display for ScheduleProjection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{: <40} {: <8} {: <8} {: <8}",
+            "NAME", "STAGES", "TASKS", "PEAK"
+        )?;
+
+        for flow in &self.flows {
+            writeln!(
+                f,
+                "{: <40} {: <8} {: <8} {: <8}",
+                flow.flow_name, flow.num_stages, flow.num_tasks, flow.peak_concurrency
+            )?
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Peak concurrency: {} tasks at step {}",
+            self.peak_concurrency, self.peak_step
+        )?;
+        writeln!(f, "Steps: {}", self.num_steps)?;
+        writeln!(f, "Total stages: {}", self.total_stages)
+    }
+}
+
+impl fmt::Display for FlowStages {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{: <8} TASKS", "STAGE")?;
+
+        for (stage_index, stage) in self.stages().iter().enumerate() {
+            writeln!(f, "{: <8} {}", stage_index, stage.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for FlowPlanExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self
+            .stages()
+            .iter()
+            .flat_map(|stage| stage.tasks.iter())
+            .map(|task| task.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("TASK".len());
+
+        writeln!(f, "{: <8} {: <name_width$} DEPENDS ON", "STAGE", "TASK")?;
+
+        for (stage_index, stage) in self.stages().iter().enumerate() {
+            for task in stage.tasks.iter() {
+                writeln!(
+                    f,
+                    "{: <8} {: <name_width$} {}",
+                    stage_index,
+                    task.name,
+                    task.depends_on.join(", ")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for FlowDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes().is_empty() {
+            return write!(f, "No changes to tasks");
+        }
+
+        for change in self.changes() {
+            match change {
+                TaskChange::Added(name) => writeln!(f, "+ {}", name)?,
+                TaskChange::Removed(name) => writeln!(f, "- {}", name)?,
+                TaskChange::Changed { name, fields } => {
+                    writeln!(f, "~ {}", name)?;
+
+                    for change in fields {
+                        writeln!(f, "    {}: {} -> {}", change.field, change.old, change.new)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for FlowDot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.dot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_flow_list() {
+        let record = |id: i32, status: FlowStatus, finished_at: Option<&str>| FlowListRecord {
+            id,
+            flow_name: format!("flow-{}", id),
+            status,
+            num_running: Some(1),
+            num_finished: Some(2),
+            num_failed: None,
+            num_total: Some(4),
+            created_at: "2026-10-17T12:00:00Z".to_owned(),
+            finished_at: finished_at.map(str::to_owned),
+        };
+
+        let records = [
+            record(1, FlowStatus::Success, Some("2026-10-17T12:01:05Z")),
+            record(2, FlowStatus::Running, None),
+        ];
+
+        let now = "2026-10-17T13:02:03Z".parse().unwrap();
+
+        assert_eq!(
+            render_flow_list(&records, now),
+            [
+                "ID       NAME                                     PROGRESS     STATUS   DURATION    ",
+                "1        flow-1                                   2/4          SUCCESS  1m5s        ",
+                "2        flow-2                                   2/4          RUNNING  1h2m3s      ",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            render_flow_list(&[], now),
+            "ID       NAME                                     PROGRESS     STATUS   DURATION    \n"
+        );
+    }
+}
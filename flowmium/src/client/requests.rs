@@ -1,17 +1,31 @@
 use getset::Getters;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Response;
+use serde::Serialize;
 use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 use tokio_stream::StreamExt;
 use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::Message;
 use url::Url;
 
-use std::fs::File;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
-use crate::server::event::{SchedulerEvent, SchedulerEventResult};
-use crate::server::model::Flow;
-use crate::server::record::{FlowListRecord, FlowRecord};
+use crate::server::event::{SchedulerEvent, SchedulerEventResult, SequencedEvent};
+use crate::server::model::{EnvOverrides, Flow, Task};
+use crate::server::planner::{Plan, StageExplanation};
+use crate::server::record::{
+    FlowListRecord, FlowRecord, FlowStatus, FlowTemplateRecord, ScheduleProjection, SecretRecord,
+    StorageUsage, StorageUsageReport, TaskStatus,
+};
+use crate::server::scheduler::FlowListFilter;
+
+use super::diff::diff_tasks;
+use super::dot::flow_to_dot;
 
 /// An error while making a request to the server.
 #[derive(Error, Debug)]
@@ -50,6 +64,15 @@ pub enum ClientError {
         #[from]
         tokio_tungstenite::tungstenite::Error,
     ),
+    /// Task definitions of a flow returned by the server could not be parsed.
+    #[error("invalid task definitions error: {0}")]
+    InvalidTaskDefinitions(#[source] serde_json::Error),
+    /// Token passed to [`set_token`] cannot be sent in a header.
+    #[error("invalid token, tokens can only contain visible ASCII characters")]
+    InvalidToken,
+    /// Workflow did not terminate within the time given to [`wait_for_completion`].
+    #[error("timed out after {0:?} waiting for workflow to terminate")]
+    WaitTimeout(Duration),
 }
 
 /// An error while receiving events from websocket.
@@ -95,6 +118,13 @@ impl<'a> IntoIterator for &'a FlowList {
     }
 }
 
+/// Wrapper type for [`Vec<FlowTemplateRecord>`](FlowTemplateRecord) with a pretty implementation for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct ScheduleList {
+    #[getset(get = "pub")]
+    list: Vec<FlowTemplateRecord>,
+}
+
 /// New type for number of bytes downloaded with a pretty implementation for [`std::fmt::Display`].
 #[derive(Getters, Debug)]
 pub struct BytesDownloaded {
@@ -102,9 +132,107 @@ pub struct BytesDownloaded {
     num_bytes: u64,
 }
 
+/// Task dependency graph of a workflow in Graphviz DOT format with a pretty implementation for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct FlowDot {
+    #[getset(get = "pub")]
+    dot: String,
+}
+
+/// Field of a task that differs between two workflows, values are as they would be in the flow definition in JSON.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Name of the field, like `image` or `depends`.
+    pub field: String,
+    /// Value of the field in the first workflow.
+    pub old: serde_json::Value,
+    /// Value of the field in the second workflow.
+    pub new: serde_json::Value,
+}
+
+/// Change to a task between two workflows, tasks are matched by name.
+#[derive(Debug, PartialEq, Serialize)]
+pub enum TaskChange {
+    /// Task only exists in the second workflow.
+    Added(String),
+    /// Task only exists in the first workflow.
+    Removed(String),
+    /// Task exists in both workflows but some of its fields differ.
+    Changed {
+        name: String,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// Changes to tasks between two workflows with a pretty implementation for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct FlowDiff {
+    #[getset(get = "pub")]
+    changes: Vec<TaskChange>,
+}
+
+/// Names of tasks in each stage a workflow would be run in with a pretty implementation for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct FlowStages {
+    #[getset(get = "pub")]
+    stages: Vec<Vec<String>>,
+}
+
+/// Tasks in each stage a workflow would be run in along with the tasks they depend on, with a pretty implementation
+/// for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct FlowPlanExplanation {
+    #[getset(get = "pub")]
+    stages: Vec<StageExplanation>,
+}
+
+/// List of secret keys with a pretty implementation for [`std::fmt::Display`].
+#[derive(Getters, Debug)]
+pub struct SecretList {
+    #[getset(get = "pub")]
+    list: Vec<SecretRecord>,
+}
+
+/// Detailed status of a workflow along with the storage used by its artefacts, serialized as a flat JSON object.
+#[derive(Serialize, Debug)]
+pub struct FlowDescription {
+    #[serde(flatten)]
+    pub record: FlowRecord,
+    pub storage_usage: StorageUsage,
+}
+
 /// Indicates the request was successful and the server responded with a 200 HTTP status code.
 pub struct Okay();
 
+/// `Authorization` header sent with every request, see [`set_token`].
+static AUTH_HEADER: OnceLock<HeaderValue> = OnceLock::new();
+
+/// Send `token` as a bearer token with every request to the server, like when the server is behind an authenticating
+/// proxy. The token can only be set once, later calls are ignored.
+pub fn set_token(token: &str) -> Result<(), ClientError> {
+    let mut header = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| ClientError::InvalidToken)?;
+    header.set_sensitive(true);
+
+    let _ = AUTH_HEADER.set(header);
+
+    Ok(())
+}
+
+/// HTTP client that sends the token set by [`set_token`].
+fn get_client() -> reqwest::Client {
+    let mut headers = HeaderMap::new();
+
+    if let Some(header) = AUTH_HEADER.get() {
+        headers.insert(AUTHORIZATION, header.clone());
+    }
+
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap_or_default()
+}
+
 fn get_abs_url(url: &str, path: &str) -> Result<Url, ClientError> {
     let base = Url::parse(url)?;
     let joined = base.join(path)?;
@@ -112,12 +240,26 @@ fn get_abs_url(url: &str, path: &str) -> Result<Url, ClientError> {
     Ok(joined)
 }
 
-/// List workflows and their status in the server.
-pub async fn list_workflows(url: &str) -> Result<FlowList, ClientError> {
+/// List workflows and their status in the server matching all filters set in `filter`.
+pub async fn list_workflows(url: &str, filter: &FlowListFilter) -> Result<FlowList, ClientError> {
     let abs_url = get_abs_url(url, "/api/v1/job")?;
 
     Ok(FlowList {
-        list: reqwest::get(abs_url)
+        list: check_status(get_client().get(abs_url).query(filter).send().await?)
+            .await?
+            .json::<Vec<FlowListRecord>>()
+            .await?,
+    })
+}
+
+/// List workflows that are currently running or yet to run (pending).
+pub async fn list_active_workflows(url: &str) -> Result<FlowList, ClientError> {
+    let abs_url = get_abs_url(url, "/api/v1/job?active=true")?;
+
+    Ok(FlowList {
+        list: get_client()
+            .get(abs_url)
+            .send()
             .await?
             .json::<Vec<FlowListRecord>>()
             .await?,
@@ -128,7 +270,94 @@ pub async fn list_workflows(url: &str) -> Result<FlowList, ClientError> {
 pub async fn get_status(url: &str, id: &str) -> Result<FlowRecord, ClientError> {
     let abs_url = get_abs_url(url, &format!("/api/v1/job/{}", id))?;
 
-    Ok(reqwest::get(abs_url).await?.json::<FlowRecord>().await?)
+    Ok(get_client()
+        .get(abs_url)
+        .send()
+        .await?
+        .json::<FlowRecord>()
+        .await?)
+}
+
+/// Get status of the latest workflow with the name `name`, the newest workflow is returned if multiple workflows
+/// have the same name, like reruns of a workflow.
+pub async fn get_latest_status_by_name(url: &str, name: &str) -> Result<FlowRecord, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/by-name/{}", name))?;
+
+    let response = check_status(get_client().get(abs_url).send().await?).await?;
+
+    Ok(response.json::<FlowRecord>().await?)
+}
+
+/// Get the storage used by artefacts of a workflow. Usage is cached by the server so it can be slightly out of date.
+pub async fn get_storage_usage(url: &str, id: &str) -> Result<StorageUsage, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}/usage", id))?;
+
+    Ok(get_client()
+        .get(abs_url)
+        .send()
+        .await?
+        .json::<StorageUsage>()
+        .await?)
+}
+
+/// Get the storage used by artefacts of all workflows.
+pub async fn get_storage_usage_report(url: &str) -> Result<StorageUsageReport, ClientError> {
+    let abs_url = get_abs_url(url, "/api/v1/usage")?;
+
+    Ok(get_client()
+        .get(abs_url)
+        .send()
+        .await?
+        .json::<StorageUsageReport>()
+        .await?)
+}
+
+/// Same as [`get_status`] but also includes the storage used by artefacts of the workflow.
+pub async fn describe_with_usage(url: &str, id: &str) -> Result<FlowDescription, ClientError> {
+    Ok(FlowDescription {
+        record: get_status(url, id).await?,
+        storage_usage: get_storage_usage(url, id).await?,
+    })
+}
+
+/// Get the task dependency graph of a workflow in Graphviz DOT format. Tasks are colored by their current status,
+/// the output can be rendered using something like `dot -Tpng`.
+pub async fn get_dot(url: &str, id: &str) -> Result<FlowDot, ClientError> {
+    let record = get_status(url, id).await?;
+
+    let tasks: Vec<Task> = serde_json::from_value(record.task_definitions)
+        .map_err(ClientError::InvalidTaskDefinitions)?;
+
+    let mut task_status = BTreeMap::new();
+
+    for (task_ids, status) in [
+        (record.running_tasks, TaskStatus::Running),
+        (record.finished_tasks, TaskStatus::Finished),
+        (record.failed_tasks, TaskStatus::Failed),
+        (record.skipped_tasks, TaskStatus::Skipped),
+    ] {
+        for task_id in task_ids {
+            task_status.insert(task_id as usize, status.clone());
+        }
+    }
+
+    Ok(FlowDot {
+        dot: flow_to_dot(&record.flow_name, &tasks, &task_status),
+    })
+}
+
+/// Compare tasks of two workflows, like a workflow and a rerun of it with small edits. Lists tasks that were added or
+/// removed and fields like the image, command or dependencies of tasks that changed.
+pub async fn diff_workflows(url: &str, id1: &str, id2: &str) -> Result<FlowDiff, ClientError> {
+    let old: Vec<Task> = serde_json::from_value(get_status(url, id1).await?.task_definitions)
+        .map_err(ClientError::InvalidTaskDefinitions)?;
+
+    let new: Vec<Task> = serde_json::from_value(get_status(url, id2).await?.task_definitions)
+        .map_err(ClientError::InvalidTaskDefinitions)?;
+
+    Ok(FlowDiff {
+        changes: diff_tasks(&old, &new),
+    })
 }
 
 async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ClientError> {
@@ -149,11 +378,42 @@ async fn check_status_take(response: reqwest::Response) -> Result<Okay, ClientEr
     Ok(Okay())
 }
 
+/// List flows registered to run on a cron schedule and the time of their next run.
+pub async fn list_schedules(url: &str) -> Result<ScheduleList, ClientError> {
+    let abs_url = get_abs_url(url, "api/v1/schedule")?;
+
+    Ok(ScheduleList {
+        list: check_status(get_client().get(abs_url).send().await?)
+            .await?
+            .json::<Vec<FlowTemplateRecord>>()
+            .await?,
+    })
+}
+
+/// Unregister a flow from running on a cron schedule, runs of it that were already created are kept.
+pub async fn delete_schedule(url: &str, id: &str) -> Result<Okay, ClientError> {
+    let abs_url = get_abs_url(url, &format!("api/v1/schedule/{}", id))?;
+
+    check_status_take(get_client().delete(abs_url).send().await?).await
+}
+
+/// List keys of secrets in the server along with when they were created and last changed.
+pub async fn list_secrets(url: &str) -> Result<SecretList, ClientError> {
+    let abs_url = get_abs_url(url, "api/v1/secret")?;
+
+    Ok(SecretList {
+        list: check_status(get_client().get(abs_url).send().await?)
+            .await?
+            .json::<Vec<SecretRecord>>()
+            .await?,
+    })
+}
+
 /// Create a secret in the server.
 pub async fn create_secret(url: &str, key: &str, value: &str) -> Result<Okay, ClientError> {
     let abs_url = get_abs_url(url, &format!("api/v1/secret/{}", key))?;
 
-    let client = reqwest::Client::new();
+    let client = get_client();
 
     check_status_take(client.post(abs_url).json::<str>(value).send().await?).await
 }
@@ -162,7 +422,7 @@ pub async fn create_secret(url: &str, key: &str, value: &str) -> Result<Okay, Cl
 pub async fn update_secret(url: &str, key: &str, value: &str) -> Result<Okay, ClientError> {
     let abs_url = get_abs_url(url, &format!("api/v1/secret/{}", key))?;
 
-    let client = reqwest::Client::new();
+    let client = get_client();
 
     check_status_take(client.put(abs_url).json::<str>(value).send().await?).await
 }
@@ -171,7 +431,7 @@ pub async fn update_secret(url: &str, key: &str, value: &str) -> Result<Okay, Cl
 pub async fn delete_secret(url: &str, key: &str) -> Result<Okay, ClientError> {
     let abs_url = get_abs_url(url, &format!("api/v1/secret/{}", key))?;
 
-    let client = reqwest::Client::new();
+    let client = get_client();
 
     check_status_take(client.delete(abs_url).send().await?).await
 }
@@ -195,28 +455,79 @@ fn get_path_from_response_url(
 pub async fn download_artefact(url: &str, id: &str, name: &str) -> Result<Response, ClientError> {
     let abs_url = get_abs_url(url, &format!("/api/v1/artefact/{}/{}", id, name))?;
 
-    let response = reqwest::get(abs_url).await?;
+    let response = get_client().get(abs_url).send().await?;
 
     check_status(response).await
 }
 
+/// Check if an output (artefact) of a workflow exists, without downloading it.
+pub async fn artefact_exists(url: &str, id: &str, name: &str) -> Result<bool, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/artefact/{}/{}", id, name))?;
+
+    let client = get_client();
+
+    let response = client.head(abs_url).send().await?;
+
+    match response.status().as_u16() {
+        200 => Ok(true),
+        404 => Ok(false),
+        status => Err(ClientError::ResponseNotOk(status, String::new())),
+    }
+}
+
+/// Removes a partially written file when dropped, unless it has been marked as complete.
+struct PartialFile {
+    path: PathBuf,
+    complete: bool,
+}
+
+impl PartialFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            complete: false,
+        }
+    }
+
+    fn mark_complete(mut self) {
+        self.complete = true;
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if !self.complete {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
 /// Download artefact output of a task in a workflow and save it to a directory path.
 /// Here `name` is the name of the output as defined in the flow definition and `dest` is path to a directory.
+/// The artefact is written to the file as it arrives. If the download fails or the returned future is dropped
+/// before it completes (for example when the user cancels with Ctrl-C), the partially written file is removed.
 pub async fn download_artefact_to_path(
     url: &str,
     id: &str,
     name: &str,
     dest: &str,
 ) -> Result<BytesDownloaded, ClientError> {
-    let response = download_artefact(url, id, name).await?;
+    let mut response = download_artefact(url, id, name).await?;
 
     let file_path = get_path_from_response_url(&response, dest, &format!("flow-{}-output", id));
 
-    let content = response.text().await?;
+    let mut file = tokio::fs::File::create(&file_path).await?;
+    let partial_file = PartialFile::new(file_path);
 
-    let mut file = File::create(file_path)?;
+    let mut num_bytes: u64 = 0;
 
-    let num_bytes = std::io::copy(&mut content.as_bytes(), &mut file)?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        num_bytes += chunk.len() as u64;
+    }
+
+    file.flush().await?;
+    partial_file.mark_complete();
 
     Ok(BytesDownloaded { num_bytes })
 }
@@ -229,18 +540,28 @@ fn get_ws_scheme(secure: bool) -> &'static str {
     "ws"
 }
 
-/// Subscribe to scheduler events on the server.
-pub async fn subscribe(
+async fn subscribe_sequenced(
     url: &str,
     secure: bool,
-) -> Result<impl StreamExt<Item = Result<SchedulerEvent, ClientWebsocketError>>, ClientError> {
+    query: &[(&str, String)],
+) -> Result<impl StreamExt<Item = Result<SequencedEvent, ClientWebsocketError>>, ClientError> {
     let mut abs_url = get_abs_url(url, "/api/v1/scheduler/ws")?;
 
     if abs_url.set_scheme(get_ws_scheme(secure)).is_err() {
         return Err(ClientError::UrlSchemeConversion);
     };
 
-    let (ws_stream, _) = tokio_tungstenite::connect_async(abs_url.to_string()).await?;
+    if !query.is_empty() {
+        abs_url.query_pairs_mut().extend_pairs(query);
+    }
+
+    let mut request = abs_url.as_str().into_client_request()?;
+
+    if let Some(header) = AUTH_HEADER.get() {
+        request.headers_mut().insert(AUTHORIZATION, header.clone());
+    }
+
+    let (ws_stream, _) = tokio_tungstenite::connect_async(request).await?;
 
     fn text_only(msg: &Result<Message, tungstenite::Error>) -> bool {
         match msg {
@@ -251,7 +572,7 @@ pub async fn subscribe(
 
     fn deserialize_msg(
         msg: Result<Message, tungstenite::Error>,
-    ) -> Result<SchedulerEvent, ClientWebsocketError> {
+    ) -> Result<SequencedEvent, ClientWebsocketError> {
         match msg {
             Err(error) => Err(ClientWebsocketError::Websocket(error)),
             Ok(msg) => match serde_json::from_str::<SchedulerEventResult>(&msg.to_string()) {
@@ -269,11 +590,394 @@ pub async fn subscribe(
     Ok(output_stream)
 }
 
-/// Submit a workflow to the server.
-pub async fn submit(url: &str, flow: &Flow) -> Result<Okay, ClientError> {
+fn flow_id_query(flow_id: Option<i32>) -> Vec<(&'static str, String)> {
+    flow_id
+        .map(|flow_id| ("flow_id", flow_id.to_string()))
+        .into_iter()
+        .collect()
+}
+
+/// Subscribe to scheduler events on the server. If `flow_id` is specified, the server only sends events of that flow.
+pub async fn subscribe(
+    url: &str,
+    secure: bool,
+    flow_id: Option<i32>,
+) -> Result<impl StreamExt<Item = Result<SchedulerEvent, ClientWebsocketError>>, ClientError> {
+    let stream = subscribe_sequenced(url, secure, &flow_id_query(flow_id)).await?;
+
+    Ok(stream.map(|event| event.map(|event| event.event)))
+}
+
+/// Subscribe to scheduler events of a single flow on the server. If `since_seq` is specified, the server first
+/// replays events of the flow with sequence number greater than it from its event history, so that a client
+/// reconnecting with the sequence number of the last event it received does not miss events in between. The server
+/// only holds a limited number of recent events of each flow, see [`crate::scheduler::Scheduler::replay_flow_events`].
+pub async fn subscribe_to_flow(
+    url: &str,
+    secure: bool,
+    flow_id: i32,
+    since_seq: Option<u64>,
+) -> Result<impl StreamExt<Item = Result<SequencedEvent, ClientWebsocketError>>, ClientError> {
+    let mut query = vec![("flow_id", flow_id.to_string())];
+
+    if let Some(since_seq) = since_seq {
+        query.push(("since_flow_event", since_seq.to_string()));
+    }
+
+    subscribe_sequenced(url, secure, &query).await
+}
+
+/// Point in the server's scheduler event history to start replaying events from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EventsSince {
+    /// Replay events with sequence number greater than this.
+    Seq(u64),
+    /// Replay events emitted within this duration.
+    Duration(Duration),
+}
+
+/// Replay recent scheduler events from the server's event history, only events of the flow with id `flow_id`
+/// if it is specified. The server only holds a limited number of recent events in memory, see
+/// [`crate::scheduler::Scheduler::replay_events`].
+pub async fn replay_events(
+    url: &str,
+    since: &EventsSince,
+    flow_id: Option<i32>,
+) -> Result<Vec<SequencedEvent>, ClientError> {
+    let abs_url = get_abs_url(url, "/api/v1/scheduler/events")?;
+
+    let mut query = flow_id_query(flow_id);
+
+    query.push(match since {
+        EventsSince::Seq(seq) => ("since_seq", seq.to_string()),
+        EventsSince::Duration(duration) => ("since_seconds", duration.as_secs().to_string()),
+    });
+
+    let client = get_client();
+
+    let response = check_status(client.get(abs_url).query(&query).send().await?).await?;
+
+    Ok(response.json::<Vec<SequencedEvent>>().await?)
+}
+
+/// Subscribe to scheduler events on the server, starting with events from the server's event history since `since`
+/// and then seamlessly continuing with live events. Events present in both the history and the live stream are
+/// only yielded once. If `flow_id` is specified, only events of that flow are yielded.
+pub async fn subscribe_since(
+    url: &str,
+    secure: bool,
+    flow_id: Option<i32>,
+    since: &EventsSince,
+) -> Result<impl StreamExt<Item = Result<SchedulerEvent, ClientWebsocketError>>, ClientError> {
+    // Subscribe before replaying so that no events are missed in between
+    let live_stream = subscribe_sequenced(url, secure, &flow_id_query(flow_id)).await?;
+    let replayed = replay_events(url, since, flow_id).await?;
+
+    let last_replayed_seq = replayed.last().map(|event| event.seq);
+
+    let live_stream = live_stream.filter(move |event| match (event, last_replayed_seq) {
+        (Ok(event), Some(last_replayed_seq)) => event.seq > last_replayed_seq,
+        _ => true,
+    });
+
+    let stream = tokio_stream::iter(replayed.into_iter().map(Ok))
+        .chain(live_stream)
+        .map(|event| event.map(|event| event.event));
+
+    Ok(stream)
+}
+
+/// Interval between checks of the status of a workflow in [`wait_for_completion`], in case an event was missed.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn is_terminal(status: &FlowStatus) -> bool {
+    matches!(
+        status,
+        FlowStatus::Success | FlowStatus::Failed | FlowStatus::Aborted
+    )
+}
+
+async fn wait_for_terminal_status(
+    url: &str,
+    id: &str,
+    secure: bool,
+) -> Result<FlowStatus, ClientError> {
+    // Subscribe before checking the status so that updates in between are not missed
+    let stream = subscribe(url, secure, None).await?;
+    tokio::pin!(stream);
+
+    let mut events_live = true;
+
+    loop {
+        let status = get_status(url, id).await?.status;
+
+        if is_terminal(&status) {
+            return Ok(status);
+        }
+
+        if !events_live {
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+            continue;
+        }
+
+        let poll = tokio::time::sleep(WAIT_POLL_INTERVAL);
+        tokio::pin!(poll);
+
+        loop {
+            tokio::select! {
+                event = stream.next() => match event {
+                    Some(Ok(event)) if event.flow_id().to_string() != id => continue,
+                    Some(Ok(_)) => break,
+                    // Events have been missed or the connection was closed, fall back to polling the status
+                    Some(Err(_)) | None => {
+                        events_live = false;
+                        break;
+                    }
+                },
+                _ = &mut poll => break,
+            }
+        }
+    }
+}
+
+/// Wait until a workflow has succeeded, failed or was aborted and return its final status. The status is checked
+/// whenever one of the workflow's tasks is updated, as received from [`subscribe`], and every few seconds in case
+/// an update was missed. Fails with [`ClientError::WaitTimeout`] if the workflow has not terminated within `timeout`.
+pub async fn wait_for_completion(
+    url: &str,
+    id: &str,
+    secure: bool,
+    timeout: Duration,
+) -> Result<FlowStatus, ClientError> {
+    match tokio::time::timeout(timeout, wait_for_terminal_status(url, id, secure)).await {
+        Ok(result) => result,
+        Err(_) => Err(ClientError::WaitTimeout(timeout)),
+    }
+}
+
+/// Submit a workflow to the server. If an idempotency key is given, submitting again with the same key does not
+/// create another workflow and returns ID of the workflow created first. The key is ignored for recurring workflows.
+pub async fn submit(
+    url: &str,
+    flow: &Flow,
+    idempotency_key: Option<&str>,
+) -> Result<Okay, ClientError> {
     let abs_url = get_abs_url(url, "/api/v1/job")?;
 
-    let client = reqwest::Client::new();
+    let client = get_client();
 
-    check_status_take(client.post(abs_url).json(flow).send().await?).await
+    let mut request = client.post(abs_url).json(flow);
+
+    if let Some(idempotency_key) = idempotency_key {
+        request = request.header("Idempotency-Key", idempotency_key);
+    }
+
+    check_status_take(request.send().await?).await
+}
+
+/// Validate a workflow without submitting it and get the stages its tasks would be run in, see [`Plan`].
+pub async fn validate_workflow(url: &str, flow: &Flow) -> Result<FlowStages, ClientError> {
+    let plan = get_validated_plan(url, flow).await?;
+
+    Ok(FlowStages {
+        stages: plan
+            .0
+            .iter()
+            .map(|stage| {
+                stage
+                    .iter()
+                    .filter_map(|&task_id| flow.tasks.get(task_id))
+                    .map(|task| task.name.clone())
+                    .collect()
+            })
+            .collect(),
+    })
+}
+
+/// Same as [`validate_workflow`] but also lists which tasks each task depends on, to explain why tasks are grouped
+/// into stages. See [`Plan::explain`].
+pub async fn explain_workflow(url: &str, flow: &Flow) -> Result<FlowPlanExplanation, ClientError> {
+    let plan = get_validated_plan(url, flow).await?;
+
+    Ok(FlowPlanExplanation {
+        stages: plan.explain(&flow.tasks),
+    })
+}
+
+async fn get_validated_plan(url: &str, flow: &Flow) -> Result<Plan, ClientError> {
+    let abs_url = get_abs_url(url, "/api/v1/job/validate")?;
+
+    let client = get_client();
+
+    let response = check_status(client.post(abs_url).json(flow).send().await?).await?;
+
+    Ok(response.json::<Plan>().await?)
+}
+
+/// Project how workflows would be scheduled if they were submitted together, like the peak number of
+/// concurrently running tasks, without submitting them. See [`crate::simulation::simulate_flows`].
+pub async fn simulate(url: &str, flows: &[Flow]) -> Result<ScheduleProjection, ClientError> {
+    let abs_url = get_abs_url(url, "/api/v1/simulate")?;
+
+    let client = get_client();
+
+    let response = check_status(client.post(abs_url).json(flows).send().await?).await?;
+
+    Ok(response.json::<ScheduleProjection>().await?)
+}
+
+/// Get combined logs of all tasks of a workflow as plain text, each line prefixed with the task's name.
+/// If `follow` is set, logs of running tasks are streamed until they terminate.
+/// The logs can be read as they arrive using [`reqwest::Response::chunk`].
+pub async fn get_logs(url: &str, id: &str, follow: bool) -> Result<Response, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}/logs?follow={}", id, follow))?;
+
+    let response = get_client().get(abs_url).send().await?;
+
+    check_status(response).await
+}
+
+/// Fetch logs of a single task of a workflow, streamed until the task terminates if `follow` is set.
+pub async fn fetch_logs(
+    url: &str,
+    id: &str,
+    task_id: &str,
+    follow: bool,
+) -> Result<Response, ClientError> {
+    let abs_url = get_abs_url(
+        url,
+        &format!("/api/v1/job/{}/task/{}/logs?follow={}", id, task_id, follow),
+    )?;
+
+    let response = get_client().get(abs_url).send().await?;
+
+    check_status(response).await
+}
+
+/// Submit a new workflow from the definition of an existing workflow, with environment variables overridden.
+pub async fn rerun(url: &str, id: &str, overrides: &EnvOverrides) -> Result<Okay, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}/rerun", id))?;
+
+    let client = get_client();
+
+    check_status_take(client.post(abs_url).json(overrides).send().await?).await
+}
+
+/// Submit a copy of an existing workflow as a new workflow without needing its original definition file. Unlike
+/// [`rerun`], the definition is fetched and submitted by the client. Inline secrets and the interval or schedule of
+/// recurring workflows are not copied, see [`FlowRecord::into_flow`].
+pub async fn resubmit(url: &str, id: &str) -> Result<Okay, ClientError> {
+    let flow = get_status(url, id)
+        .await?
+        .into_flow()
+        .map_err(ClientError::InvalidTaskDefinitions)?;
+
+    submit(url, &flow, None).await
+}
+
+/// Retry a failed workflow, only tasks that failed or did not run are run again.
+pub async fn retry_workflow(url: &str, id: &str) -> Result<Okay, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}/retry", id))?;
+
+    let client = get_client();
+
+    check_status_take(client.post(abs_url).send().await?).await
+}
+
+/// Abort a running or pending workflow, its tasks are stopped and no more tasks are run.
+pub async fn cancel_workflow(url: &str, id: &str) -> Result<Okay, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}", id))?;
+
+    let client = get_client();
+
+    check_status_take(client.delete(abs_url).send().await?).await
+}
+
+/// Delete a workflow that has terminated along with its artefacts.
+pub async fn purge_workflow(url: &str, id: &str) -> Result<Okay, ClientError> {
+    let abs_url = get_abs_url(url, &format!("/api/v1/job/{}?purge=true", id))?;
+
+    let client = get_client();
+
+    check_status_take(client.delete(abs_url).send().await?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_interrupted_download_removes_partial_file() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = [0; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1048576\r\n\r\npartial")
+                .await
+                .unwrap();
+
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+
+        let dest = std::env::temp_dir().join("flowmium-test-interrupted-download");
+        std::fs::create_dir_all(&dest).unwrap();
+        let file_path = dest.join("some-output");
+
+        let dest_str = dest.to_str().unwrap().to_owned();
+        let handle = tokio::spawn(async move {
+            download_artefact_to_path(&url, "1", "some-output", &dest_str).await
+        });
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(file_path.exists());
+
+        handle.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+
+        assert!(!file_path.exists());
+    }
+
+    async fn serve_once(response: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = [0; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+
+            assert!(request.starts_with(b"HEAD /api/v1/artefact/1/some-output "));
+
+            socket.write_all(response).await.unwrap();
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_artefact_exists() {
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 1048576\r\n\r\n").await;
+        assert!(artefact_exists(&url, "1", "some-output").await.unwrap());
+
+        let url = serve_once(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(!artefact_exists(&url, "1", "some-output").await.unwrap());
+
+        let url =
+            serve_once(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(matches!(
+            artefact_exists(&url, "1", "some-output").await,
+            Err(ClientError::ResponseNotOk(500, _))
+        ));
+    }
 }
@@ -36,37 +36,35 @@
 //!         .await
 //!         .unwrap();
 //!
-//!     let handle = driver::spawn_executor(&pool, &scheduler, &executor_config);
+//!     // Send `true` through `shutdown_tx` to stop the executor after its current scheduling pass
+//!     let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+//!     let handle = driver::spawn_executor(&pool, &scheduler, &executor_config, shutdown_rx);
 //!
 //!     let flow = create_example_flow();
-//!     executor::instantiate_flow(flow, &scheduler).await.unwrap();
+//!     executor::instantiate_flow(flow, &scheduler, &secrets, &executor_config)
+//!         .await
+//!         .unwrap();
+//!
+//!     tokio::signal::ctrl_c().await.unwrap();
+//!     shutdown_tx.send_replace(true);
 //!
 //!     handle.await.unwrap();
 //! }
 //!
 //! fn create_example_flow() -> Flow {
-//!     Flow {
-//!         name: "hello-world".to_string(),
-//!         tasks: vec![Task {
-//!             name: "hello-world".to_string(),
-//!             image: "debian:latest".to_string(),
-//!             depends: vec![],
-//!             cmd: vec![
-//!                 "sh".to_string(),
-//!                 "-c".to_string(),
-//!                 "echo $MESSAGE".to_string(),
-//!             ],
-//!             env: vec![EnvVar::SecretRef(SecretRef {
-//!                 name: "MESSAGE".to_string(),
-//!                 from_secret: "super-secret-message".to_string(),
-//!             })],
-//!             inputs: None,
-//!             outputs: None,
-//!         }],
-//!     }
+//!     FlowBuilder::new("hello-world")
+//!         .task(
+//!             TaskBuilder::new("hello-world", "debian:latest")
+//!                 .cmd(["sh", "-c", "echo $MESSAGE"])
+//!                 .secret_env("MESSAGE", "super-secret-message"),
+//!         )
+//!         .build()
 //! }
 //! ```
 //!
+//! Flows can also be constructed by filling out the fields of [`model::Flow`] and [`model::Task`] directly,
+//! see [`model::FlowBuilder`] for the equivalent of a flow built with the builders.
+//!
 //! To run the example, you will have to run something like below
 //!
 //! ```shell
@@ -89,11 +87,17 @@ mod task;
 
 pub use client::driver as driver_client;
 pub use client::requests;
+pub use server::api;
 pub use server::driver;
 pub use server::event;
 pub use server::executor;
+pub use server::logs;
 pub use server::model;
 pub use server::planner;
+pub use server::reaper;
 pub use server::record;
 pub use server::scheduler;
 pub use server::secrets;
+pub use server::simulation;
+pub use server::sink;
+pub use task::layout;
@@ -1,14 +1,31 @@
 use std::{future::Future, time::Duration};
 
+use rand::Rng;
+
+/// Backoff in milliseconds before the first retry, the backoff is doubled on every further retry.
+const INITIAL_BACKOFF_MS: u64 = 1000;
+
+/// Time to sleep before a retry. With `jitter` it is a random duration between zero and the backoff, so that many
+/// replicas retrying an unavailable dependency at the same time spread out their retries instead of retrying together.
+fn get_sleep_duration(backoff_ms: u64, jitter: bool) -> Duration {
+    match jitter {
+        true => Duration::from_millis(rand::thread_rng().gen_range(0..=backoff_ms)),
+        false => Duration::from_millis(backoff_ms),
+    }
+}
+
+/// Retry `operation` until it returns `Some`, at most `max_retry_count` times, with exponential backoff between tries.
+/// The backoff is randomized if `jitter` is set, see [`get_sleep_duration`].
 pub(crate) async fn with_exp_backoff_retry<T, F>(
     operation: impl Fn() -> F,
     retry_message: &'static str,
     max_retry_count: i32,
+    jitter: bool,
 ) -> Option<T>
 where
     F: Future<Output = Option<T>>,
 {
-    let mut backoff_counter = 500;
+    let mut backoff_ms = INITIAL_BACKOFF_MS / 2;
     let mut retry_count = 0;
 
     loop {
@@ -16,21 +33,94 @@ where
             Some(some_val) => break Some(some_val),
             None => {
                 retry_count += 1;
-                backoff_counter *= 2;
+                backoff_ms *= 2;
 
                 match retry_count > max_retry_count {
                     true => break None,
                     false => {
+                        let sleep_duration = get_sleep_duration(backoff_ms, jitter);
+
                         tracing::info!(
                             "{} retrying with backoff for {} milliseconds",
                             retry_message,
-                            backoff_counter
+                            sleep_duration.as_millis()
                         );
 
-                        tokio::time::sleep(Duration::from_millis(backoff_counter)).await
+                        tokio::time::sleep(sleep_duration).await
                     }
                 }
             }
         };
     }
 }
+
+/// Same as [`with_exp_backoff_retry`] but for fallible operations, only errors for which `is_transient`
+/// returns `true` are retried, other errors are returned immediately.
+pub(crate) async fn with_exp_backoff_retry_if<T, E, F>(
+    operation: impl Fn() -> F,
+    is_transient: impl Fn(&E) -> bool,
+    retry_message: &'static str,
+    max_retry_count: i32,
+    jitter: bool,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let mut backoff_ms = INITIAL_BACKOFF_MS / 2;
+    let mut retry_count = 0;
+
+    loop {
+        match operation().await {
+            Ok(val) => break Ok(val),
+            Err(error) => {
+                retry_count += 1;
+                backoff_ms *= 2;
+
+                match retry_count > max_retry_count || !is_transient(&error) {
+                    true => break Err(error),
+                    false => {
+                        let sleep_duration = get_sleep_duration(backoff_ms, jitter);
+
+                        tracing::info!(
+                            "{} retrying with backoff for {} milliseconds",
+                            retry_message,
+                            sleep_duration.as_millis()
+                        );
+
+                        tokio::time::sleep(sleep_duration).await
+                    }
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_sleep_duration() {
+        let mut backoff_ms = INITIAL_BACKOFF_MS;
+
+        for _ in 0..8 {
+            assert_eq!(
+                get_sleep_duration(backoff_ms, false),
+                Duration::from_millis(backoff_ms)
+            );
+
+            let sleep_durations: Vec<Duration> = (0..50)
+                .map(|_| get_sleep_duration(backoff_ms, true))
+                .collect();
+
+            assert!(sleep_durations
+                .iter()
+                .all(|duration| *duration <= Duration::from_millis(backoff_ms)));
+            assert!(sleep_durations
+                .iter()
+                .any(|duration| *duration != sleep_durations[0]));
+
+            backoff_ms *= 2;
+        }
+    }
+}
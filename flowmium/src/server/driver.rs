@@ -1,17 +1,24 @@
 use s3::Bucket;
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
-use std::{process::ExitCode, time::Duration};
-use tokio::task::JoinHandle;
+use std::{path::Path, process::ExitCode, time::Duration};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{level_filters::LevelFilter, Subscriber};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, EnvFilter};
 
 use crate::server::{
-    api::start_server,
+    api::{start_server, ApiConfig},
     args,
-    executor::{schedule_and_run_tasks, ExecutorConfig},
+    executor::{
+        reconcile_running_tasks, run_recurring_flows, schedule_and_run_tasks, ExecutorConfig,
+    },
+    reaper::{sweep_expired_flows, ReaperConfig},
     scheduler::Scheduler,
+    sink::{spawn_event_sink, EventSinkConfig},
 };
 use crate::{
     retry::with_exp_backoff_retry,
-    server::secrets::SecretsCrud,
+    server::secrets::{SecretsConfig, SecretsCrud},
     task::{
         bucket::get_bucket,
         driver::{run_task, SidecarConfig},
@@ -20,7 +27,42 @@ use crate::{
 };
 
 use super::args::TaskOpts;
-use super::pool::{init_db_and_get_pool, PostgresConfig};
+use super::pool::{get_read_replica_pool, init_db_and_get_pool, PostgresConfig};
+
+/// Format of the log lines written by `flowmium`.
+#[derive(Debug, PartialEq, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for collecting logs with a log aggregator.
+    Json,
+}
+
+/// Configuration for logging. Levels are filtered with the `RUST_LOG` environment variable, default is `info`.
+#[derive(Debug, PartialEq, Deserialize, Clone, Default)]
+pub struct LogConfig {
+    /// Format of the log lines, `pretty` or `json`. Default is `pretty`.
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+fn get_subscriber(
+    config: &LogConfig,
+    env_filter: EnvFilter,
+    writer: BoxMakeWriter,
+) -> Box<dyn Subscriber + Send + Sync> {
+    let builder = tracing_subscriber::fmt()
+        .with_line_number(true)
+        .with_env_filter(env_filter)
+        .with_writer(writer);
+
+    match config.log_format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
 
 /// Create a postgres connection pool object, create a table to store secrets and flow statuses and perform migration.
 /// The tables are `flows` and `secrets` respectively. An environment variable named `FLOWMIUM_POSTGRES_URL` with value as an
@@ -37,6 +79,21 @@ pub async fn get_default_postgres_pool() -> Option<Pool<Postgres>> {
     init_db_and_get_pool(database_config).await
 }
 
+/// Create a postgres connection pool object to a read replica used for listing and describing flows, see
+/// [`crate::scheduler::Scheduler::with_read_replica`]. Returns `Some(None)` if the environment variable
+/// `FLOWMIUM_POSTGRES_READ_REPLICA_URL` is not set and `None` if unable to connect to the replica.
+pub async fn get_default_postgres_read_replica_pool() -> Option<Option<Pool<Postgres>>> {
+    let database_config: PostgresConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            tracing::error!(%error, "Invalid env config for database");
+            return None;
+        }
+    };
+
+    get_read_replica_pool(database_config).await
+}
+
 /// Constructor executor config from environment variables. Environment variables that are expected to be set
 /// are fields of [`crate::executor::ExecutorConfig`] but in all caps prefixed with `FLOWMIUM_`.
 pub async fn get_default_executor_config() -> Option<ExecutorConfig> {
@@ -51,6 +108,138 @@ pub async fn get_default_executor_config() -> Option<ExecutorConfig> {
     Some(executor_config)
 }
 
+/// Construct API server config from environment variables. Environment variables that are expected to be set
+/// are fields of [`crate::api::ApiConfig`] but in all caps prefixed with `FLOWMIUM_`.
+pub async fn get_default_api_config() -> Option<ApiConfig> {
+    let api_config: ApiConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            tracing::error!(%error, "Invalid env config for API server");
+            return None;
+        }
+    };
+
+    Some(api_config)
+}
+
+/// Construct event sink config from environment variables. Environment variables that are expected to be set
+/// are fields of [`crate::sink::EventSinkConfig`] but in all caps prefixed with `FLOWMIUM_`.
+pub async fn get_default_event_sink_config() -> Option<EventSinkConfig> {
+    let event_sink_config: EventSinkConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            tracing::error!(%error, "Invalid env config for event sink");
+            return None;
+        }
+    };
+
+    Some(event_sink_config)
+}
+
+/// Construct artefact reaper config from environment variables. Environment variables that are expected to be set
+/// are fields of [`crate::reaper::ReaperConfig`] but in all caps prefixed with `FLOWMIUM_`.
+pub async fn get_default_reaper_config() -> Option<ReaperConfig> {
+    let reaper_config: ReaperConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            tracing::error!(%error, "Invalid env config for artefact reaper");
+            return None;
+        }
+    };
+
+    if !reaper_config.is_artefact_ttl_valid() {
+        tracing::error!(
+            artefact_ttl_days = reaper_config.artefact_ttl_days,
+            "Invalid env config for artefact reaper, artefact TTL is too long"
+        );
+        return None;
+    }
+
+    Some(reaper_config)
+}
+
+/// Construct secrets config from environment variables. Environment variables that are expected to be set
+/// are fields of [`crate::secrets::SecretsConfig`] but in all caps prefixed with `FLOWMIUM_`.
+pub async fn get_default_secrets_config() -> Option<SecretsConfig> {
+    let secrets_config: SecretsConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            tracing::error!(%error, "Invalid env config for secrets");
+            return None;
+        }
+    };
+
+    Some(secrets_config)
+}
+
+/// Create or update secrets from files in `secrets_dir`, the name of each file is the key of the secret and its
+/// contents are the value with a trailing newline removed. Hidden entries are skipped, like the `..data` directory
+/// of a Kubernetes secret mounted as a volume. Returns keys of the secrets that were created or updated.
+pub async fn bootstrap_secrets(secrets: &SecretsCrud, secrets_dir: &Path) -> Option<Vec<String>> {
+    let mut entries = match tokio::fs::read_dir(secrets_dir).await {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::error!(%error, ?secrets_dir, "Unable to read secrets directory");
+            return None;
+        }
+    };
+
+    let mut keys = vec![];
+
+    loop {
+        let entry = match entries.next_entry().await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(error) => {
+                tracing::error!(%error, ?secrets_dir, "Unable to read secrets directory");
+                return None;
+            }
+        };
+
+        let Ok(key) = entry.file_name().into_string() else {
+            tracing::warn!(path = ?entry.path(), "Skipping secret file with invalid name");
+            continue;
+        };
+
+        if key.starts_with('.') {
+            continue;
+        }
+
+        // Follows symlinks, files of a mounted Kubernetes secret are symlinks into its `..data` directory
+        match tokio::fs::metadata(entry.path()).await {
+            Ok(metadata) if metadata.is_file() => (),
+            Ok(_) => continue,
+            Err(error) => {
+                tracing::error!(%error, key, "Unable to read secret file");
+                return None;
+            }
+        }
+
+        let value = match tokio::fs::read_to_string(entry.path()).await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(%error, key, "Unable to read secret file");
+                return None;
+            }
+        };
+
+        let value = value
+            .strip_suffix('\n')
+            .map(|value| value.strip_suffix('\r').unwrap_or(value))
+            .unwrap_or(&value);
+
+        if secrets.upsert_secret(&key, value).await.is_err() {
+            return None;
+        }
+
+        keys.push(key);
+    }
+
+    keys.sort();
+
+    Some(keys)
+}
+
 async fn get_bucket_from_executor_config(
     executor_config: &ExecutorConfig,
 ) -> Result<Box<Bucket>, ArtefactError> {
@@ -59,16 +248,49 @@ async fn get_bucket_from_executor_config(
         &executor_config.secret_key,
         &executor_config.bucket_name,
         executor_config.store_url.clone(),
+        executor_config.bucket_region.as_deref(),
+        executor_config.bucket_path_style,
     )
     .await
 }
 
-/// Spawn a tokio task that periodically calls [`crate::executor::schedule_and_run_tasks`] every second
-/// and makes progress on pending flows.
+/// Wait for `interval` unless shutdown is signalled through `shutdown` before that. Returns `true` if shutdown was
+/// signalled or the sender of `shutdown` was dropped.
+async fn sleep_or_shutdown(interval: Duration, shutdown: &mut watch::Receiver<bool>) -> bool {
+    tokio::time::timeout(interval, shutdown.wait_for(|shutdown| *shutdown))
+        .await
+        .is_ok()
+}
+
+/// Wait for Ctrl-C or `SIGTERM`, which Kubernetes sends to the server's pod before stopping it.
+async fn wait_for_shutdown_signal() {
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                tracing::error!(%error, "Unable to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => (),
+        _ = terminate => (),
+    }
+}
+
+/// Spawn a tokio task that periodically calls [`crate::executor::schedule_and_run_tasks`] every
+/// [`crate::executor::ExecutorConfig::scheduler_interval_ms`] milliseconds and makes progress on pending flows.
+/// Running tasks are first reconciled with their jobs by calling [`crate::executor::reconcile_running_tasks`].
+/// The task finishes once `true` is sent through `shutdown`, after the scheduling pass in progress has completed.
 pub fn spawn_executor(
     pool: &Pool<Postgres>,
     sched: &Scheduler,
     executor_config: &ExecutorConfig,
+    mut shutdown: watch::Receiver<bool>,
 ) -> JoinHandle<()> {
     let pool_loop = pool.clone();
     let sched_loop = sched.clone();
@@ -77,22 +299,113 @@ pub fn spawn_executor(
     tracing::info!("Starting scheduler loop");
 
     tokio::spawn(async move {
-        let secrets = SecretsCrud::new(pool_loop);
+        let secrets = SecretsCrud::new(pool_loop)
+            .with_max_concurrent_fetches(executor_config_loop.max_concurrent_secret_fetches);
+
+        let interval = Duration::from_millis(executor_config_loop.scheduler_interval_ms);
 
-        loop {
-            tokio::time::sleep(Duration::from_millis(1000)).await;
+        reconcile_running_tasks(&sched_loop, &executor_config_loop).await;
+
+        while !sleep_or_shutdown(interval, &mut shutdown).await {
             schedule_and_run_tasks(&sched_loop, &executor_config_loop, &secrets).await;
         }
+
+        tracing::info!("Stopped scheduler loop");
+    })
+}
+
+/// Spawn a tokio task that calls [`crate::executor::run_recurring_flows`] every second and creates runs of
+/// flows registered to run on a cron schedule. The task finishes once `true` is sent through `shutdown`.
+pub fn spawn_recurring_flows(
+    pool: &Pool<Postgres>,
+    sched: &Scheduler,
+    executor_config: &ExecutorConfig,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    let pool_loop = pool.clone();
+    let sched_loop = sched.clone();
+    let executor_config_loop = executor_config.clone();
+
+    tracing::info!("Starting recurring flows loop");
+
+    tokio::spawn(async move {
+        let secrets = SecretsCrud::new(pool_loop);
+
+        while !sleep_or_shutdown(Duration::from_millis(1000), &mut shutdown).await {
+            run_recurring_flows(&sched_loop, &secrets, &executor_config_loop).await;
+        }
+
+        tracing::info!("Stopped recurring flows loop");
     })
 }
 
-/// Run API server. This function does not return unless there is an error.
-#[tracing::instrument(skip(pool, sched, executor_config))]
+/// Spawn a tokio task that calls [`crate::reaper::sweep_expired_flows`] every
+/// [`crate::reaper::ReaperConfig::artefact_sweep_interval_seconds`] seconds and deletes flows and artefacts that have
+/// outlived their retention. Returns `None` without spawning anything if the artefact TTL is not configured.
+/// The task finishes once `true` is sent through `shutdown`.
+pub fn spawn_reaper(
+    sched: &Scheduler,
+    executor_config: &ExecutorConfig,
+    reaper_config: ReaperConfig,
+    mut shutdown: watch::Receiver<bool>,
+) -> Option<JoinHandle<()>> {
+    let artefact_ttl = reaper_config.get_artefact_ttl()?;
+
+    let sched_loop = sched.clone();
+    let executor_config_loop = executor_config.clone();
+
+    tracing::info!("Starting artefact reaper loop");
+
+    Some(tokio::spawn(async move {
+        let Some(bucket) = with_exp_backoff_retry(
+            || async {
+                get_bucket_from_executor_config(&executor_config_loop)
+                    .await
+                    .ok()
+            },
+            "Unable to create or open bucket",
+            8,
+            true,
+        )
+        .await
+        else {
+            tracing::error!("Artefact reaper stopped, unable to open bucket");
+            return;
+        };
+
+        let interval = Duration::from_secs(reaper_config.artefact_sweep_interval_seconds);
+
+        while !sleep_or_shutdown(interval, &mut shutdown).await {
+            match sweep_expired_flows(
+                &sched_loop,
+                &bucket,
+                &executor_config_loop.store_layout,
+                artefact_ttl,
+            )
+            .await
+            {
+                Ok(flow_ids) if !flow_ids.is_empty() => {
+                    tracing::info!(?flow_ids, "Deleted expired flows")
+                }
+                Ok(_) => (),
+                Err(error) => tracing::error!(%error, "Unable to sweep expired flows"),
+            }
+        }
+
+        tracing::info!("Stopped artefact reaper loop");
+    }))
+}
+
+/// Run API server. This function does not return unless there is an error or `true` is sent through `shutdown`,
+/// in which case the server stops accepting connections and returns once in flight requests have completed.
+#[tracing::instrument(skip(pool, sched, executor_config, shutdown))]
 pub async fn run_api_server(
     pool: &Pool<Postgres>,
     sched: &Scheduler,
     executor_config: &ExecutorConfig,
+    api_config: ApiConfig,
     port: u16,
+    shutdown: watch::Receiver<bool>,
 ) -> ExitCode {
     tracing::info!("Starting API server");
 
@@ -100,13 +413,24 @@ pub async fn run_api_server(
         || async { get_bucket_from_executor_config(executor_config).await.ok() },
         "Unable to create or open bucket",
         8,
+        true,
     )
     .await
     else {
         return ExitCode::FAILURE;
     };
 
-    if let Err(error) = start_server(port, pool.clone(), sched, bucket).await {
+    if let Err(error) = start_server(
+        port,
+        pool.clone(),
+        sched,
+        bucket,
+        api_config,
+        executor_config.clone(),
+        shutdown,
+    )
+    .await
+    {
         tracing::error!(%error, "Unable to start server");
         return ExitCode::FAILURE;
     }
@@ -119,6 +443,32 @@ async fn server_main(port: u16) -> ExitCode {
         get_default_postgres_pool,
         "Unable to connect to database",
         8,
+        true,
+    )
+    .await
+    else {
+        return ExitCode::FAILURE;
+    };
+
+    let Some(secrets_config) = get_default_secrets_config().await else {
+        return ExitCode::FAILURE;
+    };
+
+    if let Some(secrets_dir) = &secrets_config.secrets_dir {
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let Some(keys) = bootstrap_secrets(&secrets, secrets_dir).await else {
+            return ExitCode::FAILURE;
+        };
+
+        tracing::info!(?keys, "Created or updated secrets from secrets directory");
+    }
+
+    let Some(read_replica_pool) = with_exp_backoff_retry(
+        get_default_postgres_read_replica_pool,
+        "Unable to connect to read replica database",
+        8,
+        true,
     )
     .await
     else {
@@ -129,11 +479,72 @@ async fn server_main(port: u16) -> ExitCode {
         return ExitCode::FAILURE;
     };
 
-    let sched = Scheduler::new(pool.clone());
+    let Some(event_sink_config) = get_default_event_sink_config().await else {
+        return ExitCode::FAILURE;
+    };
+
+    let Some(api_config) = get_default_api_config().await else {
+        return ExitCode::FAILURE;
+    };
+
+    let Some(reaper_config) = get_default_reaper_config().await else {
+        return ExitCode::FAILURE;
+    };
+
+    let sched = match read_replica_pool {
+        Some(read_replica_pool) => {
+            Scheduler::new(pool.clone()).with_read_replica(read_replica_pool)
+        }
+        None => Scheduler::new(pool.clone()),
+    };
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+    spawn_event_sink(&sched, event_sink_config);
+
+    let loops = [
+        Some(spawn_executor(
+            &pool,
+            &sched,
+            &executor_config,
+            shutdown_rx.clone(),
+        )),
+        Some(spawn_recurring_flows(
+            &pool,
+            &sched,
+            &executor_config,
+            shutdown_rx.clone(),
+        )),
+        spawn_reaper(&sched, &executor_config, reaper_config, shutdown_rx.clone()),
+    ];
+
+    let api_server = run_api_server(
+        &pool,
+        &sched,
+        &executor_config,
+        api_config,
+        port,
+        shutdown_rx,
+    );
+    tokio::pin!(api_server);
+
+    let exit_code = tokio::select! {
+        exit_code = &mut api_server => exit_code,
+        _ = wait_for_shutdown_signal() => {
+            tracing::info!("Shutting down");
+            shutdown_tx.send_replace(true);
+            api_server.await
+        }
+    };
+
+    // Loops are also stopped if the API server has stopped because of an error
+    shutdown_tx.send_replace(true);
 
-    spawn_executor(&pool, &sched, &executor_config);
+    for handle in loops.into_iter().flatten() {
+        let _ = handle.await;
+    }
 
-    run_api_server(&pool, &sched, &executor_config, port).await
+    exit_code
 }
 
 #[tracing::instrument]
@@ -163,7 +574,20 @@ async fn init_main(src: String, dest: String) -> ExitCode {
 
 /// Parse CLI arguments and environment variables and run `flowmium` CLI.
 pub async fn run() -> ExitCode {
-    let subscriber = tracing_subscriber::fmt().with_line_number(true).finish();
+    // Logger is not initialized yet, errors can only be printed
+    let log_config: LogConfig = match envy::prefixed("FLOWMIUM_").from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Invalid env config for logging: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    let subscriber = get_subscriber(&log_config, env_filter, BoxMakeWriter::new(std::io::stdout));
     match tracing::subscriber::set_global_default(subscriber) {
         Ok(()) => (),
         Err(_) => {
@@ -180,3 +604,143 @@ pub async fn run() -> ExitCode {
         args::Command::Server(server_opts) => server_main(server_opts.port).await,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::sync::{Arc, Mutex};
+
+    use crate::server::pool::get_test_pool;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn get_log_lines(log_format: &str, env_filter: &str) -> Vec<String> {
+        let config: LogConfig = envy::prefixed("FLOWMIUM_")
+            .from_iter([("FLOWMIUM_LOG_FORMAT".to_owned(), log_format.to_owned())])
+            .unwrap();
+
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+
+        let subscriber = get_subscriber(
+            &config,
+            EnvFilter::new(env_filter),
+            BoxMakeWriter::new(move || writer.clone()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(flow_id = 7, "Creating flow");
+            tracing::debug!("Scheduling tasks");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        output.lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_get_subscriber() {
+        let config: LogConfig = envy::prefixed("FLOWMIUM_").from_iter(vec![]).unwrap();
+        assert_eq!(config.log_format, LogFormat::Pretty);
+
+        let lines = get_log_lines("pretty", "info");
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("Creating flow"));
+        assert!(serde_json::from_str::<serde_json::Value>(&lines[0]).is_err());
+
+        let lines = get_log_lines("json", "debug");
+        assert_eq!(lines.len(), 2);
+
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "Creating flow");
+        assert_eq!(line["fields"]["flow_id"], 7);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_bootstrap_secrets() {
+        let pool = get_test_pool(&["secrets"]).await;
+        let secrets = SecretsCrud::new(pool);
+
+        secrets.create_secret("api-token", "old").await.unwrap();
+
+        let dir = std::env::temp_dir().join("flowmium-test-bootstrap-secrets");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("..data")).unwrap();
+        std::fs::write(dir.join("api-token"), "new\n").unwrap();
+        std::fs::write(dir.join("db-password"), "hunter2").unwrap();
+        std::fs::write(dir.join("..data/ignored"), "ignored").unwrap();
+
+        let keys = bootstrap_secrets(&secrets, &dir).await.unwrap();
+
+        assert_eq!(keys, vec!["api-token", "db-password"]);
+        assert_eq!(secrets.get_secret("api-token").await.unwrap(), "new");
+        assert_eq!(secrets.get_secret("db-password").await.unwrap(), "hunter2");
+        assert!(!secrets.secret_exists("ignored").await.unwrap());
+        assert!(!secrets.secret_exists("..data").await.unwrap());
+
+        assert!(bootstrap_secrets(&secrets, &dir.join("missing"))
+            .await
+            .is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_shutdown_stops_loops() {
+        let pool = get_test_pool(&["flows"]).await;
+        let sched = Scheduler::new(pool.clone());
+
+        let executor_config: ExecutorConfig = envy::prefixed("FLOWMIUM_")
+            .from_iter(
+                [
+                    ("FLOWMIUM_STORE_URL", "http://localhost:9000"),
+                    ("FLOWMIUM_TASK_STORE_URL", "http://localhost:9000"),
+                    ("FLOWMIUM_BUCKET_NAME", "flowmium-test"),
+                    ("FLOWMIUM_ACCESS_KEY", "minio"),
+                    ("FLOWMIUM_SECRET_KEY", "password"),
+                    ("FLOWMIUM_INIT_CONTAINER_IMAGE", "flowmium-debug"),
+                    ("FLOWMIUM_NAMESPACE", "default"),
+                    ("FLOWMIUM_SCHEDULER_INTERVAL_MS", "10"),
+                ]
+                .map(|(key, value)| (key.to_owned(), value.to_owned())),
+            )
+            .unwrap();
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        let executor = spawn_executor(&pool, &sched, &executor_config, shutdown_rx.clone());
+        let recurring_flows = spawn_recurring_flows(&pool, &sched, &executor_config, shutdown_rx);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(!executor.is_finished());
+        assert!(!recurring_flows.is_finished());
+
+        shutdown_tx.send_replace(true);
+
+        tokio::time::timeout(Duration::from_secs(5), executor)
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), recurring_flows)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -15,17 +15,44 @@ pub enum SchedulerEvent {
     },
     /// A flow was created.
     FlowCreatedEvent { flow_id: i32 },
+    /// A flow was aborted before it terminated.
+    FlowAbortedEvent { flow_id: i32 },
+    /// A failed flow is being retried from its failed tasks.
+    FlowRetriedEvent { flow_id: i32 },
+}
+
+impl SchedulerEvent {
+    /// Id of the flow the event is about.
+    pub fn flow_id(&self) -> i32 {
+        match self {
+            SchedulerEvent::TaskStatusUpdateEvent { flow_id, .. }
+            | SchedulerEvent::FlowCreatedEvent { flow_id }
+            | SchedulerEvent::FlowAbortedEvent { flow_id }
+            | SchedulerEvent::FlowRetriedEvent { flow_id } => *flow_id,
+        }
+    }
+}
+
+/// An event from the scheduler along with its position in the sequence of all events emitted by the scheduler.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SequencedEvent {
+    /// Sequence number of the event, increases by one for every event emitted by the scheduler.
+    /// Events replayed from the scheduler's history and events received live share the same sequence numbers.
+    pub seq: u64,
+    /// The event.
+    #[serde(flatten)]
+    pub event: SchedulerEvent,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum SchedulerEventResult {
-    Event(SchedulerEvent),
+    Event(SequencedEvent),
     Lag(u64),
 }
 
 pub(crate) fn to_event_result(
-    event: Result<SchedulerEvent, BroadcastStreamRecvError>,
+    event: Result<SequencedEvent, BroadcastStreamRecvError>,
 ) -> SchedulerEventResult {
     match event {
         Ok(event) => SchedulerEventResult::Event(event),
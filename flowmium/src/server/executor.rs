@@ -1,18 +1,38 @@
+use super::model::parse_cron_schedule;
+use super::model::EnvOverrides;
 use super::model::EnvVar;
 use super::model::Flow;
+use super::model::InputRef;
+use super::model::K8sSecretRef;
 use super::model::KeyValuePair;
+use super::model::ResourceRequirements;
+use super::model::RunCondition;
 use super::model::SecretRef;
 use super::model::Task;
 use super::planner::construct_plan;
+use super::planner::Plan;
 use super::planner::PlannerError;
+use super::record::FlowStatus;
+use super::scheduler::FlowOrdering;
+use super::scheduler::FlowSpawnRecord;
 use super::scheduler::Scheduler;
 use super::scheduler::SchedulerError;
 use super::secrets::SecretsCrud;
 use super::secrets::SecretsCrudError;
+use crate::retry::with_exp_backoff_retry_if;
+use crate::task::driver::TIMEOUT_TERMINATION_MESSAGE;
+use crate::task::layout::{FlowStoreLayout, StoreLayout};
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use futures::future::try_join_all;
 use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::chrono::{DateTime, Utc};
 use k8s_openapi::{api::batch::v1::Job, serde_json};
-use kube::api::ListParams;
+use kube::api::{DeleteParams, ListParams};
 use kube::core::ObjectList;
 use kube::{api::PostParams, Api, Client};
 use serde::Deserialize;
@@ -25,6 +45,9 @@ pub enum ExecutorError {
     /// Unable to deploy a job on Kubernetes.
     #[error("unable to spawn task: {0}")]
     UnableToSpawnTask(#[source] kube::error::Error),
+    /// Unable to delete a job or pods of a task on Kubernetes.
+    #[error("unable to delete task: {0}")]
+    UnableToDeleteTask(#[source] kube::error::Error),
     /// Unable to connect to Kubernetes API.
     #[error("unable connect to kubernetes: {0}")]
     UnableToConnectToKubernetes(#[source] kube::error::Error),
@@ -53,22 +76,102 @@ pub enum ExecutorError {
         #[source]
         SecretsCrudError,
     ),
-    /// Name of the flow exceeds 32 characters.
-    #[error("flow name longer than 32 characters: {0}")]
-    FlowNameTooLong(String),
+    /// A task in the flow refers to a secret that does not exist.
+    #[error("secret {0} referred by the flow does not exist")]
+    MissingSecret(String),
+    /// Timeout for the command of a task is not shorter than the active deadline of the task.
+    #[error("timeout for task {0} is not shorter than its active deadline")]
+    InvalidTaskTimeout(String),
+    /// Task with eager inputs does not set an active deadline or does not have an input from each of its dependencies.
+    #[error("invalid eager inputs for task {0}: {1}")]
+    InvalidEagerInputs(String, String),
+    /// Termination grace period of a task is negative.
+    #[error("termination grace period for task {0} is negative")]
+    InvalidTerminationGracePeriod(String),
+    /// Backoff limit of a task is negative.
+    #[error("backoff limit for task {0} is negative")]
+    InvalidBackoffLimit(String),
+    /// Annotations of a task's pod use the prefix reserved for flowmium.
+    #[error("invalid pod annotation for task {0}: {1}")]
+    InvalidPodAnnotation(String, String),
+    /// Volumes or volume mounts of a task collide with flowmium's reserved volume or refer to a missing volume.
+    #[error("invalid volumes for task {0}: {1}")]
+    InvalidTaskVolumes(String, String),
+    /// Periodic flow has a zero interval or has inline secrets which are deleted after each run.
+    #[error("invalid interval for periodic flow: {0}")]
+    InvalidFlowInterval(String),
+    /// Maximum number of parallel tasks of the flow is zero or too large.
+    #[error("invalid max parallel tasks: {0}")]
+    InvalidMaxParallelTasks(String),
+    /// Deadline of the flow is zero or too large.
+    #[error("invalid flow deadline: {0}")]
+    InvalidFlowDeadline(String),
+    /// Cron expression of a recurring flow cannot be parsed, or the flow also has an interval or inline secrets.
+    #[error("invalid schedule for recurring flow: {0}")]
+    InvalidFlowSchedule(String),
+    /// Task named by the flow's `on_failure` does not exist, has dependencies or other tasks depend on it.
+    #[error("invalid on failure task {0}: {1}")]
+    InvalidOnFailureTask(String, String),
+    /// Init container image of the flow is not a valid image reference.
+    #[error("invalid init container image: {0}")]
+    InvalidInitContainerImage(String),
+    /// Bucket name of the flow is not a valid S3 bucket name.
+    #[error("invalid bucket name: {0}")]
+    InvalidBucketName(String),
+    /// Idempotency key of a flow submission is empty or too long.
+    #[error("invalid idempotency key: {0}")]
+    InvalidIdempotencyKey(String),
+    /// Environment variables of the flow refer to an input, which only a task can.
+    #[error("invalid flow env: {0}")]
+    InvalidFlowEnv(String),
+    /// Environment variable overrides for re-running a flow refer to a missing task or have an invalid name.
+    #[error("invalid env override: {0}")]
+    InvalidEnvOverride(String),
+    /// Flow name is empty, too long or has characters that cannot be part of keys of artefacts in the store.
+    #[error("invalid flow name {0}: {1}")]
+    InvalidFlowName(String, String),
+    /// Task name is not a valid RFC 1123 label, is too long to fit in the name of the task's job or is not unique.
+    #[error("invalid task name {0}: {1}")]
+    InvalidTaskName(String, String),
+    /// Flow does not have a task with the ID.
+    #[error("flow {0} does not have task {1}")]
+    TaskDoesNotExist(i32, i32),
+    /// No pod exists for the task, the task has not been spawned yet or its pod has been garbage collected.
+    #[error("pod for flow {0} task {1} not found, the task has not been spawned yet or its pod has been deleted")]
+    TaskPodNotFound(i32, i32),
+    /// Logs of the task's pod cannot be read yet, like when the pod's containers are yet to start.
+    #[error("logs for flow {0} task {1} unavailable: {2}")]
+    TaskLogsUnavailable(i32, i32, String),
     /// Kubernetes returned an unknown status for a pod corresponding to a task.
     #[error("Unknown task status for flow {0} task {1}: {2}")]
     UnknownTaskStatus(i32, i32, String),
+    /// Image of a container of the task's pod could not be pulled for longer than the image pull grace period,
+    /// the image likely does not exist or the registry is unreachable or requires credentials.
+    #[error("unable to pull image for flow {0} task {1}: {2}")]
+    ImagePullFailed(i32, i32, String),
+    /// A container of the task's pod cannot be created and will not be until the task is changed, like when its
+    /// image name is invalid or it refers to a config map or secret that does not exist.
+    #[error("unable to create container for flow {0} task {1}: {2}")]
+    ContainerCreateFailed(i32, i32, String),
 }
 
 #[derive(Debug, PartialEq)]
-enum TaskStatus {
+pub(super) enum TaskStatus {
     Pending,
     Running,
     Finished,
     Failed,
+    TimedOut,
 }
 
+/// Annotation of a task's pod holding the backoff limit of its job, see [`Task::backoff_limit`].
+/// Prefix of annotations reserved for flowmium, tasks cannot set annotations with this prefix.
+const RESERVED_ANNOTATION_PREFIX: &str = "flowmium.io/";
+const BACKOFF_LIMIT_ANNOTATION: &str = "flowmium.io/backoff-limit";
+
+const EXECUTABLE_VOLUME_NAME: &str = "executable";
+const EXECUTABLE_MOUNT_PATH: &str = "/var/run";
+
 fn default_flow_label() -> String {
     "flowmium.io/flow-id".to_owned()
 }
@@ -77,6 +180,35 @@ fn default_task_label() -> String {
     "flowmium.io/task-id".to_owned()
 }
 
+fn default_max_concurrent_secret_fetches() -> NonZeroUsize {
+    // SAFETY: 4 is not zero
+    NonZeroUsize::new(4).unwrap()
+}
+
+fn default_task_store_retry_count() -> i32 {
+    5
+}
+
+fn default_spawn_retry_count() -> i32 {
+    3
+}
+
+fn default_scheduler_interval_ms() -> u64 {
+    1000
+}
+
+fn default_image_pull_grace_seconds() -> u64 {
+    120
+}
+
+fn default_flowmium_binary_path() -> String {
+    "/flowmium".to_owned()
+}
+
+fn default_shared_volume_path() -> String {
+    EXECUTABLE_MOUNT_PATH.to_owned()
+}
+
 /// Configuration for the executor.
 #[derive(Debug, PartialEq, Deserialize, Clone)]
 pub struct ExecutorConfig {
@@ -87,11 +219,17 @@ pub struct ExecutorConfig {
     pub task_store_url: String,
     /// Name of the bucket to store flow artifacts.
     pub bucket_name: String,
+    /// Region of the bucket to store flow artifacts, required for AWS S3. Default is `custom`, which works for MinIO.
+    pub bucket_region: Option<String>,
+    /// Use path-style URLs like `http://host/bucket` instead of virtual-hosted style URLs like
+    /// `http://bucket.host` for the bucket to store flow artifacts. Default is `true`, set this to `false` for AWS S3.
+    pub bucket_path_style: Option<bool>,
     /// Access key for s3 compatible storage for flow artifacts.
     pub access_key: String,
     /// Secret key for s3 compatible storage for flow artifacts.
     pub secret_key: String,
     /// Image to use for init container. Always set this to same image as the server.
+    /// Flows can override it with [`crate::model::Flow::init_container_image`].
     pub init_container_image: String,
     /// Kubernetes namespace for the server to run workflows in.
     pub namespace: String,
@@ -101,9 +239,83 @@ pub struct ExecutorConfig {
     /// Task ID Kubernetes label for task spawned by flowmium. Default is `flowmium.io/task-id`.
     #[serde(default = "default_task_label")]
     pub task_id_label: String,
+    /// Order in which flows are picked up to spawn their tasks, `fifo` or `lifo`. Default is `fifo`.
+    #[serde(default)]
+    pub flow_ordering: FlowOrdering,
+    /// Maximum number of concurrent database queries fetching secrets while spawning tasks, cannot be `0`.
+    /// Default is `4`.
+    #[serde(default = "default_max_concurrent_secret_fetches")]
+    pub max_concurrent_secret_fetches: NonZeroUsize,
+    /// Number of times a task retries connecting to the storage for flow artifacts if it is unreachable
+    /// when the task starts. Default is `5`.
+    #[serde(default = "default_task_store_retry_count")]
+    pub task_store_retry_count: i32,
+    /// Compress outputs of tasks with gzip before uploading them to the storage for flow artifacts.
+    /// Artifacts are decompressed when downloaded whether this is set or not, so artifacts uploaded before
+    /// changing it stay readable. Default is `false`.
+    #[serde(default)]
+    pub compress_artefacts: bool,
+    /// Number of times creating the job of a task is retried with exponential backoff if the Kubernetes API
+    /// is unreachable or responds with a server error, before the task is failed. Default is `3`.
+    #[serde(default = "default_spawn_retry_count")]
+    pub spawn_retry_count: i32,
+    /// Milliseconds to wait between passes of [`schedule_and_run_tasks`] that spawn tasks and update their statuses.
+    /// Longer intervals reduce load on the database and the Kubernetes API at the cost of latency. Default is `1000`.
+    #[serde(default = "default_scheduler_interval_ms")]
+    pub scheduler_interval_ms: u64,
+    /// Template for keys of flow artifacts in the bucket, see [`crate::layout::StoreLayout`].
+    /// Default is `{flow_id}/{output_name}`. Artifacts stored with a different layout are no longer
+    /// reachable after changing it.
+    #[serde(default)]
+    pub store_layout: StoreLayout,
+    /// Seconds a task's pod can fail to pull an image of one of its containers before the task is failed and its job
+    /// is deleted, counted from when the container started waiting rather than from when the pod was created. Kubernetes
+    /// keeps retrying pulls with a backoff, so a short outage of the registry does not fail the task. Containers that
+    /// refer to a config map or secret that does not exist (`CreateContainerConfigError`) get the same grace period.
+    /// Default is `120`.
+    #[serde(default = "default_image_pull_grace_seconds")]
+    pub image_pull_grace_seconds: u64,
+    /// Names of Kubernetes secrets with credentials for pulling images of tasks from private registries,
+    /// used for tasks that do not set [`crate::model::Task::image_pull_secrets`]. Default is none.
+    #[serde(default)]
+    pub image_pull_secrets: Vec<String>,
+    /// Path of the flowmium executable in the init container image, for custom images that install flowmium
+    /// at a different path. Default is `/flowmium`.
+    #[serde(default = "default_flowmium_binary_path")]
+    pub flowmium_binary_path: String,
+    /// Path the volume shared between the init container and the task's container is mounted at. The init
+    /// container copies the flowmium executable into it, and the task's command is run through the copy.
+    /// Default is `/var/run`.
+    #[serde(default = "default_shared_volume_path")]
+    pub shared_volume_path: String,
+    /// Seconds after a task's job has finished after which Kubernetes deletes the job and its pods, see
+    /// [`ExecutorConfig::get_job_ttl_seconds_after_finished`]. Jobs are never deleted if not set.
+    pub job_ttl_seconds_after_finished: Option<i64>,
+}
+
+/// Minimum seconds a finished job is kept for, see [`ExecutorConfig::get_job_ttl_seconds_after_finished`].
+const MIN_JOB_TTL_SECONDS_AFTER_FINISHED: i64 = 60;
+
+impl ExecutorConfig {
+    /// TTL of finished jobs, at least a minute and ten scheduling passes. The status of a task is read from its pod,
+    /// a task whose pod is deleted before its status was read fails, so the TTL is raised if it is too short.
+    /// Logs of a task can not be fetched once its job is deleted.
+    fn get_job_ttl_seconds_after_finished(&self) -> Option<i64> {
+        let scheduler_interval_seconds = (self.scheduler_interval_ms * 10).div_ceil(1000);
+        let min_ttl_seconds = MIN_JOB_TTL_SECONDS_AFTER_FINISHED
+            .max(i64::try_from(scheduler_interval_seconds).unwrap_or(i64::MAX));
+
+        self.job_ttl_seconds_after_finished
+            .map(|ttl_seconds| ttl_seconds.max(min_ttl_seconds))
+    }
+
+    /// Path of the copy of the flowmium executable in the volume shared with the task's container.
+    fn get_shared_binary_path(&self) -> String {
+        format!("{}/flowmium", self.shared_volume_path.trim_end_matches('/'))
+    }
 }
 
-async fn get_kubernetes_client() -> Result<Client, ExecutorError> {
+pub(super) async fn get_kubernetes_client() -> Result<Client, ExecutorError> {
     match Client::try_default().await {
         Ok(client) => Ok(client),
         Err(error) => {
@@ -113,35 +325,108 @@ async fn get_kubernetes_client() -> Result<Client, ExecutorError> {
     }
 }
 
-fn get_task_cmd(task: &Task) -> Vec<&str> {
-    let mut task_cmd = vec!["/var/run/flowmium", "task"];
+const MAX_TIMEOUT_GRACE_SECONDS: u64 = 30;
+
+/// Timeout for the task's command enforced by the sidecar. If the timeout is not explicitly set for the task,
+/// it is derived from the pod's active deadline with a grace period so that the sidecar times out the command
+/// and records the failure reason before Kubernetes kills the pod.
+fn get_task_timeout(task: &Task) -> Option<u64> {
+    match (task.timeout_seconds, task.active_deadline_seconds) {
+        (Some(timeout_seconds), _) => Some(timeout_seconds),
+        (None, Some(active_deadline_seconds)) => {
+            let grace_seconds = (active_deadline_seconds / 10).clamp(1, MAX_TIMEOUT_GRACE_SECONDS);
+            Some(active_deadline_seconds.saturating_sub(grace_seconds).max(1))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Tasks without inputs, outputs, a timeout, a working directory or stdin do not need the flowmium executable in
+/// their container, so their command is run directly, without an init container copying the executable into a volume shared with the task.
+fn needs_sidecar(task: &Task) -> bool {
+    task.inputs
+        .as_ref()
+        .is_some_and(|inputs| !inputs.is_empty())
+        || task
+            .outputs
+            .as_ref()
+            .is_some_and(|outputs| !outputs.is_empty())
+        || get_task_timeout(task).is_some()
+        || task.working_dir.is_some()
+        || task.stdin.is_some()
+}
+
+fn get_task_cmd<'a>(task: &'a Task, shared_binary_path: &'a str) -> Vec<&'a str> {
+    let mut task_cmd = vec![shared_binary_path, "task"];
     task_cmd.extend(task.cmd.iter().map(|elem| &elem[..]));
 
     task_cmd
 }
 
+/// Environment variable of the task's container, `None` for variables that are set by the sidecar instead.
 async fn get_env_json(
     env: &EnvVar,
+    flow_id: i32,
     secrets: &SecretsCrud,
-) -> Result<serde_json::Value, ExecutorError> {
+) -> Result<Option<serde_json::Value>, ExecutorError> {
     match env {
         EnvVar::KeyValuePair(KeyValuePair { name, value }) => {
-            Ok(serde_json::json! ({"name": name, "value": value}))
-        }
-        EnvVar::SecretRef(SecretRef { name, from_secret }) => {
-            Ok(serde_json::json! ({"name": name, "value": secrets.get_secret(from_secret).await?}))
+            Ok(Some(serde_json::json! ({"name": name, "value": value})))
         }
+        EnvVar::SecretRef(SecretRef { name, from_secret }) => Ok(Some(
+            serde_json::json! ({"name": name, "value": secrets.get_flow_secret(flow_id, from_secret).await?}),
+        )),
+        EnvVar::K8sSecretRef(K8sSecretRef {
+            name,
+            secret_name,
+            secret_key,
+        }) => Ok(Some(serde_json::json! ({
+            "name": name,
+            "valueFrom": {
+                "secretKeyRef": {"name": secret_name, "key": secret_key}
+            }
+        }))),
+        EnvVar::InputRef(_) => Ok(None),
     }
 }
 
-async fn get_task_envs<'a>(
-    task: &'a Task,
-    input_json: String,
-    output_json: String,
+/// Names of environment variables with values from inputs of the task along with the paths the inputs are
+/// downloaded to, see [`crate::task::driver::SidecarConfig`]. `None` if the task has no such variables.
+fn get_input_env_json(task: &Task) -> Option<String> {
+    let input_envs: Vec<serde_json::Value> = task
+        .env
+        .iter()
+        .filter_map(|env| match env {
+            EnvVar::InputRef(InputRef { name, from_input }) => Some((name, from_input)),
+            _ => None,
+        })
+        .filter_map(|(name, from_input)| {
+            let input = task
+                .inputs
+                .iter()
+                .flatten()
+                .find(|input| &input.from == from_input)?;
+
+            Some(serde_json::json!({"name": name, "path": input.path}))
+        })
+        .collect();
+
+    (!input_envs.is_empty()).then(|| serde_json::Value::from(input_envs).to_string())
+}
+
+/// Environment variables configuring the flowmium executable that runs the task's command, see
+/// [`crate::task::driver::SidecarConfig`].
+fn get_sidecar_envs(
+    task: &Task,
     flow_id: i32,
-    config: &'a ExecutorConfig,
-    secrets: &SecretsCrud,
-) -> Result<Vec<serde_json::Value>, ExecutorError> {
+    store_layout: &FlowStoreLayout,
+    bucket_name: &str,
+    config: &ExecutorConfig,
+) -> Vec<serde_json::Value> {
+    // SAFETY: Flow model types don't implement custom serializer methods or have non string keys
+    let input_json = serde_json::to_string(&task.inputs).unwrap();
+    let output_json = serde_json::to_string(&task.outputs).unwrap();
+
     let mut task_envs: Vec<serde_json::Value> = vec![
         serde_json::json! ({
             "name": "FLOWMIUM_INPUT_JSON",
@@ -155,6 +440,14 @@ async fn get_task_envs<'a>(
             "name": "FLOWMIUM_FLOW_ID",
             "value": flow_id.to_string(),
         }),
+        serde_json::json!( {
+            "name": "FLOWMIUM_STORE_PATH_PREFIX",
+            "value": store_layout.prefix(),
+        }),
+        serde_json::json!( {
+            "name": "FLOWMIUM_STORE_PATH_SUFFIX",
+            "value": store_layout.suffix(),
+        }),
         serde_json::json!( {
             "name": "FLOWMIUM_ACCESS_KEY",
             "value": config.access_key,
@@ -165,98 +458,451 @@ async fn get_task_envs<'a>(
         }),
         serde_json::json!( {
             "name": "FLOWMIUM_BUCKET_NAME",
-            "value": config.bucket_name,
+            "value": bucket_name,
         }),
         serde_json::json!( {
             "name": "FLOWMIUM_TASK_STORE_URL",
             "value": config.task_store_url,
         }),
+        serde_json::json!( {
+            "name": "FLOWMIUM_STORE_RETRY_COUNT",
+            "value": config.task_store_retry_count.to_string(),
+        }),
     ];
 
-    for env in task.env.iter() {
-        let json_env = get_env_json(env, secrets).await?;
-        task_envs.push(json_env);
+    if let Some(bucket_region) = &config.bucket_region {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_BUCKET_REGION",
+            "value": bucket_region,
+        }));
+    }
+
+    if let Some(bucket_path_style) = config.bucket_path_style {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_BUCKET_PATH_STYLE",
+            "value": bucket_path_style.to_string(),
+        }));
+    }
+
+    if let Some(input_env_json) = get_input_env_json(task) {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_INPUT_ENV_JSON",
+            "value": input_env_json,
+        }));
+    }
+
+    if task.eager_inputs {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_EAGER_INPUTS",
+            "value": "true",
+        }));
+    }
+
+    if config.compress_artefacts {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_COMPRESS_ARTEFACTS",
+            "value": "true",
+        }));
+    }
+
+    if let Some(timeout_seconds) = get_task_timeout(task) {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_TASK_TIMEOUT",
+            "value": timeout_seconds.to_string(),
+        }));
+    }
+
+    if let Some(working_dir) = &task.working_dir {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_WORKING_DIR",
+            "value": working_dir,
+        }));
+    }
+
+    if let Some(stdin) = &task.stdin {
+        task_envs.push(serde_json::json!( {
+            "name": "FLOWMIUM_STDIN",
+            "value": stdin,
+        }));
     }
 
+    task_envs
+}
+
+async fn get_task_envs<'a>(
+    task: &'a Task,
+    flow_env: &[EnvVar],
+    flow_id: i32,
+    store_layout: &FlowStoreLayout,
+    bucket_name: &str,
+    config: &'a ExecutorConfig,
+    secrets: &SecretsCrud,
+) -> Result<Vec<serde_json::Value>, ExecutorError> {
+    let mut task_envs = match needs_sidecar(task) {
+        true => get_sidecar_envs(task, flow_id, store_layout, bucket_name, config),
+        false => vec![],
+    };
+
+    // Variables of the flow are left out if the task sets a variable with the same name
+    let inherited_envs = flow_env.iter().filter(|flow_env| {
+        !task
+            .env
+            .iter()
+            .any(|env| get_env_name(env) == get_env_name(flow_env))
+    });
+
+    // Secrets are fetched concurrently, bounded by the secrets CRUD's fetch limit
+    let json_envs = try_join_all(
+        inherited_envs
+            .chain(task.env.iter())
+            .map(|env| get_env_json(env, flow_id, secrets)),
+    )
+    .await?;
+    task_envs.extend(json_envs.into_iter().flatten());
+
     Ok(task_envs)
 }
 
-#[tracing::instrument(skip(task, config, secrets))]
-async fn spawn_task(
+/// The container's `resources` block, `None` if no request or limit is set so that the block is omitted.
+fn get_resources_json(resources: &ResourceRequirements) -> Option<serde_json::Value> {
+    let quantities = |cpu: &Option<String>, memory: &Option<String>| {
+        let quantities: serde_json::Map<String, serde_json::Value> =
+            [("cpu", cpu), ("memory", memory)]
+                .into_iter()
+                .filter_map(|(name, quantity)| {
+                    let quantity = quantity
+                        .as_deref()
+                        .filter(|quantity| !quantity.is_empty())?;
+                    Some((name.to_owned(), serde_json::json!(quantity)))
+                })
+                .collect();
+
+        (!quantities.is_empty()).then_some(quantities)
+    };
+
+    let mut resources_json = serde_json::Map::new();
+
+    if let Some(requests) = quantities(&resources.cpu_request, &resources.memory_request) {
+        resources_json.insert("requests".to_owned(), requests.into());
+    }
+
+    if let Some(limits) = quantities(&resources.cpu_limit, &resources.memory_limit) {
+        resources_json.insert("limits".to_owned(), limits.into());
+    }
+
+    (!resources_json.is_empty()).then_some(resources_json.into())
+}
+
+fn get_job_json(
     flow_id: i32,
     task_id: i32,
     task: &Task,
     config: &ExecutorConfig,
-    secrets: &SecretsCrud,
-) -> Result<Job, ExecutorError> {
-    tracing::info!("Spawning task");
+    init_container_image: &str,
+    task_envs: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    // Labels and annotations managed by flowmium take precedence over the ones of the task
+    let mut labels = task.pod_labels.clone().unwrap_or_default();
+    labels.insert(config.flow_id_label.clone(), flow_id.to_string());
+    labels.insert(config.task_id_label.clone(), task_id.to_string());
 
-    let client = get_kubernetes_client().await?;
+    let mut annotations = task.pod_annotations.clone().unwrap_or_default();
 
-    let jobs: Api<Job> = Api::namespaced(client, &config.namespace);
+    // Reserved annotations are rejected when the flow is created, flows created before that still may have them
+    annotations.retain(|key, _| !key.starts_with(RESERVED_ANNOTATION_PREFIX));
 
-    // SAFETY: Flow model types don't implement custom serializer methods or have non string keys
-    let input_json = serde_json::to_string(&task.inputs).unwrap();
-    let output_json = serde_json::to_string(&task.outputs).unwrap();
+    if let Some(backoff_limit) = task
+        .backoff_limit
+        .filter(|backoff_limit| *backoff_limit > 0)
+    {
+        annotations.insert(
+            BACKOFF_LIMIT_ANNOTATION.to_owned(),
+            backoff_limit.to_string(),
+        );
+    }
 
-    let data = serde_json::from_value(serde_json::json!({
+    let mut job = serde_json::json!({
         "apiVersion": "batch/v1",
         "kind": "Job",
         "metadata": {
-            "name": format!("flow-{}-task-{}", flow_id, task.name),
+            "name": get_job_name(flow_id, task),
         },
         "spec": {
             "template": {
                 "metadata": {
                     "name": task.name,
-                    "labels": {
-                        &config.flow_id_label: flow_id.to_string(),
-                        &config.task_id_label: task_id.to_string()
-                    }
+                    "labels": labels,
                 },
                 "spec": {
-                    "initContainers": [
-                        {
-                            "name": "init",
-                            "image": &config.init_container_image,
-                            "command": ["/flowmium", "init", "/flowmium", "/var/run/flowmium"],
-                            "volumeMounts": [
-                                {
-                                    "name": "executable",
-                                    "mountPath": "/var/run",
-                                }
-                            ]
-                        }
-                    ],
                     "containers": [{
                         "name": task.name,
                         "image": task.image,
-                        "command": get_task_cmd(task),
-                        "env": get_task_envs(task, input_json, output_json, flow_id, config, secrets).await?,
-                        "volumeMounts": [
-                            {
-                                "name": "executable",
-                                "mountPath": "/var/run",
-                            }
-                        ]
+                        "env": task_envs,
+                        "volumeMounts": []
                     }],
                     "restartPolicy": "Never",
-                    "volumes": [
-                        {
-                            "name": "executable",
-                            "emptyDir": {
-                                "medium": "Memory",
-                            }
-                        }
-                    ],
+                    "volumes": [],
                 }
             },
-            "backoffLimit": 0,
+            "backoffLimit": task.backoff_limit.unwrap_or(0),
+        }
+    });
+
+    if let Some(ttl_seconds) = config.get_job_ttl_seconds_after_finished() {
+        job["spec"]["ttlSecondsAfterFinished"] = serde_json::json!(ttl_seconds);
+    }
+
+    if !annotations.is_empty() {
+        job["spec"]["template"]["metadata"]["annotations"] = serde_json::json!(annotations);
+    }
+
+    let pod_spec = &mut job["spec"]["template"]["spec"];
+
+    if needs_sidecar(task) {
+        let shared_binary_path = config.get_shared_binary_path();
+
+        let executable_volume_mount = serde_json::json!({
+            "name": EXECUTABLE_VOLUME_NAME,
+            "mountPath": &config.shared_volume_path,
+        });
+
+        pod_spec["initContainers"] = serde_json::json!([
+            {
+                "name": "init",
+                "image": init_container_image,
+                "command": [
+                    &config.flowmium_binary_path,
+                    "init",
+                    &config.flowmium_binary_path,
+                    &shared_binary_path,
+                ],
+                "volumeMounts": [executable_volume_mount]
+            }
+        ]);
+
+        pod_spec["containers"][0]["command"] =
+            serde_json::json!(get_task_cmd(task, &shared_binary_path));
+        pod_spec["containers"][0]["volumeMounts"] = serde_json::json!([executable_volume_mount]);
+
+        pod_spec["volumes"] = serde_json::json!([
+            {
+                "name": EXECUTABLE_VOLUME_NAME,
+                "emptyDir": {
+                    "medium": "Memory",
+                }
+            }
+        ]);
+    } else if !task.cmd.is_empty() {
+        // Without a command the image's own entrypoint is run
+        pod_spec["containers"][0]["command"] = serde_json::json!(task.cmd);
+    }
+
+    if let Some(active_deadline_seconds) = task.active_deadline_seconds {
+        pod_spec["activeDeadlineSeconds"] = serde_json::json!(active_deadline_seconds);
+    }
+
+    if let Some(termination_grace_period_seconds) = task.termination_grace_period_seconds {
+        pod_spec["terminationGracePeriodSeconds"] =
+            serde_json::json!(termination_grace_period_seconds);
+    }
+
+    let image_pull_secrets = task
+        .image_pull_secrets
+        .as_ref()
+        .unwrap_or(&config.image_pull_secrets);
+
+    if !image_pull_secrets.is_empty() {
+        pod_spec["imagePullSecrets"] = image_pull_secrets
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+    }
+
+    if let Some(node_selector) = &task.node_selector {
+        pod_spec["nodeSelector"] = serde_json::json!(node_selector);
+    }
+
+    // SAFETY: Kubernetes types don't implement custom serializer methods or have non string keys
+    if let Some(tolerations) = &task.tolerations {
+        pod_spec["tolerations"] = serde_json::to_value(tolerations).unwrap();
+    }
+
+    // SAFETY: Kubernetes volume types don't implement custom serializer methods or have non string keys
+    if let Some(volumes) = &task.volumes {
+        let pod_volumes = pod_spec["volumes"].as_array_mut().unwrap();
+        pod_volumes.extend(
+            volumes
+                .iter()
+                .map(|volume| serde_json::to_value(volume).unwrap()),
+        );
+    }
+
+    // SAFETY: Kubernetes types don't implement custom serializer methods or have non string keys
+    if let Some(env_from) = &task.env_from {
+        pod_spec["containers"][0]["envFrom"] = serde_json::to_value(env_from).unwrap();
+    }
+
+    if let Some(resources) = task.resources.as_ref().and_then(get_resources_json) {
+        pod_spec["containers"][0]["resources"] = resources;
+    }
+
+    if let Some(volume_mounts) = &task.volume_mounts {
+        let container_mounts = pod_spec["containers"][0]["volumeMounts"]
+            .as_array_mut()
+            .unwrap();
+        container_mounts.extend(
+            volume_mounts
+                .iter()
+                .map(|volume_mount| serde_json::to_value(volume_mount).unwrap()),
+        );
+    }
+
+    if pod_spec["volumes"].as_array().is_some_and(Vec::is_empty) {
+        pod_spec.as_object_mut().unwrap().remove("volumes");
+    }
+
+    let container = &mut pod_spec["containers"][0];
+
+    if container["volumeMounts"]
+        .as_array()
+        .is_some_and(Vec::is_empty)
+    {
+        container.as_object_mut().unwrap().remove("volumeMounts");
+    }
+
+    job
+}
+
+fn get_job_name(flow_id: i32, task: &Task) -> String {
+    format!("flow-{}-task-{}", flow_id, task.name)
+}
+
+/// Delete the job and pods of a task. Used to delete a previous attempt of a task, so that the task can be spawned
+/// again with the same job name and the task's status is not read from the failed pod, and to stop an aborted flow.
+#[tracing::instrument(skip(task, config))]
+async fn delete_task_job(
+    flow_id: i32,
+    task_id: i32,
+    task: &Task,
+    config: &ExecutorConfig,
+) -> Result<(), ExecutorError> {
+    let client = get_kubernetes_client().await?;
+
+    let jobs: Api<Job> = Api::namespaced(client.clone(), &config.namespace);
+    let pods: Api<Pod> = Api::namespaced(client, &config.namespace);
+
+    match jobs
+        .delete(&get_job_name(flow_id, task), &DeleteParams::background())
+        .await
+    {
+        Ok(_) => (),
+        Err(kube::Error::Api(response)) if response.code == 404 => (),
+        Err(error) => {
+            tracing::error!(%error, "Unable to delete job of task");
+            return Err(ExecutorError::UnableToDeleteTask(error));
         }
-    }))
+    }
+
+    let list_params =
+        ListParams::default().labels(&get_task_label_selector(flow_id, task_id, config));
+
+    if let Err(error) = pods
+        .delete_collection(&DeleteParams::default(), &list_params)
+        .await
+    {
+        tracing::error!(%error, "Unable to delete pods of task");
+        return Err(ExecutorError::UnableToDeleteTask(error));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(task, config, flow, secrets))]
+async fn spawn_task(
+    flow_id: i32,
+    task_id: i32,
+    task: &Task,
+    config: &ExecutorConfig,
+    flow: &FlowSpawnRecord,
+    secrets: &SecretsCrud,
+) -> Result<Job, ExecutorError> {
+    tracing::info!("Spawning task");
+
+    let client = get_kubernetes_client().await?;
+
+    let jobs: Api<Job> = Api::namespaced(client, &config.namespace);
+
+    let task = &task
+        .with_resolved_artefact_names()
+        .map_err(|var_name| PlannerError::UnresolvedVariable(task.name.clone(), var_name))?;
+
+    let store_layout = flow.get_store_layout(flow_id, &config.store_layout);
+    let bucket_name = flow.bucket_name.as_deref().unwrap_or(&config.bucket_name);
+
+    let task_envs = get_task_envs(
+        task,
+        &flow.env,
+        flow_id,
+        &store_layout,
+        bucket_name,
+        config,
+        secrets,
+    )
+    .await?;
+
+    let init_container_image = flow
+        .init_container_image
+        .as_deref()
+        .unwrap_or(&config.init_container_image);
+
+    let data = serde_json::from_value(get_job_json(
+        flow_id,
+        task_id,
+        task,
+        config,
+        init_container_image,
+        task_envs,
+    ))
     .unwrap();
 
-    match jobs.create(&PostParams::default(), &data).await {
+    let post_params = PostParams::default();
+
+    create_job_with_retry(
+        || jobs.create(&post_params, &data),
+        config.spawn_retry_count,
+    )
+    .await
+}
+
+/// Returns `true` if the Kubernetes API is likely unreachable or overloaded and the request can be retried.
+/// Errors like an invalid job or a job that already exists are not transient.
+fn is_transient_kube_error(error: &kube::Error) -> bool {
+    match error {
+        kube::Error::Api(response) => {
+            response.code == 408 || response.code == 429 || response.code >= 500
+        }
+        kube::Error::HyperError(_) | kube::Error::Service(_) => true,
+        _ => false,
+    }
+}
+
+/// Create a job by calling `create`, retrying with exponential backoff up to `max_retry_count` times on transient errors.
+async fn create_job_with_retry<F>(
+    create: impl Fn() -> F,
+    max_retry_count: i32,
+) -> Result<Job, ExecutorError>
+where
+    F: Future<Output = Result<Job, kube::Error>>,
+{
+    match with_exp_backoff_retry_if(
+        create,
+        is_transient_kube_error,
+        "Unable to spawn job",
+        max_retry_count,
+        true,
+    )
+    .await
+    {
         Ok(job) => Ok(job),
         Err(error) => {
             tracing::error!(%error, "Unable to spawn job");
@@ -265,23 +911,34 @@ async fn spawn_task(
     }
 }
 
+fn get_task_label_selector(flow_id: i32, task_id: i32, config: &ExecutorConfig) -> String {
+    format!(
+        "{}={},{}={}",
+        config.flow_id_label, flow_id, config.task_id_label, task_id
+    )
+}
+
+/// List pods of all tasks of a flow.
 #[tracing::instrument(skip(config))]
-async fn list_pods(
+pub(super) async fn list_flow_pods(
     flow_id: i32,
-    task_id: i32,
+    config: &ExecutorConfig,
+) -> Result<ObjectList<Pod>, ExecutorError> {
+    let label_selector = format!("{}={}", config.flow_id_label, flow_id);
+
+    list_pods_with_labels(&label_selector, config).await
+}
+
+async fn list_pods_with_labels(
+    label_selector: &str,
     config: &ExecutorConfig,
 ) -> Result<ObjectList<Pod>, ExecutorError> {
     let client = get_kubernetes_client().await?;
 
     let pods_api: Api<Pod> = Api::namespaced(client, &config.namespace);
 
-    let label_selector = format!(
-        "{}={},{}={}",
-        config.flow_id_label, flow_id, config.task_id_label, task_id
-    );
-
     let mut list_params = ListParams::default();
-    list_params = list_params.labels(&label_selector);
+    list_params = list_params.labels(label_selector);
 
     let pod_list = match pods_api.list(&list_params).await {
         Ok(list) => list,
@@ -294,14 +951,14 @@ async fn list_pods(
     Ok(pod_list)
 }
 
-fn get_pod_phase(pod: Pod) -> Option<String> {
+pub(super) fn get_pod_phase(pod: Pod) -> Option<String> {
     let pod_status = pod.status?;
     let phase = pod_status.phase?;
 
     Some(phase)
 }
 
-fn phase_to_task_status(phase: &str) -> Option<TaskStatus> {
+pub(super) fn phase_to_task_status(phase: &str) -> Option<TaskStatus> {
     match phase {
         "Pending" => Some(TaskStatus::Pending),
         "Running" => Some(TaskStatus::Running),
@@ -312,284 +969,1434 @@ fn phase_to_task_status(phase: &str) -> Option<TaskStatus> {
     }
 }
 
-// TODO: Batch these requests
+pub(super) fn get_pod_task_id(pod: &Pod, config: &ExecutorConfig) -> Option<i32> {
+    pod.metadata
+        .labels
+        .as_ref()?
+        .get(&config.task_id_label)?
+        .parse()
+        .ok()
+}
+
+/// Status of each of the running tasks of a flow with a single list of the flow's pods, keyed by task ID.
 #[tracing::instrument(skip(config))]
-async fn get_task_status(
+async fn get_statuses_for_flow(
     flow_id: i32,
-    task_id: i32,
+    running_tasks: &[i32],
     config: &ExecutorConfig,
-) -> Result<TaskStatus, ExecutorError> {
-    let pod_list = list_pods(flow_id, task_id, config).await?;
-    let mut pod_iter = pod_list.iter();
+) -> Result<BTreeMap<i32, Result<TaskStatus, ExecutorError>>, ExecutorError> {
+    let pod_list = list_flow_pods(flow_id, config).await?;
 
-    let Some(pod) = pod_iter.next() else {
-        tracing::error!("Cannot find corresponding pod for task");
-        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
-    };
+    Ok(get_pods_task_statuses(
+        flow_id,
+        running_tasks,
+        &pod_list.items,
+        config,
+        Utc::now(),
+    ))
+}
 
-    if pod_iter.peekable().peek().is_some() {
-        tracing::error!("Found duplicate pod for task");
-        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
+/// Status of each of the running tasks of a flow from the flow's pods. A task fails with
+/// [`ExecutorError::UnexpectedRunnerState`] if it has no pod or more than one pod.
+fn get_pods_task_statuses(
+    flow_id: i32,
+    running_tasks: &[i32],
+    pods: &[Pod],
+    config: &ExecutorConfig,
+    now: DateTime<Utc>,
+) -> BTreeMap<i32, Result<TaskStatus, ExecutorError>> {
+    let mut task_pods: BTreeMap<i32, Vec<&Pod>> = BTreeMap::new();
+
+    // Pods of a previous attempt of a retried task are being deleted
+    for pod in pods
+        .iter()
+        .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+    {
+        if let Some(task_id) = get_pod_task_id(pod, config) {
+            task_pods.entry(task_id).or_default().push(pod);
+        }
     }
 
-    let Some(phase) = get_pod_phase(pod.to_owned()) else {
-        tracing::error!("Unable to fetch status for pod");
-        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
-    };
+    running_tasks
+        .iter()
+        .map(|&task_id| {
+            let status = match task_pods.get(&task_id).map(Vec::as_slice) {
+                None | Some([]) => {
+                    tracing::error!(task_id, "Cannot find corresponding pod for task");
+                    Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id))
+                }
+                Some(pods) => get_attempts_task_status(flow_id, task_id, pods, config, now),
+            };
 
-    let status = phase_to_task_status(&phase);
+            (task_id, status)
+        })
+        .collect()
+}
 
-    let Some(status) = status else {
-        tracing::error!("Unknown status for pod");
-        return Err(ExecutorError::UnknownTaskStatus(flow_id, task_id, phase));
-    };
+const IMAGE_PULL_FAILURE_REASONS: [&str; 2] = ["ErrImagePull", "ImagePullBackOff"];
 
-    Ok(status)
+/// Reasons for a container waiting to be created that Kubernetes keeps retrying, but are not going to resolve
+/// by themselves. Unlike the container's image failing to be pulled, which may be a transient registry outage.
+const CONTAINER_CREATE_FAILURE_REASONS: [&str; 2] = ["CreateContainerError", "InvalidImageName"];
+
+/// Reasons for a container waiting to be created because a config map or secret it refers to does not exist,
+/// which resolves by itself if they are created shortly after the task, so these get the image pull grace period.
+const CONTAINER_CONFIG_FAILURE_REASONS: [&str; 1] = ["CreateContainerConfigError"];
+
+/// Reason and message of a container of the pod, including the init container, waiting with one of `reasons`,
+/// like waiting for its image to be pulled after a failed pull. Containers that are just being created
+/// (`ContainerCreating`) are not failures.
+fn get_waiting_failure(pod: &Pod, reasons: &[&str]) -> Option<String> {
+    let pod_status = pod.status.as_ref()?;
+
+    pod_status
+        .init_container_statuses
+        .iter()
+        .chain(pod_status.container_statuses.iter())
+        .flatten()
+        .find_map(|container_status| {
+            let waiting = container_status.state.as_ref()?.waiting.as_ref()?;
+            let reason = waiting.reason.as_deref()?;
+
+            if !reasons.contains(&reason) {
+                return None;
+            }
+
+            Some(format!(
+                "{} for image {}: {}",
+                reason,
+                container_status.image,
+                waiting.message.as_deref().unwrap_or_default()
+            ))
+        })
 }
 
-/// Create a workflow in pending state that will start running eventually by calling [`crate::executor::schedule_and_run_tasks`].
-#[tracing::instrument(skip(sched, flow))]
-pub async fn instantiate_flow(flow: Flow, sched: &Scheduler) -> Result<i32, ExecutorError> {
-    if flow.name.len() > 32 {
-        return Err(ExecutorError::FlowNameTooLong(flow.name.clone()));
-    }
+/// Time the containers of a pending pod started waiting, that is when the pod's init containers completed for its
+/// containers or when the pod was scheduled for its init containers. Falls back to the time the pod was created.
+fn get_waiting_since(pod: &Pod) -> Option<DateTime<Utc>> {
+    let transitioned_at = |condition_type: &str| {
+        pod.status
+            .as_ref()?
+            .conditions
+            .iter()
+            .flatten()
+            .find(|condition| condition.type_ == condition_type && condition.status == "True")?
+            .last_transition_time
+            .as_ref()
+            .map(|time| time.0)
+    };
 
-    let plan = construct_plan(&flow.tasks)?;
+    transitioned_at("Initialized")
+        .or_else(|| transitioned_at("PodScheduled"))
+        .or_else(|| pod.metadata.creation_timestamp.as_ref().map(|time| time.0))
+}
 
-    tracing::info!(flow_name = flow.name, plan = ?plan, "Creating flow");
-    let flow_id = sched.create_flow(flow.name, plan, flow.tasks).await?;
+/// Returns `true` if the task's container terminated because its command exceeded the task's timeout.
+fn has_timed_out(pod: &Pod) -> bool {
+    pod.status
+        .iter()
+        .flat_map(|pod_status| pod_status.container_statuses.iter().flatten())
+        .filter_map(|container_status| container_status.state.as_ref()?.terminated.as_ref())
+        .filter_map(|terminated| terminated.message.as_deref())
+        .any(|message| message.starts_with(TIMEOUT_TERMINATION_MESSAGE))
+}
 
-    Ok(flow_id)
+pub(super) fn get_pod_backoff_limit(pod: &Pod) -> usize {
+    pod.metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(BACKOFF_LIMIT_ANNOTATION))
+        .and_then(|backoff_limit| backoff_limit.parse().ok())
+        .unwrap_or(0)
 }
 
-#[tracing::instrument(skip(sched, config, secrets))]
-async fn sched_tasks(
-    sched: &Scheduler,
+/// Status of a task from the pods of its job, Kubernetes creates a pod for every attempt until the job's backoff
+/// limit is reached ([`Task::backoff_limit`]). The task is running while Kubernetes is yet to replace a failed pod,
+/// and fails once the last attempt has failed.
+fn get_attempts_task_status(
     flow_id: i32,
+    task_id: i32,
+    pods: &[&Pod],
     config: &ExecutorConfig,
-    secrets: &SecretsCrud,
-) -> Result<bool, ExecutorError> {
-    let option_tasks = sched.schedule_tasks(flow_id).await?;
+    now: DateTime<Utc>,
+) -> Result<TaskStatus, ExecutorError> {
+    let backoff_limit = pods
+        .iter()
+        .map(|pod| get_pod_backoff_limit(pod))
+        .max()
+        .unwrap_or(0);
 
-    if let Some(tasks) = option_tasks {
-        for (task_id, task) in tasks {
-            match spawn_task(flow_id, task_id, &task, config, secrets).await {
-                Ok(_) => sched.mark_task_running(flow_id, task_id).await?,
-                Err(_) => {
-                    // TODO: Add test for below, without below, jobs could get stale on restart
-                    sched.mark_task_failed(flow_id, task_id).await?;
-                    break;
-                }
-            }
-        }
+    if pods.len() > backoff_limit + 1 {
+        tracing::error!(task_id, "Found duplicate pod for task");
+        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
+    }
 
-        return Ok(true);
+    let mut pods = pods.to_vec();
+    pods.sort_by_key(|pod| pod.metadata.creation_timestamp.clone());
+
+    let mut statuses = pods
+        .iter()
+        .map(|pod| get_pod_task_status(flow_id, task_id, pod, config, now))
+        .collect::<Result<Vec<TaskStatus>, ExecutorError>>()?;
+
+    if statuses.contains(&TaskStatus::Finished) {
+        return Ok(TaskStatus::Finished);
     }
 
-    Ok(false)
+    let attempts = statuses.len();
+
+    if let Some(index) = statuses
+        .iter()
+        .rposition(|status| matches!(status, TaskStatus::Pending | TaskStatus::Running))
+    {
+        return Ok(statuses.swap_remove(index));
+    }
+
+    match attempts > backoff_limit {
+        true => Ok(statuses.pop().unwrap_or(TaskStatus::Failed)),
+        false => {
+            tracing::warn!(task_id, "Task failed, waiting for its job to retry it");
+            Ok(TaskStatus::Running)
+        }
+    }
 }
 
-#[tracing::instrument(skip(sched, config))]
-async fn mark_tasks(
-    sched: &Scheduler,
+/// Status of a task from its pod. A pending pod that has not been able to pull an image for longer than
+/// [`ExecutorConfig::image_pull_grace_seconds`] since its containers started waiting (see [`get_waiting_since`])
+/// fails with [`ExecutorError::ImagePullFailed`], and one whose container cannot be created fails with
+/// [`ExecutorError::ContainerCreateFailed`], right away or after the same grace period if the container's config
+/// cannot be created, as Kubernetes would otherwise keep them pending forever.
+fn get_pod_task_status(
     flow_id: i32,
     task_id: i32,
+    pod: &Pod,
     config: &ExecutorConfig,
-) -> Result<(), SchedulerError> {
-    let status = match get_task_status(flow_id, task_id, config).await {
-        Ok(status) => status,
-        Err(_) => return sched.mark_task_failed(flow_id, task_id).await,
+    now: DateTime<Utc>,
+) -> Result<TaskStatus, ExecutorError> {
+    let Some(phase) = get_pod_phase(pod.to_owned()) else {
+        tracing::error!("Unable to fetch status for pod");
+        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
     };
 
-    match status {
-        TaskStatus::Pending | TaskStatus::Running => Ok(()),
-        TaskStatus::Finished => sched.mark_task_finished(flow_id, task_id).await,
-        TaskStatus::Failed => sched.mark_task_failed(flow_id, task_id).await,
+    let status = phase_to_task_status(&phase);
+
+    let Some(status) = status else {
+        tracing::error!("Unknown status for pod");
+        return Err(ExecutorError::UnknownTaskStatus(flow_id, task_id, phase));
+    };
+
+    if status == TaskStatus::Failed && has_timed_out(pod) {
+        tracing::error!("Task timed out");
+        return Ok(TaskStatus::TimedOut);
+    }
+
+    if status != TaskStatus::Pending {
+        return Ok(status);
+    }
+
+    if let Some(failure) = get_waiting_failure(pod, &CONTAINER_CREATE_FAILURE_REASONS) {
+        tracing::error!(failure, "Unable to create container for task");
+        return Err(ExecutorError::ContainerCreateFailed(
+            flow_id, task_id, failure,
+        ));
+    }
+
+    let pending_seconds =
+        get_waiting_since(pod).map_or(0, |waiting_since| (now - waiting_since).num_seconds());
+    let is_past_grace_period = pending_seconds > config.image_pull_grace_seconds as i64;
+
+    if let Some(failure) = get_waiting_failure(pod, &CONTAINER_CONFIG_FAILURE_REASONS) {
+        if is_past_grace_period {
+            tracing::error!(failure, "Unable to create container config for task");
+            return Err(ExecutorError::ContainerCreateFailed(
+                flow_id, task_id, failure,
+            ));
+        }
+
+        tracing::warn!(
+            failure,
+            "Unable to create container config for task, retrying"
+        );
+    }
+
+    if let Some(failure) = get_waiting_failure(pod, &IMAGE_PULL_FAILURE_REASONS) {
+        if is_past_grace_period {
+            tracing::error!(failure, "Unable to pull image for task");
+            return Err(ExecutorError::ImagePullFailed(flow_id, task_id, failure));
+        }
+
+        tracing::warn!(failure, "Unable to pull image for task, retrying");
     }
+
+    Ok(status)
 }
 
-/// Spawn jobs to make progress pending tasks. Should be called periodically.
-#[tracing::instrument(skip(sched, config, secrets))]
-pub async fn schedule_and_run_tasks(
-    sched: &Scheduler,
-    config: &ExecutorConfig,
-    secrets: &SecretsCrud,
-) {
-    if let Ok(flows) = sched.get_running_or_pending_flow_ids().await {
-        for (flow_id, running_tasks) in flows {
-            match sched_tasks(sched, flow_id, config, secrets).await {
-                Ok(true) => continue,
-                Ok(false) => (),
-                Err(_) => break,
+/// Status of a task from the conditions of its job, `None` while the job has neither completed nor failed.
+fn get_job_task_status(job: &Job) -> Option<TaskStatus> {
+    job.status
+        .as_ref()?
+        .conditions
+        .iter()
+        .flatten()
+        .filter(|condition| condition.status == "True")
+        .find_map(|condition| match condition.type_.as_str() {
+            "Complete" | "SuccessCriteriaMet" => Some(TaskStatus::Finished),
+            "Failed" | "FailureTarget" => Some(TaskStatus::Failed),
+            _ => None,
+        })
+}
+
+/// Status of a task whose status could not be read from its pods, like when the pod has been garbage collected,
+/// from its job if the job has completed or failed.
+fn reconcile_task_status(
+    status: Result<TaskStatus, ExecutorError>,
+    job: Option<&Job>,
+) -> Result<TaskStatus, ExecutorError> {
+    match (status, job.and_then(get_job_task_status)) {
+        (Err(ExecutorError::UnexpectedRunnerState(..)), Some(job_status)) => Ok(job_status),
+        (status, _) => status,
+    }
+}
+
+fn check_task_timeouts(flow: &Flow) -> Result<(), ExecutorError> {
+    for task in flow.tasks.iter() {
+        if let (Some(timeout_seconds), Some(active_deadline_seconds)) =
+            (task.timeout_seconds, task.active_deadline_seconds)
+        {
+            if timeout_seconds >= active_deadline_seconds {
+                return Err(ExecutorError::InvalidTaskTimeout(task.name.clone()));
             }
+        }
+    }
 
-            for task_id in running_tasks {
-                if (mark_tasks(sched, flow_id, task_id, config).await).is_err() {
-                    break;
-                };
+    Ok(())
+}
+
+fn check_eager_inputs(flow: &Flow) -> Result<(), ExecutorError> {
+    // Artefact names of the flow resolve, they have been checked when constructing the plan
+    let tasks: Vec<Task> = flow
+        .tasks
+        .iter()
+        .filter_map(|task| task.with_resolved_artefact_names().ok())
+        .collect();
+
+    for task in tasks.iter().filter(|task| task.eager_inputs) {
+        let invalid = |reason: String| ExecutorError::InvalidEagerInputs(task.name.clone(), reason);
+
+        if task.active_deadline_seconds.is_none() {
+            return Err(invalid("active_deadline_seconds is not set".to_owned()));
+        }
+
+        if task.on != RunCondition::OnSuccess {
+            return Err(invalid("task does not run on success".to_owned()));
+        }
+
+        let input_names: BTreeSet<&str> = task
+            .inputs
+            .iter()
+            .flatten()
+            .map(|input| input.from.as_str())
+            .collect();
+
+        for dependency in task.depends.iter() {
+            let has_input = tasks
+                .iter()
+                .filter(|other_task| &other_task.name == dependency)
+                .flat_map(|other_task| other_task.outputs.iter().flatten())
+                .any(|output| input_names.contains(output.name.as_str()));
+
+            if !has_input {
+                return Err(invalid(format!("no input from dependency {}", dependency)));
             }
         }
     }
+
+    Ok(())
 }
-#[cfg(test)]
-mod tests {
 
-    use std::time::Duration;
+fn check_backoff_limits(flow: &Flow) -> Result<(), ExecutorError> {
+    match flow.tasks.iter().find(|task| {
+        task.backoff_limit
+            .is_some_and(|backoff_limit| backoff_limit < 0)
+    }) {
+        Some(task) => Err(ExecutorError::InvalidBackoffLimit(task.name.clone())),
+        None => Ok(()),
+    }
+}
 
-    use kube::api::DeleteParams;
-    use s3::Bucket;
-    use serial_test::serial;
+fn check_termination_grace_periods(flow: &Flow) -> Result<(), ExecutorError> {
+    for task in flow.tasks.iter() {
+        if task
+            .termination_grace_period_seconds
+            .is_some_and(|seconds| seconds < 0)
+        {
+            return Err(ExecutorError::InvalidTerminationGracePeriod(
+                task.name.clone(),
+            ));
+        }
+    }
 
-    use crate::{
-        server::{
-            model::{Input, Output},
-            pool::get_test_pool,
-        },
-        task::bucket::get_bucket,
-    };
+    Ok(())
+}
 
-    use super::*;
+fn check_pod_annotations(flow: &Flow) -> Result<(), ExecutorError> {
+    for task in flow.tasks.iter() {
+        if let Some(key) = task
+            .pod_annotations
+            .iter()
+            .flat_map(BTreeMap::keys)
+            .find(|key| key.starts_with(RESERVED_ANNOTATION_PREFIX))
+        {
+            return Err(ExecutorError::InvalidPodAnnotation(
+                task.name.clone(),
+                format!(
+                    "{} uses the prefix {} reserved by flowmium",
+                    key, RESERVED_ANNOTATION_PREFIX
+                ),
+            ));
+        }
+    }
 
-    fn test_executor_config() -> ExecutorConfig {
-        ExecutorConfig {
-            store_url: "http://localhost:9000".to_owned(),
-            task_store_url: "http://172.16.238.4:9000".to_owned(),
-            bucket_name: "flowmium-test".to_owned(),
-            access_key: "minio".to_owned(),
-            secret_key: "password".to_owned(),
-            init_container_image: "registry:5000/flowmium-debug".to_owned(),
-            namespace: "default".to_owned(),
-            flow_id_label: default_flow_label(),
-            task_id_label: default_task_label(),
+    Ok(())
+}
+
+fn check_task_volumes(flow: &Flow, config: &ExecutorConfig) -> Result<(), ExecutorError> {
+    let shared_volume_path = config.shared_volume_path.trim_end_matches('/');
+    let executable_path = config.get_shared_binary_path();
+
+    for task in flow.tasks.iter() {
+        let invalid = |reason: String| ExecutorError::InvalidTaskVolumes(task.name.clone(), reason);
+
+        let volumes = task.volumes.as_deref().unwrap_or_default();
+        let volume_mounts = task.volume_mounts.as_deref().unwrap_or_default();
+
+        let mut volume_names = BTreeSet::new();
+
+        for volume in volumes {
+            if volume.name == EXECUTABLE_VOLUME_NAME {
+                return Err(invalid(format!(
+                    "volume name {} is reserved",
+                    EXECUTABLE_VOLUME_NAME
+                )));
+            }
+
+            if !volume_names.insert(&volume.name) {
+                return Err(invalid(format!("duplicate volume {}", volume.name)));
+            }
+        }
+
+        for volume_mount in volume_mounts {
+            if !volume_names.contains(&volume_mount.name) {
+                return Err(invalid(format!(
+                    "volume mount refers to missing volume {}",
+                    volume_mount.name
+                )));
+            }
+
+            if volume_mount.mount_path.trim_end_matches('/') == shared_volume_path {
+                return Err(invalid(format!(
+                    "mount path {} is reserved",
+                    shared_volume_path
+                )));
+            }
+
+            // A mount at or below the copy of the flowmium executable would shadow it in the task's container
+            if Path::new(&volume_mount.mount_path).starts_with(&executable_path) {
+                return Err(invalid(format!(
+                    "mount path {} would shadow the flowmium executable",
+                    volume_mount.mount_path
+                )));
+            }
         }
     }
 
-    async fn delete_all_pods() {
-        let client = get_kubernetes_client().await.unwrap();
+    Ok(())
+}
 
-        let pods_api: Api<Pod> = Api::namespaced(client, "default");
+fn is_valid_image_reference(image: &str) -> bool {
+    !image.is_empty()
+        && image.len() <= 255
+        && image
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:@".contains(c))
+        && image.starts_with(|c: char| c.is_ascii_alphanumeric())
+}
 
-        pods_api
-            .delete_collection(&DeleteParams::default(), &ListParams::default())
-            .await
-            .unwrap();
+/// Bucket names as accepted by S3, 3 to 63 lowercase alphanumeric characters, `-` or `.` starting and ending
+/// with an alphanumeric character.
+fn is_valid_bucket_name(bucket_name: &str) -> bool {
+    (3..=63).contains(&bucket_name.len())
+        && bucket_name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && bucket_name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && bucket_name.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && !bucket_name.contains("..")
+}
+
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+fn check_idempotency_key(idempotency_key: &str) -> Result<(), ExecutorError> {
+    if idempotency_key.is_empty() || idempotency_key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(ExecutorError::InvalidIdempotencyKey(format!(
+            "must be between 1 and {MAX_IDEMPOTENCY_KEY_LEN} characters long"
+        )));
     }
 
-    async fn delete_all_objects(config: &ExecutorConfig) -> Box<Bucket> {
-        let bucket = get_bucket(
-            &config.access_key,
-            &config.secret_key,
-            &config.bucket_name,
-            config.store_url.clone(),
-        )
-        .await
-        .unwrap();
+    Ok(())
+}
 
-        let object_list = bucket
-            .list("".to_string(), None)
-            .await
-            .unwrap()
-            .get(0)
-            .unwrap()
-            .contents
-            .clone();
+fn check_flow_env(flow: &Flow) -> Result<(), ExecutorError> {
+    match flow
+        .env
+        .iter()
+        .find(|env| matches!(env, EnvVar::InputRef(_)))
+    {
+        Some(env) => Err(ExecutorError::InvalidFlowEnv(format!(
+            "{} refers to an input, only tasks can have variables with values from inputs",
+            get_env_name(env)
+        ))),
+        None => Ok(()),
+    }
+}
 
-        for obj in object_list {
-            bucket.delete_object(obj.key).await.unwrap();
+fn check_bucket_name(flow: &Flow) -> Result<(), ExecutorError> {
+    match &flow.bucket_name {
+        Some(bucket_name) if !is_valid_bucket_name(bucket_name) => {
+            Err(ExecutorError::InvalidBucketName(bucket_name.to_owned()))
         }
+        _ => Ok(()),
+    }
+}
 
-        bucket
+fn check_init_container_image(flow: &Flow) -> Result<(), ExecutorError> {
+    match &flow.init_container_image {
+        Some(image) if !is_valid_image_reference(image) => {
+            Err(ExecutorError::InvalidInitContainerImage(image.to_owned()))
+        }
+        _ => Ok(()),
     }
+}
 
-    async fn delete_all_jobs() {
-        let client = get_kubernetes_client().await.unwrap();
+fn check_on_failure_task(flow: &Flow) -> Result<(), ExecutorError> {
+    let Some(on_failure) = &flow.on_failure else {
+        return Ok(());
+    };
 
-        let jobs_api: Api<Job> = Api::namespaced(client, "default");
+    let invalid =
+        |reason: &str| ExecutorError::InvalidOnFailureTask(on_failure.clone(), reason.to_owned());
 
-        jobs_api
-            .delete_collection(&DeleteParams::default(), &ListParams::default())
-            .await
-            .unwrap();
+    let Some(task_id) = flow.get_on_failure_task_id() else {
+        return Err(invalid("task does not exist"));
+    };
+
+    if !flow.tasks[task_id].depends.is_empty() {
+        return Err(invalid("task cannot depend on other tasks"));
     }
 
-    async fn get_contents(bucket: &Bucket, path: String) -> String {
-        let response_data = bucket.get_object(path).await.unwrap();
+    if flow
+        .tasks
+        .iter()
+        .any(|task| task.depends.contains(on_failure))
+    {
+        return Err(invalid("other tasks cannot depend on it"));
+    }
 
-        std::str::from_utf8(response_data.bytes())
-            .unwrap()
-            .to_owned()
+    if flow.tasks.len() == 1 {
+        return Err(invalid("flow has no other tasks"));
     }
 
-    fn test_flow() -> Flow {
-        Flow {
-            name: "hello-world".to_owned(),
-            tasks: vec![
-                Task {
-                    name: "task-e".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec![],
-                    cmd: vec![
-                        "sh".to_string(),
-                        "-c".to_string(),
-                        "echo $GREETINGS >> /greetings-foobar".to_string(),
-                    ],
-                    env: vec![
-                        EnvVar::SecretRef(SecretRef{name: "GREETINGS".to_string(), from_secret: "test-greetings-secret".to_string()})
-                    ],
-                    inputs: None,
-                    outputs: Some(vec![Output {
-                        name: "OutputFromTaskE".to_string(),
-                        path: "/greetings-foobar".to_string(),
-                    }]),
-                },
-                Task {
-                    name: "task-b".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec!["task-d".to_string()],
-                    cmd: vec![
-                        "sh".to_string(),
-                        "-c".to_string(),
-                        "cat /task-d-output | sed 's/\\bfoobar\\b/world/g' > /hello-world"
-                            .to_string(),
-                    ],
-                    env: vec![],
-                    inputs: Some(vec![Input {
-                        from: "OutputFromTaskD".to_string(),
-                        path: "/task-d-output".to_string(),
-                    }]),
-                    outputs: Some(vec![Output {
-                        name: "OutputFromTaskB".to_string(),
-                        path: "/hello-world".to_string(),
-                    }]),
-                },
-                Task {
-                    name: "task-a".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec![
-                        "task-b".to_string(),
-                        "task-c".to_string(),
-                        "task-d".to_string(),
-                        "task-e".to_string(),
-                    ],
-                    cmd: vec![
-                        "sh".to_string(),
-                        "-c".to_string(),
-                        "echo `cat /task-b-output` `cat /task-c-output` `cat /task-d-output` `cat /task-e-output` > /concat-all"
-                            .to_string(),
-                    ],
-                    env: vec![],
-                    inputs: Some(vec![
-                        Input {
-                            from: "OutputFromTaskB".to_string(),
-                            path: "/task-b-output".to_string(),
-                        },
-                        Input {
-                            from: "OutputFromTaskC".to_string(),
-                            path: "/task-c-output".to_string(),
-                        },
-                        Input {
-                            from: "OutputFromTaskD".to_string(),
-                            path: "/task-d-output".to_string(),
+    if flow
+        .tasks
+        .iter()
+        .any(|task| task.on != RunCondition::OnSuccess)
+    {
+        return Err(invalid("flow has tasks with a run condition"));
+    }
+
+    Ok(())
+}
+
+/// Longest name of a task's job ([`get_job_name`]). Names of jobs can be longer than this, but Kubernetes also sets
+/// the name of the job as the value of a label of its pods, and values of labels are at most 63 characters.
+const MAX_JOB_NAME_LEN: usize = 63;
+
+/// Longest task name that fits in a job name with any flow ID. The flow's name is not part of the job name.
+const MAX_TASK_NAME_LEN: usize = MAX_JOB_NAME_LEN - "flow--task-".len() - 10;
+
+fn is_rfc1123_label(name: &str) -> bool {
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    name.starts_with(is_alphanumeric)
+        && name.ends_with(is_alphanumeric)
+        && name.chars().all(|c| is_alphanumeric(c) || c == '-')
+}
+
+/// Longest flow name, names of flows can be part of keys of artefacts ([`crate::task::layout::StoreLayout`]),
+/// which are at most 1024 bytes in S3.
+const MAX_FLOW_NAME_LEN: usize = 128;
+
+fn check_flow_name(flow: &Flow) -> Result<(), ExecutorError> {
+    let invalid =
+        |reason: &str| ExecutorError::InvalidFlowName(flow.name.clone(), reason.to_owned());
+
+    if flow.name.is_empty() || flow.name.len() > MAX_FLOW_NAME_LEN {
+        return Err(invalid(&format!(
+            "has to be between 1 and {} characters",
+            MAX_FLOW_NAME_LEN
+        )));
+    }
+
+    // Names are pasted into keys of artefacts as they are, so they cannot add or escape segments of keys
+    if !flow
+        .name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        || flow.name == "."
+        || flow.name == ".."
+    {
+        return Err(invalid(
+            "has to consist of alphanumeric characters, '-', '_' or '.', and cannot be '.' or '..'",
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_task_names(flow: &Flow) -> Result<(), ExecutorError> {
+    let mut names = BTreeSet::new();
+
+    for task in flow.tasks.iter() {
+        let invalid =
+            |reason: &str| ExecutorError::InvalidTaskName(task.name.clone(), reason.to_owned());
+
+        if !is_rfc1123_label(&task.name) {
+            return Err(invalid(
+                "has to consist of lowercase alphanumeric characters or '-', and start and end with an alphanumeric character",
+            ));
+        }
+
+        if task.name.len() > MAX_TASK_NAME_LEN {
+            return Err(invalid(&format!(
+                "longer than {} characters, name of its job {} would be longer than {} characters",
+                MAX_TASK_NAME_LEN,
+                get_job_name(i32::MAX, task),
+                MAX_JOB_NAME_LEN
+            )));
+        }
+
+        if !names.insert(&task.name) {
+            return Err(invalid("not unique"));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_deadline_seconds(flow: &Flow) -> Result<(), ExecutorError> {
+    let Some(deadline_seconds) = flow.deadline_seconds else {
+        return Ok(());
+    };
+
+    if deadline_seconds == 0 {
+        return Err(ExecutorError::InvalidFlowDeadline(
+            "has to be at least one second".to_owned(),
+        ));
+    }
+
+    if i64::try_from(deadline_seconds).is_err() {
+        return Err(ExecutorError::InvalidFlowDeadline(format!(
+            "has to be at most {} seconds",
+            i64::MAX
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_max_parallel_tasks(flow: &Flow) -> Result<(), ExecutorError> {
+    let Some(max_parallel_tasks) = flow.max_parallel_tasks else {
+        return Ok(());
+    };
+
+    if max_parallel_tasks == 0 {
+        return Err(ExecutorError::InvalidMaxParallelTasks(
+            "has to be at least one".to_owned(),
+        ));
+    }
+
+    if i32::try_from(max_parallel_tasks).is_err() {
+        return Err(ExecutorError::InvalidMaxParallelTasks(format!(
+            "has to be at most {}",
+            i32::MAX
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_flow_interval(flow: &Flow) -> Result<(), ExecutorError> {
+    let Some(interval_seconds) = flow.interval_seconds else {
+        return Ok(());
+    };
+
+    if interval_seconds == 0 {
+        return Err(ExecutorError::InvalidFlowInterval(
+            "interval has to be at least one second".to_owned(),
+        ));
+    }
+
+    if !flow.secrets.is_empty() {
+        return Err(ExecutorError::InvalidFlowInterval(
+            "periodic flows cannot have inline secrets".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_flow_schedule(flow: &Flow) -> Result<(), ExecutorError> {
+    let Some(schedule) = &flow.schedule else {
+        return Ok(());
+    };
+
+    if flow.interval_seconds.is_some() {
+        return Err(ExecutorError::InvalidFlowSchedule(
+            "recurring flows cannot have an interval".to_owned(),
+        ));
+    }
+
+    if !flow.secrets.is_empty() {
+        return Err(ExecutorError::InvalidFlowSchedule(
+            "recurring flows cannot have inline secrets".to_owned(),
+        ));
+    }
+
+    let next_run_at = parse_cron_schedule(schedule)
+        .map_err(|error| ExecutorError::InvalidFlowSchedule(format!("{}: {}", schedule, error)))?
+        .after(&Utc::now())
+        .next();
+
+    if next_run_at.is_none() {
+        return Err(ExecutorError::InvalidFlowSchedule(format!(
+            "{}: never runs again",
+            schedule
+        )));
+    }
+
+    Ok(())
+}
+
+async fn check_secrets_exist(flow: &Flow, secrets: &SecretsCrud) -> Result<(), ExecutorError> {
+    let envs = flow
+        .env
+        .iter()
+        .chain(flow.tasks.iter().flat_map(|task| task.env.iter()));
+
+    for env in envs {
+        if let EnvVar::SecretRef(SecretRef { from_secret, .. }) = env {
+            if !flow.secrets.contains_key(from_secret)
+                && !secrets.secret_exists(from_secret).await?
+            {
+                return Err(ExecutorError::MissingSecret(from_secret.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prefix of environment variables set by flowmium for the task's sidecar, these cannot be overridden.
+const RESERVED_ENV_PREFIX: &str = "FLOWMIUM_";
+
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(RESERVED_ENV_PREFIX)
+}
+
+fn get_env_name(env: &EnvVar) -> &str {
+    match env {
+        EnvVar::KeyValuePair(KeyValuePair { name, .. }) => name,
+        EnvVar::SecretRef(SecretRef { name, .. }) => name,
+        EnvVar::K8sSecretRef(K8sSecretRef { name, .. }) => name,
+        EnvVar::InputRef(InputRef { name, .. }) => name,
+    }
+}
+
+fn apply_env_overrides(flow: &mut Flow, overrides: &EnvOverrides) -> Result<(), ExecutorError> {
+    if let Some(name) = overrides.env.keys().find(|name| !is_valid_env_name(name)) {
+        return Err(ExecutorError::InvalidEnvOverride(format!(
+            "invalid environment variable name {}",
+            name
+        )));
+    }
+
+    if let Some(task_name) = overrides
+        .tasks
+        .iter()
+        .find(|task_name| !flow.tasks.iter().any(|task| &task.name == *task_name))
+    {
+        return Err(ExecutorError::InvalidEnvOverride(format!(
+            "task {} does not exist",
+            task_name
+        )));
+    }
+
+    for task in flow
+        .tasks
+        .iter_mut()
+        .filter(|task| overrides.tasks.is_empty() || overrides.tasks.contains(&task.name))
+    {
+        for (name, value) in overrides.env.iter() {
+            let env = EnvVar::KeyValuePair(KeyValuePair {
+                name: name.clone(),
+                value: value.clone(),
+            });
+
+            match task.env.iter_mut().find(|env| get_env_name(env) == name) {
+                Some(existing_env) => *existing_env = env,
+                None => task.env.push(env),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Retry a failed flow, re-running only tasks that failed or did not run and reusing outputs of finished tasks,
+/// see [`crate::scheduler::Scheduler::retry_flow`]. Jobs and pods of tasks that are run again are deleted first.
+/// Inline secrets of the flow are deleted once it has failed, so tasks referring to them fail to spawn again.
+#[tracing::instrument(skip(sched, config))]
+pub async fn retry_failed_flow(
+    flow_id: i32,
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+) -> Result<(), ExecutorError> {
+    let record = sched.get_flow(flow_id).await?;
+
+    if record.status != FlowStatus::Failed {
+        return Err(SchedulerError::FlowNotFailed(flow_id).into());
+    }
+
+    let flow = sched.get_flow_definition(flow_id).await?;
+
+    try_join_all(
+        flow.tasks
+            .iter()
+            .enumerate()
+            .map(|(task_id, task)| (task_id as i32, task))
+            .filter(|(task_id, _)| {
+                !record.finished_tasks.contains(task_id) || record.on_failure_task == Some(*task_id)
+            })
+            .map(|(task_id, task)| delete_task_job(flow_id, task_id, task, config)),
+    )
+    .await?;
+
+    sched.retry_flow(flow_id).await?;
+
+    Ok(())
+}
+
+/// Create a new workflow from the definition of an existing flow, with environment variables overridden.
+/// The existing flow can be in any state. Inline secrets of the existing flow are not carried over,
+/// they have to be overridden or exist as secrets stored in the server.
+#[tracing::instrument(skip(sched, secrets, config))]
+pub async fn rerun_flow(
+    flow_id: i32,
+    overrides: &EnvOverrides,
+    sched: &Scheduler,
+    secrets: &SecretsCrud,
+    config: &ExecutorConfig,
+) -> Result<i32, ExecutorError> {
+    let mut flow = sched.get_flow_definition(flow_id).await?;
+
+    apply_env_overrides(&mut flow, overrides)?;
+
+    instantiate_flow(flow, sched, secrets, config).await
+}
+
+/// Validate the flow definition without touching the database and construct the plan it will be run with.
+pub(crate) fn validate_and_plan_flow(
+    flow: &Flow,
+    config: &ExecutorConfig,
+) -> Result<Plan, ExecutorError> {
+    check_flow_name(flow)?;
+    check_task_names(flow)?;
+
+    let plan = construct_plan(&flow.tasks)?;
+
+    check_on_failure_task(flow)?;
+    check_task_timeouts(flow)?;
+    check_termination_grace_periods(flow)?;
+    check_backoff_limits(flow)?;
+    check_eager_inputs(flow)?;
+    check_flow_interval(flow)?;
+    check_flow_schedule(flow)?;
+    check_max_parallel_tasks(flow)?;
+    check_deadline_seconds(flow)?;
+    check_init_container_image(flow)?;
+    check_bucket_name(flow)?;
+    check_flow_env(flow)?;
+    check_task_volumes(flow, config)?;
+    check_pod_annotations(flow)?;
+
+    // The on failure task only runs when another task fails, so it is not part of the plan
+    let plan = match flow.get_on_failure_task_id() {
+        Some(task_id) => plan.without_task(task_id),
+        None => plan,
+    };
+
+    Ok(plan)
+}
+
+/// Abort a running or pending flow and delete the jobs and pods of its tasks. The flow is marked as aborted before
+/// its tasks are deleted, so that the executor does not spawn more tasks of the flow in the meantime.
+#[tracing::instrument(skip(sched, config))]
+pub async fn abort_flow(
+    flow_id: i32,
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+) -> Result<(), ExecutorError> {
+    sched.mark_flow_aborted(flow_id).await?;
+
+    delete_flow_jobs(flow_id, sched, config).await
+}
+
+async fn delete_flow_jobs(
+    flow_id: i32,
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+) -> Result<(), ExecutorError> {
+    let flow = sched.get_flow_definition(flow_id).await?;
+
+    try_join_all(
+        flow.tasks
+            .iter()
+            .enumerate()
+            .map(|(task_id, task)| delete_task_job(flow_id, task_id as i32, task, config)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Abort flows that have not terminated within their deadline ([`crate::model::Flow::deadline_seconds`]), marking
+/// their running tasks as failed and deleting the jobs and pods of their tasks.
+#[tracing::instrument(skip(sched, config))]
+async fn abort_flows_past_deadline(sched: &Scheduler, config: &ExecutorConfig) {
+    let Ok(flow_ids) = sched.get_flows_past_deadline().await else {
+        return;
+    };
+
+    for flow_id in flow_ids {
+        tracing::warn!(flow_id, "Flow has exceeded its deadline, aborting");
+
+        // Errors are logged
+        let _ = async {
+            sched.mark_flow_deadline_exceeded(flow_id).await?;
+            delete_flow_jobs(flow_id, sched, config).await
+        }
+        .await;
+    }
+}
+
+/// Create a workflow in pending state that will start running eventually by calling [`crate::executor::schedule_and_run_tasks`].
+/// Every secret referred by the flow is expected to exist at the time of creation, either as a secret stored in the server
+/// or as one of the flow's inline secrets ([`crate::model::Flow::secrets`]).
+#[tracing::instrument(skip(sched, flow, secrets, config))]
+pub async fn instantiate_flow(
+    flow: Flow,
+    sched: &Scheduler,
+    secrets: &SecretsCrud,
+    config: &ExecutorConfig,
+) -> Result<i32, ExecutorError> {
+    instantiate_flow_with_idempotency_key(flow, None, sched, secrets, config).await
+}
+
+/// Create a workflow like [`instantiate_flow`], unless a flow was already created with the same `idempotency_key`,
+/// in which case ID of the existing flow is returned. This allows clients to retry a submission without creating
+/// duplicate flows. Idempotency keys can be at most 255 characters long.
+#[tracing::instrument(skip(sched, flow, secrets, config))]
+pub async fn instantiate_flow_with_idempotency_key(
+    flow: Flow,
+    idempotency_key: Option<&str>,
+    sched: &Scheduler,
+    secrets: &SecretsCrud,
+    config: &ExecutorConfig,
+) -> Result<i32, ExecutorError> {
+    if let Some(idempotency_key) = idempotency_key {
+        check_idempotency_key(idempotency_key)?;
+    }
+
+    let plan = validate_and_plan_flow(&flow, config)?;
+
+    check_secrets_exist(&flow, secrets).await?;
+
+    tracing::info!(flow_name = flow.name, plan = ?plan, "Creating flow");
+    let flow_id = match idempotency_key {
+        Some(_) => {
+            sched
+                .create_flow_with_idempotency_key(flow, plan, idempotency_key)
+                .await?
+        }
+        None => sched.create_flow(flow, plan).await?,
+    };
+
+    Ok(flow_id)
+}
+
+/// Register a recurring flow ([`crate::model::Flow::schedule`]) that is created like [`instantiate_flow`] each time
+/// its schedule matches by calling [`run_recurring_flows`]. The flow is validated and its secrets are expected to exist
+/// at the time of registration, runs that fail to be created later are skipped. Returns ID of the recurring flow.
+#[tracing::instrument(skip(sched, flow, secrets, config))]
+pub async fn register_recurring_flow(
+    flow: Flow,
+    sched: &Scheduler,
+    secrets: &SecretsCrud,
+    config: &ExecutorConfig,
+) -> Result<i32, ExecutorError> {
+    validate_and_plan_flow(&flow, config)?;
+
+    check_secrets_exist(&flow, secrets).await?;
+
+    tracing::info!(
+        flow_name = flow.name,
+        schedule = flow.schedule,
+        "Registering recurring flow"
+    );
+    let template_id = sched.create_flow_template(&flow).await?;
+
+    Ok(template_id)
+}
+
+/// Create runs of recurring flows whose schedule has matched since the last call. Should be called periodically.
+#[tracing::instrument(skip(sched, secrets, config))]
+pub async fn run_recurring_flows(
+    sched: &Scheduler,
+    secrets: &SecretsCrud,
+    config: &ExecutorConfig,
+) {
+    // Errors are logged, due flows are taken again on the next call
+    let Ok(templates) = sched.take_due_flow_templates().await else {
+        return;
+    };
+
+    for (template_id, mut flow) in templates {
+        flow.schedule = None;
+
+        match instantiate_flow(flow, sched, secrets, config).await {
+            Ok(flow_id) => tracing::info!(template_id, flow_id, "Created run of recurring flow"),
+            Err(error) => {
+                tracing::error!(%error, template_id, "Unable to create run of recurring flow")
+            }
+        }
+    }
+}
+
+#[tracing::instrument(skip(sched, config, secrets))]
+async fn sched_tasks(
+    sched: &Scheduler,
+    flow_id: i32,
+    config: &ExecutorConfig,
+    secrets: &SecretsCrud,
+) -> Result<bool, ExecutorError> {
+    let (option_tasks, retrying) = match sched.schedule_on_failure_task(flow_id).await? {
+        Some(task) => (Some(vec![task]), false),
+        None => match sched.schedule_retry_tasks(flow_id).await? {
+            Some(tasks) => (Some(tasks), true),
+            None => (sched.schedule_tasks(flow_id).await?, false),
+        },
+    };
+
+    if let Some(tasks) = option_tasks {
+        let flow = sched.get_flow_spawn_record(flow_id).await?;
+
+        let mut failed_task_ids = vec![];
+
+        for (task_id, task) in tasks {
+            let spawned = async {
+                if retrying {
+                    delete_task_job(flow_id, task_id, &task, config).await?;
+                }
+
+                spawn_task(flow_id, task_id, &task, config, &flow, secrets).await
+            }
+            .await;
+
+            match spawned {
+                Ok(_) => sched.mark_task_running(flow_id, task_id).await?,
+                Err(_) => failed_task_ids.push(task_id),
+            }
+        }
+
+        // Tasks that failed to spawn are marked after the others are marked running, so that the flow stays failed
+        // if they run out of retries. Remaining tasks are still spawned as a failed task can be retried.
+        // TODO: Add test for below, without below, jobs could get stale on restart
+        for task_id in failed_task_ids {
+            sched.mark_task_failed(flow_id, task_id).await?;
+        }
+
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+async fn mark_task_with_status(
+    sched: &Scheduler,
+    flow_id: i32,
+    task_id: i32,
+    status: Result<TaskStatus, ExecutorError>,
+) -> Result<(), SchedulerError> {
+    let status = match status {
+        Ok(status) => status,
+        Err(
+            error @ (ExecutorError::ImagePullFailed(..) | ExecutorError::ContainerCreateFailed(..)),
+        ) => {
+            return sched
+                .mark_task_failed_with_reason(flow_id, task_id, &error.to_string())
+                .await
+        }
+        Err(_) => return sched.mark_task_failed(flow_id, task_id).await,
+    };
+
+    match status {
+        TaskStatus::Pending | TaskStatus::Running => Ok(()),
+        TaskStatus::Finished => sched.mark_task_finished(flow_id, task_id).await,
+        TaskStatus::Failed => sched.mark_task_failed(flow_id, task_id).await,
+        TaskStatus::TimedOut => sched.mark_task_timed_out(flow_id, task_id).await,
+    }
+}
+
+/// Mark a task with its status like [`mark_task_with_status`]. Jobs of tasks whose pod cannot run are deleted first,
+/// as Kubernetes would otherwise keep the pod pending, if that fails the task is left running to be tried again.
+async fn update_task_status(
+    sched: &Scheduler,
+    flow_id: i32,
+    task_id: i32,
+    status: Result<TaskStatus, ExecutorError>,
+    config: &ExecutorConfig,
+) -> Result<(), ExecutorError> {
+    if let Err(ExecutorError::ImagePullFailed(..) | ExecutorError::ContainerCreateFailed(..)) =
+        status
+    {
+        let flow = sched.get_flow_definition(flow_id).await?;
+
+        if let Some(task) = flow.tasks.get(task_id as usize) {
+            delete_task_job(flow_id, task_id, task, config).await?;
+        }
+    }
+
+    Ok(mark_task_with_status(sched, flow_id, task_id, status).await?)
+}
+
+/// Status of each of the running tasks of a flow like [`get_statuses_for_flow`], except that tasks without a pod
+/// take the status of their job, see [`reconcile_task_status`].
+#[tracing::instrument(skip(sched, config))]
+async fn get_reconciled_statuses_for_flow(
+    sched: &Scheduler,
+    flow_id: i32,
+    running_tasks: &[i32],
+    config: &ExecutorConfig,
+) -> Result<BTreeMap<i32, Result<TaskStatus, ExecutorError>>, ExecutorError> {
+    let statuses = get_statuses_for_flow(flow_id, running_tasks, config).await?;
+    let flow = sched.get_flow_definition(flow_id).await?;
+
+    let client = get_kubernetes_client().await?;
+    let jobs: Api<Job> = Api::namespaced(client, &config.namespace);
+
+    let mut reconciled = BTreeMap::new();
+
+    for (task_id, status) in statuses {
+        let job = match (&status, flow.tasks.get(task_id as usize)) {
+            (Err(ExecutorError::UnexpectedRunnerState(..)), Some(task)) => {
+                match jobs.get_opt(&get_job_name(flow_id, task)).await {
+                    Ok(job) => job,
+                    Err(error) => {
+                        tracing::error!(%error, task_id, "Unable to get job of task");
+                        return Err(ExecutorError::UnableToConnectToKubernetes(error));
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        reconciled.insert(task_id, reconcile_task_status(status, job.as_ref()));
+    }
+
+    Ok(reconciled)
+}
+
+/// Reconcile running tasks of flows with the jobs of the tasks, should be called once when the server starts. Pods
+/// of tasks that finished while the server was down may have been garbage collected, these tasks are marked with
+/// the status of their job instead of failing for not having a pod.
+#[tracing::instrument(skip(sched, config))]
+pub async fn reconcile_running_tasks(sched: &Scheduler, config: &ExecutorConfig) {
+    let Ok(flows) = sched
+        .get_running_or_pending_flow_ids(config.flow_ordering)
+        .await
+    else {
+        return;
+    };
+
+    for (flow_id, running_tasks) in flows {
+        if running_tasks.is_empty() {
+            continue;
+        }
+
+        // Errors are logged, statuses of the flow's tasks are fetched by the scheduler loop instead
+        let Ok(statuses) =
+            get_reconciled_statuses_for_flow(sched, flow_id, &running_tasks, config).await
+        else {
+            continue;
+        };
+
+        for (task_id, status) in statuses {
+            if (update_task_status(sched, flow_id, task_id, status, config).await).is_err() {
+                break;
+            };
+        }
+    }
+}
+
+/// Spawn jobs to make progress pending tasks. Should be called periodically.
+#[tracing::instrument(skip(sched, config, secrets))]
+pub async fn schedule_and_run_tasks(
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+    secrets: &SecretsCrud,
+) {
+    // Errors are logged, creating the next runs is retried on the next call
+    let _ = sched.rerun_periodic_flows().await;
+
+    abort_flows_past_deadline(sched, config).await;
+
+    if let Ok(flows) = sched
+        .get_running_or_pending_flow_ids(config.flow_ordering)
+        .await
+    {
+        for (flow_id, running_tasks) in flows {
+            match sched_tasks(sched, flow_id, config, secrets).await {
+                Ok(true) => continue,
+                Ok(false) => (),
+                Err(_) => break,
+            }
+
+            // Errors are logged, statuses of the flow's tasks are fetched again on the next call
+            let Ok(statuses) = get_statuses_for_flow(flow_id, &running_tasks, config).await else {
+                continue;
+            };
+
+            for (task_id, status) in statuses {
+                if (update_task_status(sched, flow_id, task_id, status, config).await).is_err() {
+                    break;
+                };
+            }
+        }
+    }
+
+    // Errors are logged, deleting is retried on the next call
+    let _ = secrets.delete_terminated_flow_secrets().await;
+}
+#[cfg(test)]
+mod tests {
+
+    use std::{collections::BTreeMap, time::Duration};
+
+    use futures::StreamExt;
+    use k8s_openapi::api::core::v1::{Volume, VolumeMount};
+    use s3::Bucket;
+    use serial_test::serial;
+
+    use crate::{
+        server::{
+            logs::stream_task_logs,
+            model::{FlowBuilder, InlineSecrets, Input, Output, TaskBuilder},
+            pool::get_test_pool,
+        },
+        task::bucket::get_bucket,
+    };
+
+    use super::*;
+
+    async fn get_task_status(
+        flow_id: i32,
+        task_id: i32,
+        config: &ExecutorConfig,
+    ) -> Result<TaskStatus, ExecutorError> {
+        get_statuses_for_flow(flow_id, &[task_id], config)
+            .await?
+            .remove(&task_id)
+            .unwrap()
+    }
+
+    fn test_executor_config() -> ExecutorConfig {
+        ExecutorConfig {
+            store_url: "http://localhost:9000".to_owned(),
+            task_store_url: "http://172.16.238.4:9000".to_owned(),
+            bucket_name: "flowmium-test".to_owned(),
+            bucket_region: None,
+            bucket_path_style: None,
+            access_key: "minio".to_owned(),
+            secret_key: "password".to_owned(),
+            init_container_image: "registry:5000/flowmium-debug".to_owned(),
+            namespace: "default".to_owned(),
+            flow_id_label: default_flow_label(),
+            task_id_label: default_task_label(),
+            flow_ordering: FlowOrdering::Fifo,
+            max_concurrent_secret_fetches: default_max_concurrent_secret_fetches(),
+            task_store_retry_count: default_task_store_retry_count(),
+            compress_artefacts: false,
+            spawn_retry_count: default_spawn_retry_count(),
+            scheduler_interval_ms: default_scheduler_interval_ms(),
+            store_layout: StoreLayout::default(),
+            image_pull_grace_seconds: default_image_pull_grace_seconds(),
+            image_pull_secrets: vec![],
+            flowmium_binary_path: default_flowmium_binary_path(),
+            shared_volume_path: default_shared_volume_path(),
+            job_ttl_seconds_after_finished: None,
+        }
+    }
+
+    async fn delete_all_pods() {
+        let client = get_kubernetes_client().await.unwrap();
+
+        let pods_api: Api<Pod> = Api::namespaced(client, "default");
+
+        pods_api
+            .delete_collection(&DeleteParams::default(), &ListParams::default())
+            .await
+            .unwrap();
+    }
+
+    async fn delete_all_objects(config: &ExecutorConfig) -> Box<Bucket> {
+        let bucket = get_bucket(
+            &config.access_key,
+            &config.secret_key,
+            &config.bucket_name,
+            config.store_url.clone(),
+            config.bucket_region.as_deref(),
+            config.bucket_path_style,
+        )
+        .await
+        .unwrap();
+
+        let object_list = bucket
+            .list("".to_string(), None)
+            .await
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .contents
+            .clone();
+
+        for obj in object_list {
+            bucket.delete_object(obj.key).await.unwrap();
+        }
+
+        bucket
+    }
+
+    async fn delete_all_jobs() {
+        let client = get_kubernetes_client().await.unwrap();
+
+        let jobs_api: Api<Job> = Api::namespaced(client, "default");
+
+        jobs_api
+            .delete_collection(&DeleteParams::default(), &ListParams::default())
+            .await
+            .unwrap();
+    }
+
+    async fn get_contents(bucket: &Bucket, path: String) -> String {
+        let response_data = bucket.get_object(path).await.unwrap();
+
+        std::str::from_utf8(response_data.bytes())
+            .unwrap()
+            .to_owned()
+    }
+
+    fn test_flow() -> Flow {
+        Flow {
+            name: "hello-world".to_owned(),
+            tasks: vec![
+                Task {
+                    name: "task-e".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec![],
+                    cmd: vec![
+                        "sh".to_string(),
+                        "-c".to_string(),
+                        "echo $GREETINGS >> /greetings-foobar".to_string(),
+                    ],
+                    env: vec![
+                        EnvVar::SecretRef(SecretRef{name: "GREETINGS".to_string(), from_secret: "test-greetings-secret".to_string()})
+                    ],
+                    inputs: None,
+                    outputs: Some(vec![Output {
+                        name: "OutputFromTaskE".to_string(),
+                        path: "/greetings-foobar".to_string(),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+                Task {
+                    name: "task-b".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec!["task-d".to_string()],
+                    cmd: vec![
+                        "sh".to_string(),
+                        "-c".to_string(),
+                        "cat /task-d-output | sed 's/\\bfoobar\\b/world/g' > /hello-world"
+                            .to_string(),
+                    ],
+                    env: vec![],
+                    inputs: Some(vec![Input {
+                        from: "OutputFromTaskD".to_string(),
+                        path: "/task-d-output".to_string(),
+                    }]),
+                    outputs: Some(vec![Output {
+                        name: "OutputFromTaskB".to_string(),
+                        path: "/hello-world".to_string(),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+                Task {
+                    name: "task-a".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec![
+                        "task-b".to_string(),
+                        "task-c".to_string(),
+                        "task-d".to_string(),
+                        "task-e".to_string(),
+                    ],
+                    cmd: vec![
+                        "sh".to_string(),
+                        "-c".to_string(),
+                        "echo `cat /task-b-output` `cat /task-c-output` `cat /task-d-output` `cat /task-e-output` > /concat-all"
+                            .to_string(),
+                    ],
+                    env: vec![],
+                    inputs: Some(vec![
+                        Input {
+                            from: "OutputFromTaskB".to_string(),
+                            path: "/task-b-output".to_string(),
+                        },
+                        Input {
+                            from: "OutputFromTaskC".to_string(),
+                            path: "/task-c-output".to_string(),
+                        },
+                        Input {
+                            from: "OutputFromTaskD".to_string(),
+                            path: "/task-d-output".to_string(),
                         },
                         Input {
                             from: "OutputFromTaskE".to_string(),
@@ -599,7 +2406,9 @@ mod tests {
                     outputs: Some(vec![Output {
                         name: "OutputFromTaskA".to_string(),
                         path: "/concat-all".to_string(),
+                        ..Default::default()
                     }]),
+                    ..Default::default()
                 },
                 Task {
                     name: "task-d".to_string(),
@@ -613,159 +2422,2048 @@ mod tests {
                     ],
                     env: vec![],
                     inputs: Some(vec![Input {
-                        from: "OutputFromTaskE".to_string(),
-                        path: "/inputs/testing/task-e-output".to_string(),
+                        from: "OutputFromTaskE".to_string(),
+                        path: "/inputs/testing/task-e-output".to_string(),
+                    }]),
+                    outputs: Some(vec![Output {
+                        name: "OutputFromTaskD".to_string(),
+                        path: "/hello-foobar".to_string(),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+                Task {
+                    name: "task-c".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec!["task-d".to_string()],
+                    cmd: vec![
+                        "sh".to_string(),
+                        "-c".to_string(),
+                        "cat /task-d-output | sed 's/\\bfoobar\\b/mars/g' > /hello-mars"
+                            .to_string(),
+                    ],
+                    env: vec![],
+                    inputs: Some(vec![Input {
+                        from: "OutputFromTaskD".to_string(),
+                        path: "/task-d-output".to_string(),
                     }]),
                     outputs: Some(vec![Output {
-                        name: "OutputFromTaskD".to_string(),
-                        path: "/hello-foobar".to_string(),
+                        name: "OutputFromTaskC".to_string(),
+                        path: "/hello-mars".to_string(),
+                        ..Default::default()
                     }]),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_schedule_and_run_tasks() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        secrets
+            .create_secret("test-greetings-secret", "Greetings foobar")
+            .await
+            .unwrap();
+
+        // delete_all_pods().await;
+        // delete_all_jobs().await;
+        let bucket = delete_all_objects(&config).await;
+
+        let flow_id = instantiate_flow(test_flow(), &sched, &secrets, &config)
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            schedule_and_run_tasks(&sched, &config, &secrets).await;
+        }
+
+        for task_id in 0..5 {
+            assert_eq!(
+                get_task_status(flow_id, task_id, &config).await.unwrap(),
+                TaskStatus::Finished
+            )
+        }
+
+        assert_eq!(
+            get_contents(&bucket, format!("{}/OutputFromTaskA", flow_id)).await,
+            "Hello world Hello mars Hello foobar Greetings foobar\n"
+        );
+        assert_eq!(
+            get_contents(&bucket, format!("{}/OutputFromTaskB", flow_id)).await,
+            "Hello world\n"
+        );
+        assert_eq!(
+            get_contents(&bucket, format!("{}/OutputFromTaskC", flow_id)).await,
+            "Hello mars\n"
+        );
+        assert_eq!(
+            get_contents(&bucket, format!("{}/OutputFromTaskD", flow_id)).await,
+            "Hello foobar\n"
+        );
+        assert_eq!(
+            get_contents(&bucket, format!("{}/OutputFromTaskE", flow_id)).await,
+            "Greetings foobar\n"
+        );
+
+        let logs: Vec<String> = stream_task_logs(flow_id, 0, false, &sched, &config)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(!logs.is_empty());
+    }
+
+    fn test_flow_fail() -> Flow {
+        Flow {
+            name: "hello-world".to_owned(),
+            tasks: vec![
+                Task {
+                    name: "task-one".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec!["task-two".to_string()],
+                    cmd: vec!["exit".to_string(), "1".to_string()],
+                    env: vec![],
+                    inputs: None,
+                    outputs: None,
+                    ..Default::default()
+                },
+                Task {
+                    name: "task-zero".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec!["task-one".to_string()],
+                    cmd: vec!["sleep".to_string(), "0.01".to_string()],
+                    env: vec![],
+                    inputs: None,
+                    outputs: None,
+                    ..Default::default()
+                },
+                Task {
+                    name: "task-two".to_string(),
+                    image: "ubuntu:latest".to_string(),
+                    depends: vec![],
+                    cmd: vec!["sleep".to_string(), "0.01".to_string()],
+                    env: vec![],
+                    inputs: None,
+                    outputs: None,
+                    ..Default::default()
                 },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_schedule_and_run_tasks_fail() {
+        delete_all_pods().await;
+        delete_all_jobs().await;
+
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let flow_id = instantiate_flow(test_flow_fail(), &sched, &secrets, &config)
+            .await
+            .unwrap();
+
+        for _ in 0..30 {
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            schedule_and_run_tasks(&sched, &config, &secrets).await;
+        }
+
+        assert_eq!(
+            get_task_status(flow_id, 2, &config).await.unwrap(),
+            TaskStatus::Finished
+        );
+
+        assert_eq!(
+            get_task_status(flow_id, 0, &config).await.unwrap(),
+            TaskStatus::Failed
+        );
+
+        match get_task_status(flow_id, 1, &config).await {
+            Err(ExecutorError::UnexpectedRunnerState(..)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_instantiate_flow_missing_secret() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        match instantiate_flow(test_flow(), &sched, &secrets, &config).await {
+            Err(ExecutorError::MissingSecret(key)) => assert_eq!(key, "test-greetings-secret"),
+            _ => panic!(),
+        }
+
+        assert_eq!(sched.list_flows().await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_instantiate_flow_inline_secret() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let flow = Flow {
+            secrets: InlineSecrets(BTreeMap::from([(
+                "test-greetings-secret".to_owned(),
+                "Greetings inline".to_owned(),
+            )])),
+            ..test_flow()
+        };
+
+        let flow_id = instantiate_flow(flow, &sched, &secrets, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            secrets
+                .get_flow_secret(flow_id, "test-greetings-secret")
+                .await
+                .unwrap(),
+            "Greetings inline"
+        );
+        assert!(!secrets
+            .secret_exists("test-greetings-secret")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rerun_flow() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let flow = Flow {
+            secrets: InlineSecrets(BTreeMap::from([(
+                "test-greetings-secret".to_owned(),
+                "Greetings inline".to_owned(),
+            )])),
+            ..test_flow()
+        };
+
+        let flow_id = instantiate_flow(flow, &sched, &secrets, &config)
+            .await
+            .unwrap();
+
+        match rerun_flow(flow_id, &EnvOverrides::default(), &sched, &secrets, &config).await {
+            Err(ExecutorError::MissingSecret(key)) => assert_eq!(key, "test-greetings-secret"),
+            _ => panic!(),
+        }
+
+        let overrides = EnvOverrides {
+            env: BTreeMap::from([
+                ("GREETINGS".to_owned(), "Hi".to_owned()),
+                ("LOG_LEVEL".to_owned(), "debug".to_owned()),
+            ]),
+            tasks: vec!["task-e".to_owned()],
+        };
+
+        let rerun_flow_id = rerun_flow(flow_id, &overrides, &sched, &secrets, &config)
+            .await
+            .unwrap();
+
+        let rerun_definition = sched.get_flow_definition(rerun_flow_id).await.unwrap();
+        let original_definition = sched.get_flow_definition(flow_id).await.unwrap();
+
+        assert_eq!(rerun_definition.name, "hello-world");
+        assert_eq!(
+            rerun_definition.tasks[0].env,
+            vec![
+                EnvVar::KeyValuePair(KeyValuePair {
+                    name: "GREETINGS".to_owned(),
+                    value: "Hi".to_owned()
+                }),
+                EnvVar::KeyValuePair(KeyValuePair {
+                    name: "LOG_LEVEL".to_owned(),
+                    value: "debug".to_owned()
+                })
+            ]
+        );
+        assert_eq!(rerun_definition.tasks[1..], original_definition.tasks[1..]);
+
+        for overrides in [
+            EnvOverrides {
+                env: BTreeMap::from([("GREETINGS".to_owned(), "Hi".to_owned())]),
+                tasks: vec!["task-z".to_owned()],
+            },
+            EnvOverrides {
+                env: BTreeMap::from([("FLOWMIUM_FLOW_ID".to_owned(), "1".to_owned())]),
+                tasks: vec![],
+            },
+            EnvOverrides {
+                env: BTreeMap::from([("LOG LEVEL".to_owned(), "debug".to_owned())]),
+                tasks: vec![],
+            },
+        ] {
+            assert!(matches!(
+                rerun_flow(flow_id, &overrides, &sched, &secrets, &config).await,
+                Err(ExecutorError::InvalidEnvOverride(_))
+            ));
+        }
+
+        assert!(matches!(
+            rerun_flow(404, &overrides, &sched, &secrets, &config).await,
+            Err(ExecutorError::UnableToCreateFlowOrMarkTask(
+                SchedulerError::FlowDoesNotExist(404)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_flow_env() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let literal = |name: &str, value: &str| {
+            EnvVar::KeyValuePair(KeyValuePair {
+                name: name.to_owned(),
+                value: value.to_owned(),
+            })
+        };
+
+        let flow = |env: Vec<EnvVar>| Flow {
+            name: "flow-env".to_owned(),
+            tasks: vec![Task {
+                name: "deploy".to_owned(),
+                image: "ubuntu:latest".to_owned(),
+                cmd: vec!["true".to_owned()],
+                env: vec![literal("LOG_LEVEL", "debug")],
+                ..Default::default()
+            }],
+            env,
+            secrets: InlineSecrets(BTreeMap::from([(
+                "test-flow-env-token".to_owned(),
+                "hunter2".to_owned(),
+            )])),
+            ..Default::default()
+        };
+
+        let flow_id = instantiate_flow(
+            flow(vec![
+                literal("ENVIRONMENT", "prod"),
+                literal("LOG_LEVEL", "info"),
+                EnvVar::SecretRef(SecretRef {
+                    name: "API_TOKEN".to_owned(),
+                    from_secret: "test-flow-env-token".to_owned(),
+                }),
+            ]),
+            &sched,
+            &secrets,
+            &config,
+        )
+        .await
+        .unwrap();
+
+        let spawn_record = sched.get_flow_spawn_record(flow_id).await.unwrap();
+        let task = &sched.get_flow_definition(flow_id).await.unwrap().tasks[0];
+
+        let task_envs = get_task_envs(
+            task,
+            &spawn_record.env,
+            flow_id,
+            &spawn_record.get_store_layout(flow_id, &config.store_layout),
+            &config.bucket_name,
+            &config,
+            &secrets,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            task_envs,
+            vec![
+                serde_json::json!({"name": "ENVIRONMENT", "value": "prod"}),
+                serde_json::json!({"name": "API_TOKEN", "value": "hunter2"}),
+                serde_json::json!({"name": "LOG_LEVEL", "value": "debug"}),
+            ]
+        );
+
+        let missing_secret = EnvVar::SecretRef(SecretRef {
+            name: "API_TOKEN".to_owned(),
+            from_secret: "test-flow-env-missing".to_owned(),
+        });
+        assert!(matches!(
+            instantiate_flow(flow(vec![missing_secret]), &sched, &secrets, &config).await,
+            Err(ExecutorError::MissingSecret(_))
+        ));
+
+        let input_ref = EnvVar::InputRef(InputRef {
+            name: "GREETING".to_owned(),
+            from_input: "greeting".to_owned(),
+        });
+        assert!(matches!(
+            instantiate_flow(flow(vec![input_ref]), &sched, &secrets, &config).await,
+            Err(ExecutorError::InvalidFlowEnv(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_k8s_secret_ref_env() {
+        let pool = get_test_pool(&["secrets"]).await;
+        let secrets = SecretsCrud::new(pool);
+
+        let env = serde_yaml::from_str::<EnvVar>(
+            "{name: DB_PASSWORD, secretName: db-credentials, secretKey: password}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            env,
+            EnvVar::K8sSecretRef(K8sSecretRef {
+                name: "DB_PASSWORD".to_owned(),
+                secret_name: "db-credentials".to_owned(),
+                secret_key: "password".to_owned(),
+            })
+        );
+
+        assert_eq!(
+            get_env_json(&env, 1, &secrets).await.unwrap(),
+            Some(serde_json::json!({
+                "name": "DB_PASSWORD",
+                "valueFrom": {
+                    "secretKeyRef": {"name": "db-credentials", "key": "password"}
+                }
+            }))
+        );
+
+        // Variables with values from inputs are set by the sidecar instead of Kubernetes
+        let env = serde_yaml::from_str::<EnvVar>("{name: GREETING, fromInput: greeting}").unwrap();
+
+        assert_eq!(
+            env,
+            EnvVar::InputRef(InputRef {
+                name: "GREETING".to_owned(),
+                from_input: "greeting".to_owned(),
+            })
+        );
+        assert_eq!(get_env_json(&env, 1, &secrets).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_max_parallel_tasks() {
+        let flow = |max_parallel_tasks| Flow {
+            name: "limited".to_owned(),
+            max_parallel_tasks,
+            ..Default::default()
+        };
+
+        assert!(check_max_parallel_tasks(&flow(None)).is_ok());
+        assert!(check_max_parallel_tasks(&flow(Some(2))).is_ok());
+
+        for max_parallel_tasks in [0, i32::MAX as usize + 1] {
+            assert!(matches!(
+                check_max_parallel_tasks(&flow(Some(max_parallel_tasks))),
+                Err(ExecutorError::InvalidMaxParallelTasks(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_deadline_seconds() {
+        let flow = |deadline_seconds| Flow {
+            name: "deadline".to_owned(),
+            deadline_seconds,
+            ..Default::default()
+        };
+
+        assert!(check_deadline_seconds(&flow(None)).is_ok());
+        assert!(check_deadline_seconds(&flow(Some(3600))).is_ok());
+
+        for deadline_seconds in [0, i64::MAX as u64 + 1] {
+            assert!(matches!(
+                check_deadline_seconds(&flow(Some(deadline_seconds))),
+                Err(ExecutorError::InvalidFlowDeadline(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_flow_interval() {
+        let flow = |interval_seconds, secrets| Flow {
+            name: "hello-world".to_owned(),
+            interval_seconds,
+            secrets: InlineSecrets(secrets),
+            ..Default::default()
+        };
+
+        let inline_secrets = BTreeMap::from([("key".to_owned(), "value".to_owned())]);
+
+        assert!(check_flow_interval(&flow(None, BTreeMap::new())).is_ok());
+        assert!(check_flow_interval(&flow(None, inline_secrets.clone())).is_ok());
+        assert!(check_flow_interval(&flow(Some(60), BTreeMap::new())).is_ok());
+        assert!(matches!(
+            check_flow_interval(&flow(Some(0), BTreeMap::new())),
+            Err(ExecutorError::InvalidFlowInterval(_))
+        ));
+        assert!(matches!(
+            check_flow_interval(&flow(Some(60), inline_secrets)),
+            Err(ExecutorError::InvalidFlowInterval(_))
+        ));
+    }
+
+    fn env(extra: &[(&str, &str)]) -> Vec<(String, String)> {
+        [
+            ("FLOWMIUM_STORE_URL", "http://localhost:9000"),
+            ("FLOWMIUM_TASK_STORE_URL", "http://localhost:9000"),
+            ("FLOWMIUM_BUCKET_NAME", "flowmium-test"),
+            ("FLOWMIUM_ACCESS_KEY", "minio"),
+            ("FLOWMIUM_SECRET_KEY", "password"),
+            ("FLOWMIUM_INIT_CONTAINER_IMAGE", "flowmium"),
+            ("FLOWMIUM_NAMESPACE", "default"),
+        ]
+        .iter()
+        .chain(extra)
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_executor_config_scheduler_interval() {
+        let config: ExecutorConfig = envy::prefixed("FLOWMIUM_").from_iter(env(&[])).unwrap();
+        assert_eq!(config.scheduler_interval_ms, 1000);
+
+        let config: ExecutorConfig = envy::prefixed("FLOWMIUM_")
+            .from_iter(env(&[("FLOWMIUM_SCHEDULER_INTERVAL_MS", "250")]))
+            .unwrap();
+        assert_eq!(config.scheduler_interval_ms, 250);
+    }
+
+    #[test]
+    fn test_executor_config_max_concurrent_secret_fetches() {
+        let config: ExecutorConfig = envy::prefixed("FLOWMIUM_").from_iter(env(&[])).unwrap();
+        assert_eq!(config.max_concurrent_secret_fetches.get(), 4);
+
+        let config: ExecutorConfig = envy::prefixed("FLOWMIUM_")
+            .from_iter(env(&[("FLOWMIUM_MAX_CONCURRENT_SECRET_FETCHES", "1")]))
+            .unwrap();
+        assert_eq!(config.max_concurrent_secret_fetches.get(), 1);
+
+        // No fetch would ever get a permit
+        assert!(envy::prefixed("FLOWMIUM_")
+            .from_iter::<_, ExecutorConfig>(env(&[("FLOWMIUM_MAX_CONCURRENT_SECRET_FETCHES", "0")]))
+            .is_err());
+    }
+
+    fn kube_api_error(code: u16) -> kube::Error {
+        kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".to_owned(),
+            message: format!("status {}", code),
+            reason: "".to_owned(),
+            code,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_job_with_retry() {
+        use std::sync::atomic::{AtomicI32, Ordering};
+
+        let attempts = AtomicI32::new(0);
+
+        let create = |fail_with: u16, fail_count: i32| {
+            attempts.store(0, Ordering::SeqCst);
+            let attempts = &attempts;
+
+            move || async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) < fail_count {
+                    true => Err(kube_api_error(fail_with)),
+                    false => Ok(Job::default()),
+                }
+            }
+        };
+
+        assert!(create_job_with_retry(create(503, 2), 3).await.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        assert!(matches!(
+            create_job_with_retry(create(409, 2), 3).await,
+            Err(ExecutorError::UnableToSpawnTask(kube::Error::Api(response))) if response.code == 409
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        assert!(matches!(
+            create_job_with_retry(create(500, 2), 1).await,
+            Err(ExecutorError::UnableToSpawnTask(_))
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_check_task_names() {
+        let flow = |names: &[&str]| Flow {
+            name: "hello-world".to_owned(),
+            tasks: names
+                .iter()
+                .map(|name| Task {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let assert_invalid = |names: &[&str]| {
+            assert!(matches!(
+                check_task_names(&flow(names)),
+                Err(ExecutorError::InvalidTaskName(..))
+            ))
+        };
+
+        assert!(check_task_names(&flow(&["task-a", "task-2", "b"])).is_ok());
+        assert!(check_task_names(&flow(&[&"a".repeat(MAX_TASK_NAME_LEN)])).is_ok());
+
+        assert_invalid(&["Task-A"]);
+        assert_invalid(&["task_a"]);
+        assert_invalid(&["-task"]);
+        assert_invalid(&["task-"]);
+        assert_invalid(&["task.a"]);
+        assert_invalid(&[""]);
+        assert_invalid(&[&"a".repeat(MAX_TASK_NAME_LEN + 1)]);
+        assert_invalid(&["task-a", "task-b", "task-a"]);
+    }
+
+    #[test]
+    fn test_check_flow_name() {
+        let flow = |name: &str| Flow {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+
+        for name in [
+            "hello-world",
+            "Nightly_ETL.v2",
+            &"a".repeat(MAX_FLOW_NAME_LEN),
+        ] {
+            assert!(check_flow_name(&flow(name)).is_ok(), "{}", name);
+        }
+
+        for name in [
+            "",
+            &"a".repeat(MAX_FLOW_NAME_LEN + 1),
+            "etl/../other-flow",
+            "nightly etl",
+            "{flow_id}",
+            ".",
+            "..",
+        ] {
+            assert!(
+                matches!(check_flow_name(&flow(name)), Err(ExecutorError::InvalidFlowName(invalid, _)) if invalid == name),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_job_name_length() {
+        let config = test_executor_config();
+
+        let flow = |flow_name: &str, task_name: &str| Flow {
+            name: flow_name.to_owned(),
+            tasks: vec![Task {
+                name: task_name.to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Names of flows are not part of the names of their jobs
+        let longest_task_name = "a".repeat(MAX_TASK_NAME_LEN);
+        let fits = flow(&"long-flow-name-".repeat(8), &longest_task_name);
+        assert!(validate_and_plan_flow(&fits, &config).is_ok());
+        assert_eq!(
+            get_job_name(i32::MAX, &fits.tasks[0]).len(),
+            MAX_JOB_NAME_LEN
+        );
+
+        let too_long_task_name = "b".repeat(MAX_TASK_NAME_LEN + 1);
+        let too_long = flow(&"long-flow-name-".repeat(8), &too_long_task_name);
+
+        match validate_and_plan_flow(&too_long, &config) {
+            Err(ExecutorError::InvalidTaskName(name, reason)) => {
+                assert_eq!(name, too_long_task_name);
+                assert_eq!(
+                    reason,
+                    format!(
+                        "longer than {} characters, name of its job flow-2147483647-task-{} would be longer than 63 characters",
+                        MAX_TASK_NAME_LEN, too_long_task_name
+                    )
+                );
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_check_flow_schedule() {
+        let flow = |schedule: Option<&str>, interval_seconds| Flow {
+            name: "hello-world".to_owned(),
+            schedule: schedule.map(str::to_owned),
+            interval_seconds,
+            ..Default::default()
+        };
+
+        assert!(check_flow_schedule(&flow(None, Some(60))).is_ok());
+        assert!(check_flow_schedule(&flow(Some("*/15 * * * *"), None)).is_ok());
+        assert!(check_flow_schedule(&flow(Some("0 30 9 * * Mon-Fri"), None)).is_ok());
+        assert!(matches!(
+            check_flow_schedule(&flow(Some("every hour"), None)),
+            Err(ExecutorError::InvalidFlowSchedule(_))
+        ));
+        assert!(matches!(
+            check_flow_schedule(&flow(Some("0 0 0 1 1 * 2000"), None)),
+            Err(ExecutorError::InvalidFlowSchedule(_))
+        ));
+        assert!(matches!(
+            check_flow_schedule(&flow(Some("*/15 * * * *"), Some(60))),
+            Err(ExecutorError::InvalidFlowSchedule(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_on_failure_task() {
+        let task = |name: &str, depends: &[&str]| Task {
+            name: name.to_owned(),
+            depends: depends.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let flow = |tasks, on_failure: Option<&str>| Flow {
+            name: "hello-world".to_owned(),
+            tasks,
+            on_failure: on_failure.map(str::to_owned),
+            ..Default::default()
+        };
+
+        assert!(check_on_failure_task(&flow(vec![task("task-a", &[])], None)).is_ok());
+        assert!(check_on_failure_task(&flow(
+            vec![task("task-a", &[]), task("cleanup", &[])],
+            Some("cleanup")
+        ))
+        .is_ok());
+
+        for (tasks, on_failure) in [
+            (vec![task("task-a", &[])], "cleanup"),
+            (vec![task("cleanup", &[])], "cleanup"),
+            (
+                vec![task("task-a", &[]), task("cleanup", &["task-a"])],
+                "cleanup",
+            ),
+            (
+                vec![task("task-a", &["cleanup"]), task("cleanup", &[])],
+                "cleanup",
+            ),
+        ] {
+            assert!(matches!(
+                check_on_failure_task(&flow(tasks, Some(on_failure))),
+                Err(ExecutorError::InvalidOnFailureTask(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_bucket_name() {
+        let flow = |bucket_name: Option<&str>| Flow {
+            name: "hello-world".to_owned(),
+            bucket_name: bucket_name.map(str::to_owned),
+            ..Default::default()
+        };
+
+        assert!(check_bucket_name(&flow(None)).is_ok());
+        assert!(check_bucket_name(&flow(Some("flowmium-restricted"))).is_ok());
+        assert!(check_bucket_name(&flow(Some("data.flowmium.io"))).is_ok());
+
+        for bucket_name in [
+            "",
+            "ab",
+            "Flowmium",
+            "-flowmium",
+            "flowmium..test",
+            "flowmium/test",
+        ] {
+            assert!(matches!(
+                check_bucket_name(&flow(Some(bucket_name))),
+                Err(ExecutorError::InvalidBucketName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_init_container_image() {
+        let flow = |init_container_image: Option<&str>| Flow {
+            name: "hello-world".to_owned(),
+            init_container_image: init_container_image.map(str::to_owned),
+            ..Default::default()
+        };
+
+        assert!(check_init_container_image(&flow(None)).is_ok());
+        assert!(
+            check_init_container_image(&flow(Some("docker.io/shnoo28/flowmium:v0.0.9"))).is_ok()
+        );
+        assert!(check_init_container_image(&flow(Some(
+            "registry:5000/flowmium@sha256:0123456789abcdef"
+        )))
+        .is_ok());
+
+        for image in ["", "flowmium latest", ":latest", "flowmium:$(whoami)"] {
+            assert!(matches!(
+                check_init_container_image(&flow(Some(image))),
+                Err(ExecutorError::InvalidInitContainerImage(_))
+            ));
+        }
+
+        let config = test_executor_config();
+        let job = get_job_json(
+            0,
+            0,
+            &test_flow().tasks[0],
+            &config,
+            "registry:5000/flowmium-canary",
+            vec![],
+        );
+
+        assert_eq!(
+            job["spec"]["template"]["spec"]["initContainers"][0]["image"],
+            "registry:5000/flowmium-canary"
+        );
+    }
+
+    fn test_volumes() -> (Vec<Volume>, Vec<VolumeMount>) {
+        let volumes = serde_json::from_value(serde_json::json!([
+            {"name": "shared", "persistentVolumeClaim": {"claimName": "shared-claim"}},
+            {"name": "token", "projected": {"sources": [{"serviceAccountToken": {"path": "token"}}]}}
+        ]))
+        .unwrap();
+
+        let volume_mounts = serde_json::from_value(serde_json::json!([
+            {"name": "shared", "mountPath": "/shared"},
+            {"name": "token", "mountPath": "/var/run/secrets/tokens", "readOnly": true}
+        ]))
+        .unwrap();
+
+        (volumes, volume_mounts)
+    }
+
+    #[test]
+    fn test_check_task_volumes() {
+        let config = test_executor_config();
+
+        let flow = |volumes, volume_mounts| Flow {
+            name: "hello-world".to_owned(),
+            tasks: vec![Task {
+                name: "task-volumes".to_owned(),
+                volumes: Some(volumes),
+                volume_mounts: Some(volume_mounts),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let (volumes, volume_mounts) = test_volumes();
+
+        let check = |volumes, volume_mounts, config| {
+            check_task_volumes(&flow(volumes, volume_mounts), config)
+        };
+
+        assert!(check(volumes.clone(), volume_mounts.clone(), &config).is_ok());
+
+        let assert_invalid = |volumes, volume_mounts, config| {
+            assert!(matches!(
+                check(volumes, volume_mounts, config),
+                Err(ExecutorError::InvalidTaskVolumes(task, _)) if task == "task-volumes"
+            ))
+        };
+
+        let mut reserved_name = volumes.clone();
+        reserved_name[0].name = "executable".to_owned();
+        assert_invalid(reserved_name, vec![], &config);
+
+        let mut duplicate_name = volumes.clone();
+        duplicate_name[1].name = "shared".to_owned();
+        assert_invalid(duplicate_name, vec![], &config);
+
+        let mut missing_volume = volume_mounts.clone();
+        missing_volume[0].name = "missing".to_owned();
+        assert_invalid(volumes.clone(), missing_volume, &config);
+
+        let mount_at = |mount_path: &str| {
+            let mut volume_mounts = volume_mounts.clone();
+            volume_mounts[0].mount_path = mount_path.to_owned();
+            volume_mounts
+        };
+
+        assert_invalid(volumes.clone(), mount_at("/var/run/"), &config);
+        assert_invalid(volumes.clone(), mount_at("/var/run/flowmium/"), &config);
+        assert!(check(
+            volumes.clone(),
+            mount_at("/var/run/flowmium-cache"),
+            &config
+        )
+        .is_ok());
+
+        // Reserved paths follow the configured path of the volume shared with the init container
+        let custom_config = ExecutorConfig {
+            shared_volume_path: "/opt/flowmium-shared/".to_owned(),
+            ..test_executor_config()
+        };
+
+        assert!(check(volumes.clone(), mount_at("/var/run"), &custom_config).is_ok());
+        assert!(check(
+            volumes.clone(),
+            mount_at("/var/run/flowmium"),
+            &custom_config
+        )
+        .is_ok());
+        assert_invalid(
+            volumes.clone(),
+            mount_at("/opt/flowmium-shared"),
+            &custom_config,
+        );
+        assert_invalid(
+            volumes,
+            mount_at("/opt/flowmium-shared/flowmium"),
+            &custom_config,
+        );
+    }
+
+    #[test]
+    fn test_job_json_volumes() {
+        let config = test_executor_config();
+
+        let (volumes, volume_mounts) = test_volumes();
+
+        let task = Task {
+            volumes: Some(volumes),
+            volume_mounts: Some(volume_mounts),
+            ..test_flow().tasks[0].clone()
+        };
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+
+        assert_eq!(
+            pod_spec["volumes"],
+            serde_json::json!([
+                {"name": "executable", "emptyDir": {"medium": "Memory"}},
+                {"name": "shared", "persistentVolumeClaim": {"claimName": "shared-claim"}},
+                {"name": "token", "projected": {"sources": [{"serviceAccountToken": {"path": "token"}}]}}
+            ])
+        );
+
+        assert_eq!(
+            pod_spec["containers"][0]["volumeMounts"],
+            serde_json::json!([
+                {"name": "executable", "mountPath": "/var/run"},
+                {"name": "shared", "mountPath": "/shared"},
+                {"name": "token", "mountPath": "/var/run/secrets/tokens", "readOnly": true}
+            ])
+        );
+
+        assert_eq!(
+            pod_spec["initContainers"][0]["volumeMounts"],
+            serde_json::json!([{"name": "executable", "mountPath": "/var/run"}])
+        );
+    }
+
+    #[test]
+    fn test_job_json_without_artefacts() {
+        let config = test_executor_config();
+
+        let task = Task {
+            name: "notify".to_owned(),
+            image: "curlimages/curl:latest".to_owned(),
+            cmd: vec!["curl".to_owned(), "https://example.com".to_owned()],
+            inputs: None,
+            outputs: Some(vec![]),
+            ..Default::default()
+        };
+
+        assert!(!needs_sidecar(&task));
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+
+        assert_eq!(pod_spec.get("initContainers"), None);
+        assert_eq!(pod_spec.get("volumes"), None);
+        assert_eq!(pod_spec["containers"][0].get("volumeMounts"), None);
+        assert_eq!(
+            pod_spec["containers"][0]["command"],
+            serde_json::json!(["curl", "https://example.com"])
+        );
+
+        let entrypoint_task = Task {
+            cmd: vec![],
+            ..task.clone()
+        };
+
+        let job = get_job_json(0, 0, &entrypoint_task, &config, "", vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0].get("command"),
+            None
+        );
+
+        let (volumes, volume_mounts) = test_volumes();
+
+        let volumes_task = Task {
+            volumes: Some(volumes),
+            volume_mounts: Some(volume_mounts),
+            ..task.clone()
+        };
+
+        let job = get_job_json(0, 0, &volumes_task, &config, "", vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+
+        assert_eq!(pod_spec["volumes"].as_array().unwrap().len(), 2);
+        assert_eq!(pod_spec["volumes"][0]["name"], "shared");
+        assert_eq!(
+            pod_spec["containers"][0]["volumeMounts"][0]["name"],
+            "shared"
+        );
+
+        let timeout_task = Task {
+            timeout_seconds: Some(60),
+            ..task
+        };
+
+        assert!(needs_sidecar(&timeout_task));
+        assert!(needs_sidecar(&test_flow().tasks[0]));
+
+        let stdin_task = Task {
+            stdin: Some("hello".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(needs_sidecar(&stdin_task));
+    }
+
+    #[test]
+    fn test_sidecar_envs_bucket_region_and_path_style() {
+        let sidecar_env = |config: &ExecutorConfig, name: &str| {
+            get_sidecar_envs(
+                &test_flow().tasks[0],
+                0,
+                &config.store_layout.for_flow(0, "hello-world", "2024-01-01"),
+                &config.bucket_name,
+                config,
+            )
+            .into_iter()
+            .find(|env| env["name"] == name)
+            .map(|env| env["value"].clone())
+        };
+
+        let config = test_executor_config();
+
+        assert_eq!(sidecar_env(&config, "FLOWMIUM_BUCKET_REGION"), None);
+        assert_eq!(sidecar_env(&config, "FLOWMIUM_BUCKET_PATH_STYLE"), None);
+
+        let config = ExecutorConfig {
+            bucket_region: Some("eu-west-1".to_owned()),
+            bucket_path_style: Some(false),
+            ..test_executor_config()
+        };
+
+        assert_eq!(
+            sidecar_env(&config, "FLOWMIUM_BUCKET_REGION"),
+            Some(serde_json::json!("eu-west-1"))
+        );
+        assert_eq!(
+            sidecar_env(&config, "FLOWMIUM_BUCKET_PATH_STYLE"),
+            Some(serde_json::json!("false"))
+        );
+    }
+
+    #[test]
+    fn test_job_json_custom_flowmium_paths() {
+        let default_job = get_job_json(
+            0,
+            0,
+            &test_flow().tasks[0],
+            &test_executor_config(),
+            "registry:5000/flowmium-debug",
+            vec![],
+        );
+        let default_pod_spec = &default_job["spec"]["template"]["spec"];
+
+        assert_eq!(
+            default_pod_spec["initContainers"][0]["command"],
+            serde_json::json!(["/flowmium", "init", "/flowmium", "/var/run/flowmium"])
+        );
+        assert_eq!(
+            default_pod_spec["containers"][0]["command"][0],
+            "/var/run/flowmium"
+        );
+
+        let config = ExecutorConfig {
+            flowmium_binary_path: "/usr/local/bin/flowmium".to_owned(),
+            shared_volume_path: "/opt/flowmium/".to_owned(),
+            ..test_executor_config()
+        };
+
+        let task = Task {
+            cmd: vec!["python3".to_owned(), "main.py".to_owned()],
+            ..test_flow().tasks[0].clone()
+        };
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+
+        assert_eq!(
+            pod_spec["initContainers"][0]["command"],
+            serde_json::json!([
+                "/usr/local/bin/flowmium",
+                "init",
+                "/usr/local/bin/flowmium",
+                "/opt/flowmium/flowmium"
+            ])
+        );
+        assert_eq!(
+            pod_spec["initContainers"][0]["volumeMounts"][0]["mountPath"],
+            "/opt/flowmium/"
+        );
+
+        assert_eq!(
+            pod_spec["containers"][0]["command"],
+            serde_json::json!(["/opt/flowmium/flowmium", "task", "python3", "main.py"])
+        );
+        assert_eq!(
+            pod_spec["containers"][0]["volumeMounts"][0]["mountPath"],
+            "/opt/flowmium/"
+        );
+    }
+
+    #[test]
+    fn test_get_task_timeout() {
+        let task = |timeout_seconds, active_deadline_seconds| Task {
+            timeout_seconds,
+            active_deadline_seconds,
+            ..Default::default()
+        };
+
+        assert_eq!(get_task_timeout(&task(None, None)), None);
+        assert_eq!(get_task_timeout(&task(Some(50), None)), Some(50));
+        assert_eq!(get_task_timeout(&task(Some(50), Some(60))), Some(50));
+        assert_eq!(get_task_timeout(&task(None, Some(60))), Some(54));
+        assert_eq!(get_task_timeout(&task(None, Some(3600))), Some(3570));
+        assert_eq!(get_task_timeout(&task(None, Some(5))), Some(4));
+
+        let flow = |timeout_seconds, active_deadline_seconds| Flow {
+            name: "hello-world".to_owned(),
+            tasks: vec![task(timeout_seconds, active_deadline_seconds)],
+            ..Default::default()
+        };
+
+        assert!(check_task_timeouts(&flow(Some(50), Some(60))).is_ok());
+        assert!(check_task_timeouts(&flow(Some(50), None)).is_ok());
+        assert!(matches!(
+            check_task_timeouts(&flow(Some(60), Some(60))),
+            Err(ExecutorError::InvalidTaskTimeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_job_json_active_deadline() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"].get("activeDeadlineSeconds"),
+            None
+        );
+
+        task.active_deadline_seconds = Some(300);
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["activeDeadlineSeconds"],
+            serde_json::json!(300)
+        );
+    }
+
+    #[test]
+    fn test_job_json_resources() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0].get("resources"),
+            None
+        );
+
+        task.resources = Some(ResourceRequirements {
+            cpu_request: Some(String::new()),
+            ..Default::default()
+        });
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0].get("resources"),
+            None
+        );
+
+        task.resources = Some(ResourceRequirements {
+            cpu_request: Some("250m".to_owned()),
+            memory_request: Some("256Mi".to_owned()),
+            cpu_limit: None,
+            memory_limit: Some("1Gi".to_owned()),
+        });
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0]["resources"],
+            serde_json::json!({
+                "requests": {"cpu": "250m", "memory": "256Mi"},
+                "limits": {"memory": "1Gi"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_job_json_node_selector_and_tolerations() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+        assert_eq!(pod_spec.get("nodeSelector"), None);
+        assert_eq!(pod_spec.get("tolerations"), None);
+
+        task.node_selector = Some(BTreeMap::from([(
+            "accelerator".to_owned(),
+            "nvidia-tesla-t4".to_owned(),
+        )]));
+        task.tolerations =
+            serde_yaml::from_str("[{key: nvidia.com/gpu, operator: Exists, effect: NoSchedule}]")
+                .unwrap();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        let pod_spec = &job["spec"]["template"]["spec"];
+        assert_eq!(
+            pod_spec["nodeSelector"],
+            serde_json::json!({"accelerator": "nvidia-tesla-t4"})
+        );
+        assert_eq!(
+            pod_spec["tolerations"],
+            serde_json::json!([
+                {"key": "nvidia.com/gpu", "operator": "Exists", "effect": "NoSchedule"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_job_json_env_from() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0].get("envFrom"),
+            None
+        );
+
+        task.env_from = serde_yaml::from_str(
+            "[{configMapRef: {name: app-config}}, {secretRef: {name: db-credentials}, prefix: DB_}]",
+        )
+        .unwrap();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0]["envFrom"],
+            serde_json::json!([
+                {"configMapRef": {"name": "app-config"}},
+                {"secretRef": {"name": "db-credentials"}, "prefix": "DB_"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_job_json_image_pull_secrets() {
+        let mut config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let image_pull_secrets = |task: &Task, config: &ExecutorConfig| {
+            let job = get_job_json(0, 0, task, config, &config.init_container_image, vec![]);
+            job["spec"]["template"]["spec"]
+                .get("imagePullSecrets")
+                .cloned()
+        };
+
+        assert_eq!(image_pull_secrets(&task, &config), None);
+
+        config.image_pull_secrets = vec!["default-registry".to_owned()];
+        assert_eq!(
+            image_pull_secrets(&task, &config),
+            Some(serde_json::json!([{"name": "default-registry"}]))
+        );
+
+        task.image_pull_secrets = Some(vec!["private-a".to_owned(), "private-b".to_owned()]);
+        assert_eq!(
+            image_pull_secrets(&task, &config),
+            Some(serde_json::json!([{"name": "private-a"}, {"name": "private-b"}]))
+        );
+
+        task.image_pull_secrets = Some(vec![]);
+        assert_eq!(image_pull_secrets(&task, &config), None);
+    }
+
+    #[test]
+    fn test_job_json_termination_grace_period() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"].get("terminationGracePeriodSeconds"),
+            None
+        );
+
+        task.termination_grace_period_seconds = Some(5);
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(
+            job["spec"]["template"]["spec"]["terminationGracePeriodSeconds"],
+            serde_json::json!(5)
+        );
+
+        let flow = Flow {
+            tasks: vec![Task {
+                termination_grace_period_seconds: Some(-1),
+                ..task
+            }],
+            ..test_flow()
+        };
+
+        assert!(matches!(
+            check_termination_grace_periods(&flow),
+            Err(ExecutorError::InvalidTerminationGracePeriod(_))
+        ));
+    }
+
+    #[test]
+    fn test_job_json_ttl_seconds_after_finished() {
+        let task = &test_flow().tasks[0];
+
+        let job_ttl = |job_ttl_seconds_after_finished, scheduler_interval_ms| {
+            let config = ExecutorConfig {
+                job_ttl_seconds_after_finished,
+                scheduler_interval_ms,
+                ..test_executor_config()
+            };
+
+            let job = get_job_json(0, 0, task, &config, &config.init_container_image, vec![]);
+
+            job["spec"].get("ttlSecondsAfterFinished").cloned()
+        };
+
+        assert_eq!(job_ttl(None, 1000), None);
+        assert_eq!(job_ttl(Some(600), 1000), Some(serde_json::json!(600)));
+        assert_eq!(job_ttl(Some(0), 1000), Some(serde_json::json!(60)));
+        assert_eq!(job_ttl(Some(600), 120_000), Some(serde_json::json!(1200)));
+    }
+
+    #[test]
+    fn test_job_json_pod_labels_and_annotations() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        task.pod_labels = Some(BTreeMap::from([
+            ("team".to_owned(), "data".to_owned()),
+            (config.task_id_label.clone(), "overridden".to_owned()),
+        ]));
+        task.pod_annotations = Some(BTreeMap::from([
+            ("sidecar.istio.io/inject".to_owned(), "false".to_owned()),
+            (BACKOFF_LIMIT_ANNOTATION.to_owned(), "overridden".to_owned()),
+        ]));
+        task.backoff_limit = Some(2);
+
+        let job = get_job_json(3, 1, &task, &config, &config.init_container_image, vec![]);
+        let metadata = &job["spec"]["template"]["metadata"];
+
+        assert_eq!(
+            metadata["labels"],
+            serde_json::json!({
+                "team": "data",
+                &config.flow_id_label: "3",
+                &config.task_id_label: "1",
+            })
+        );
+        assert_eq!(
+            metadata["annotations"],
+            serde_json::json!({
+                "sidecar.istio.io/inject": "false",
+                BACKOFF_LIMIT_ANNOTATION: "2",
+            })
+        );
+    }
+
+    #[test]
+    fn test_reserved_pod_annotations() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+        task.pod_annotations = Some(BTreeMap::from([(
+            BACKOFF_LIMIT_ANNOTATION.to_owned(),
+            "5".to_owned(),
+        )]));
+
+        let flow = Flow {
+            name: "reserved-annotations".to_owned(),
+            tasks: vec![task.clone()],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            check_pod_annotations(&flow),
+            Err(ExecutorError::InvalidPodAnnotation(task, _)) if task == "task-e"
+        ));
+
+        // Flows created before reserved annotations were rejected do not have them on their pods
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(job["spec"]["template"]["metadata"].get("annotations"), None);
+    }
+
+    #[test]
+    fn test_job_json_backoff_limit() {
+        let config = test_executor_config();
+
+        let mut task = test_flow().tasks[0].clone();
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(job["spec"]["backoffLimit"], serde_json::json!(0));
+        assert_eq!(job["spec"]["template"]["metadata"].get("annotations"), None);
+
+        task.backoff_limit = Some(3);
+
+        let job = get_job_json(0, 0, &task, &config, &config.init_container_image, vec![]);
+        assert_eq!(job["spec"]["backoffLimit"], serde_json::json!(3));
+        assert_eq!(
+            job["spec"]["template"]["spec"]["restartPolicy"],
+            serde_json::json!("Never")
+        );
+        assert_eq!(
+            job["spec"]["template"]["metadata"]["annotations"],
+            serde_json::json!({ BACKOFF_LIMIT_ANNOTATION: "3" })
+        );
+
+        let flow = Flow {
+            tasks: vec![Task {
+                backoff_limit: Some(-1),
+                ..task
+            }],
+            ..test_flow()
+        };
+
+        assert!(matches!(
+            check_backoff_limits(&flow),
+            Err(ExecutorError::InvalidBackoffLimit(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_eager_inputs() {
+        let eager_task = |active_deadline_seconds, inputs| Task {
+            name: "task-b".to_owned(),
+            depends: vec!["task-a".to_owned()],
+            inputs,
+            active_deadline_seconds,
+            eager_inputs: true,
+            ..Default::default()
+        };
+
+        let flow = |task_b| Flow {
+            name: "eager".to_owned(),
+            tasks: vec![
                 Task {
-                    name: "task-c".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec!["task-d".to_string()],
-                    cmd: vec![
-                        "sh".to_string(),
-                        "-c".to_string(),
-                        "cat /task-d-output | sed 's/\\bfoobar\\b/mars/g' > /hello-mars"
-                            .to_string(),
-                    ],
-                    env: vec![],
-                    inputs: Some(vec![Input {
-                        from: "OutputFromTaskD".to_string(),
-                        path: "/task-d-output".to_string(),
-                    }]),
+                    name: "task-a".to_owned(),
                     outputs: Some(vec![Output {
-                        name: "OutputFromTaskC".to_string(),
-                        path: "/hello-mars".to_string(),
+                        name: "output-a".to_owned(),
+                        path: "/output-a".to_owned(),
+                        ..Default::default()
                     }]),
+                    ..Default::default()
                 },
+                task_b,
             ],
-        }
+            ..Default::default()
+        };
+
+        let input_a = Some(vec![Input {
+            from: "output-a".to_owned(),
+            path: "/input-a".to_owned(),
+        }]);
+
+        assert!(check_eager_inputs(&flow(eager_task(Some(60), input_a.clone()))).is_ok());
+
+        assert!(matches!(
+            check_eager_inputs(&flow(eager_task(None, input_a))),
+            Err(ExecutorError::InvalidEagerInputs(..))
+        ));
+
+        assert!(matches!(
+            check_eager_inputs(&flow(eager_task(Some(60), None))),
+            Err(ExecutorError::InvalidEagerInputs(..))
+        ));
+
+        assert!(check_eager_inputs(&flow(Task {
+            eager_inputs: false,
+            ..eager_task(None, None)
+        }))
+        .is_ok());
+    }
+
+    #[test]
+    fn test_validate_templated_artefact_names() {
+        let config = test_executor_config();
+
+        let merge = |input_from: &str| {
+            TaskBuilder::new("merge", "foo")
+                .depends_on("shard")
+                .env_literal("SHARD", "7")
+                .input(input_from, "/result")
+                .active_deadline_seconds(60)
+                .build()
+        };
+
+        let flow = |merge| {
+            FlowBuilder::new("templated")
+                .task(
+                    TaskBuilder::new("shard", "foo")
+                        .env_literal("SHARD", "7")
+                        .output("result-${SHARD}", "/result"),
+                )
+                .task(Task {
+                    eager_inputs: true,
+                    ..merge
+                })
+                .build()
+        };
+
+        assert!(validate_and_plan_flow(&flow(merge("result-${SHARD}")), &config).is_ok());
+        assert!(validate_and_plan_flow(&flow(merge("result-7")), &config).is_ok());
+
+        assert!(matches!(
+            validate_and_plan_flow(&flow(merge("result-${SHARDS}")), &config),
+            Err(ExecutorError::UnableToConstructPlan(
+                PlannerError::UnresolvedVariable(task_name, var_name)
+            )) if task_name == "merge" && var_name == "SHARDS"
+        ));
+    }
+
+    fn image_pull_backoff_pod(created_at: &str) -> Pod {
+        waiting_pod("ImagePullBackOff", "Back-off pulling image", created_at)
+    }
+
+    fn waiting_pod(reason: &str, message: &str, created_at: &str) -> Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "flow-1-task-pull",
+                "creationTimestamp": created_at,
+            },
+            "status": {
+                "phase": "Pending",
+                "initContainerStatuses": [{
+                    "name": "init",
+                    "image": "registry:5000/flowmium-debug",
+                    "imageID": "",
+                    "ready": true,
+                    "restartCount": 0,
+                    "state": {"terminated": {"exitCode": 0, "reason": "Completed"}},
+                }],
+                "containerStatuses": [{
+                    "name": "pull",
+                    "image": "registry:5000/does-not-exist",
+                    "imageID": "",
+                    "ready": false,
+                    "restartCount": 0,
+                    "state": {
+                        "waiting": {
+                            "reason": reason,
+                            "message": message,
+                        }
+                    },
+                }],
+            },
+        }))
+        .unwrap()
     }
 
     #[tokio::test]
     #[serial]
-    async fn test_schedule_and_run_tasks() {
+    async fn test_image_pull_failure() {
         let pool = get_test_pool(&["flows", "secrets"]).await;
         let config = test_executor_config();
 
         let sched = Scheduler::new(pool.clone());
         let secrets = SecretsCrud::new(pool.clone());
 
-        secrets
-            .create_secret("test-greetings-secret", "Greetings foobar")
-            .await
-            .unwrap();
+        let now: DateTime<Utc> = "2026-10-17T12:10:00Z".parse().unwrap();
 
-        // delete_all_pods().await;
-        // delete_all_jobs().await;
-        let bucket = delete_all_objects(&config).await;
+        assert_eq!(
+            get_pod_task_status(
+                1,
+                0,
+                &image_pull_backoff_pod("2026-10-17T12:09:00Z"),
+                &config,
+                now
+            )
+            .unwrap(),
+            TaskStatus::Pending
+        );
 
-        let flow_id = instantiate_flow(test_flow(), &sched).await.unwrap();
+        let status = get_pod_task_status(
+            1,
+            0,
+            &image_pull_backoff_pod("2026-10-17T12:00:00Z"),
+            &config,
+            now,
+        );
 
-        for _ in 0..50 {
-            tokio::time::sleep(Duration::from_millis(1000)).await;
-            schedule_and_run_tasks(&sched, &config, &secrets).await;
+        match &status {
+            Err(ExecutorError::ImagePullFailed(1, 0, failure)) => assert_eq!(
+                failure,
+                "ImagePullBackOff for image registry:5000/does-not-exist: Back-off pulling image"
+            ),
+            _ => panic!("unexpected status {:?}", status),
         }
 
-        for task_id in 0..5 {
-            assert_eq!(
-                get_task_status(flow_id, task_id, &config).await.unwrap(),
-                TaskStatus::Finished
-            )
-        }
+        // Grace period starts when the pod's init containers completed, not when the pod was created
+        let with_conditions = |initialized: &str| {
+            let mut pod = image_pull_backoff_pod("2026-10-17T12:00:00Z");
+            pod.status.as_mut().unwrap().conditions = Some(
+                serde_json::from_value(serde_json::json!([
+                    {"type": "PodScheduled", "status": "True", "lastTransitionTime": "2026-10-17T12:01:00Z"},
+                    {"type": "Initialized", "status": initialized, "lastTransitionTime": "2026-10-17T12:09:00Z"},
+                ]))
+                .unwrap(),
+            );
+            pod
+        };
 
         assert_eq!(
-            get_contents(&bucket, format!("{}/OutputFromTaskA", flow_id)).await,
-            "Hello world Hello mars Hello foobar Greetings foobar\n"
+            get_pod_task_status(1, 0, &with_conditions("True"), &config, now).unwrap(),
+            TaskStatus::Pending
         );
+        assert!(matches!(
+            get_pod_task_status(1, 0, &with_conditions("False"), &config, now),
+            Err(ExecutorError::ImagePullFailed(1, 0, _))
+        ));
+
+        let flow = Flow {
+            name: "image-pull".to_owned(),
+            tasks: vec![Task {
+                name: "pull".to_owned(),
+                image: "registry:5000/does-not-exist".to_owned(),
+                cmd: vec!["true".to_owned()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let flow_id = instantiate_flow(flow, &sched, &secrets, &config)
+            .await
+            .unwrap();
+        sched.schedule_tasks(flow_id).await.unwrap();
+        sched.mark_task_running(flow_id, 0).await.unwrap();
+
+        mark_task_with_status(&sched, flow_id, 0, status)
+            .await
+            .unwrap();
+
+        let record = sched.get_flow(flow_id).await.unwrap();
+        assert_eq!(record.status, FlowStatus::Failed);
+        assert_eq!(record.failed_tasks, vec![0]);
         assert_eq!(
-            get_contents(&bucket, format!("{}/OutputFromTaskB", flow_id)).await,
-            "Hello world\n"
+            record.task_failures,
+            BTreeMap::from([(
+                0,
+                "unable to pull image for flow 1 task 0: ImagePullBackOff for image \
+                registry:5000/does-not-exist: Back-off pulling image"
+                    .to_owned()
+            )])
         );
+    }
+
+    #[test]
+    fn test_container_create_failure() {
+        let config = test_executor_config();
+
+        let now: DateTime<Utc> = "2026-10-17T12:00:10Z".parse().unwrap();
+
         assert_eq!(
-            get_contents(&bucket, format!("{}/OutputFromTaskC", flow_id)).await,
-            "Hello mars\n"
+            get_pod_task_status(
+                1,
+                0,
+                &waiting_pod("ContainerCreating", "", "2026-10-17T12:00:00Z"),
+                &config,
+                now
+            )
+            .unwrap(),
+            TaskStatus::Pending
+        );
+
+        let status = get_pod_task_status(
+            1,
+            0,
+            &waiting_pod(
+                "InvalidImageName",
+                "couldn't parse image name",
+                "2026-10-17T12:00:00Z",
+            ),
+            &config,
+            now,
+        );
+
+        match &status {
+            Err(ExecutorError::ContainerCreateFailed(1, 0, failure)) => assert_eq!(
+                failure,
+                "InvalidImageName for image registry:5000/does-not-exist: couldn't parse image name"
+            ),
+            _ => panic!("unexpected status {:?}", status),
+        }
+
+        // Config maps and secrets can be created after the task, so missing ones get a grace period
+        let config_error_pod = waiting_pod(
+            "CreateContainerConfigError",
+            "configmap \"app-config\" not found",
+            "2026-10-17T12:00:00Z",
         );
+
         assert_eq!(
-            get_contents(&bucket, format!("{}/OutputFromTaskD", flow_id)).await,
-            "Hello foobar\n"
+            get_pod_task_status(1, 0, &config_error_pod, &config, now).unwrap(),
+            TaskStatus::Pending
         );
+
+        let status = get_pod_task_status(
+            1,
+            0,
+            &config_error_pod,
+            &config,
+            "2026-10-17T12:10:00Z".parse().unwrap(),
+        );
+
+        match &status {
+            Err(ExecutorError::ContainerCreateFailed(1, 0, failure)) => assert_eq!(
+                failure,
+                "CreateContainerConfigError for image registry:5000/does-not-exist: configmap \"app-config\" not found"
+            ),
+            _ => panic!("unexpected status {:?}", status),
+        }
+    }
+
+    fn failed_pod(message: &str) -> Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "flow-1-task-sleep",
+                "creationTimestamp": "2026-10-17T12:00:00Z",
+            },
+            "status": {
+                "phase": "Failed",
+                "containerStatuses": [{
+                    "name": "sleep",
+                    "image": "registry:5000/flowmium-debug",
+                    "imageID": "",
+                    "ready": false,
+                    "restartCount": 0,
+                    "state": {
+                        "terminated": {
+                            "exitCode": 1,
+                            "reason": "Error",
+                            "message": message,
+                        }
+                    },
+                }],
+            },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_timed_out_task() {
+        let pool = get_test_pool(&["flows", "secrets"]).await;
+        let config = test_executor_config();
+
+        let sched = Scheduler::new(pool.clone());
+        let secrets = SecretsCrud::new(pool.clone());
+
+        let now: DateTime<Utc> = "2026-10-17T12:10:00Z".parse().unwrap();
+
         assert_eq!(
-            get_contents(&bucket, format!("{}/OutputFromTaskE", flow_id)).await,
-            "Greetings foobar\n"
+            get_pod_task_status(1, 0, &failed_pod("missing outputs"), &config, now).unwrap(),
+            TaskStatus::Failed
+        );
+
+        let status = get_pod_task_status(
+            1,
+            0,
+            &failed_pod("Task timed out after 1 seconds"),
+            &config,
+            now,
         );
+        assert_eq!(status.as_ref().unwrap(), &TaskStatus::TimedOut);
+
+        let flow = Flow {
+            name: "timeout".to_owned(),
+            tasks: vec![Task {
+                name: "sleep".to_owned(),
+                image: "registry:5000/flowmium-debug".to_owned(),
+                cmd: vec!["sleep".to_owned(), "10".to_owned()],
+                timeout_seconds: Some(1),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let flow_id = instantiate_flow(flow, &sched, &secrets, &config)
+            .await
+            .unwrap();
+        sched.schedule_tasks(flow_id).await.unwrap();
+        sched.mark_task_running(flow_id, 0).await.unwrap();
+
+        mark_task_with_status(&sched, flow_id, 0, status)
+            .await
+            .unwrap();
+
+        let record = sched.get_flow(flow_id).await.unwrap();
+        assert_eq!(record.status, FlowStatus::Failed);
+        assert_eq!(record.failed_tasks, vec![0]);
+        assert_eq!(record.timed_out_tasks, vec![0]);
     }
 
-    fn test_flow_fail() -> Flow {
-        Flow {
-            name: "hello-world".to_owned(),
-            tasks: vec![
-                Task {
-                    name: "task-one".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec!["task-two".to_string()],
-                    cmd: vec!["exit".to_string(), "1".to_string()],
-                    env: vec![],
-                    inputs: None,
-                    outputs: None,
-                },
-                Task {
-                    name: "task-zero".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec!["task-one".to_string()],
-                    cmd: vec!["sleep".to_string(), "0.01".to_string()],
-                    env: vec![],
-                    inputs: None,
-                    outputs: None,
-                },
-                Task {
-                    name: "task-two".to_string(),
-                    image: "ubuntu:latest".to_string(),
-                    depends: vec![],
-                    cmd: vec!["sleep".to_string(), "0.01".to_string()],
-                    env: vec![],
-                    inputs: None,
-                    outputs: None,
+    #[test]
+    fn test_get_pods_task_statuses() {
+        let config = test_executor_config();
+
+        let pod = |task_id: i32, phase: &str, deleting: bool| -> Pod {
+            let mut pod = serde_json::json!({
+                "metadata": {
+                    "name": format!("flow-1-task-{}", task_id),
+                    "labels": {
+                        "flowmium.io/flow-id": "1",
+                        "flowmium.io/task-id": task_id.to_string(),
+                    },
                 },
-            ],
+                "status": {"phase": phase},
+            });
+
+            if deleting {
+                pod["metadata"]["deletionTimestamp"] = serde_json::json!("2026-10-17T12:00:00Z");
+            }
+
+            serde_json::from_value(pod).unwrap()
+        };
+
+        let pods = [
+            pod(0, "Running", false),
+            pod(1, "Succeeded", false),
+            pod(2, "Running", false),
+            pod(2, "Running", false),
+            pod(3, "Failed", true),
+            pod(3, "Pending", false),
+            pod(5, "Running", false),
+        ];
+
+        let statuses = get_pods_task_statuses(1, &[0, 1, 2, 3, 4], &pods, &config, Utc::now());
+
+        assert_eq!(
+            statuses.keys().copied().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(statuses[&0].as_ref().unwrap(), &TaskStatus::Running);
+        assert_eq!(statuses[&1].as_ref().unwrap(), &TaskStatus::Finished);
+        assert!(matches!(
+            statuses[&2],
+            Err(ExecutorError::UnexpectedRunnerState(1, 2))
+        ));
+        assert_eq!(statuses[&3].as_ref().unwrap(), &TaskStatus::Pending);
+        assert!(matches!(
+            statuses[&4],
+            Err(ExecutorError::UnexpectedRunnerState(1, 4))
+        ));
+    }
+
+    fn job_with_condition(condition: Option<&str>) -> Job {
+        let mut job = serde_json::json!({
+            "metadata": {"name": "flow-1-task-hello"},
+            "status": {"active": 1},
+        });
+
+        if let Some(condition) = condition {
+            job["status"] = serde_json::json!({
+                "conditions": [{"type": condition, "status": "True"}],
+            });
         }
+
+        serde_json::from_value(job).unwrap()
     }
 
     #[tokio::test]
     #[serial]
-    async fn test_schedule_and_run_tasks_fail() {
-        delete_all_pods().await;
-        delete_all_jobs().await;
-
+    async fn test_reconcile_task_status() {
         let pool = get_test_pool(&["flows", "secrets"]).await;
         let config = test_executor_config();
 
         let sched = Scheduler::new(pool.clone());
         let secrets = SecretsCrud::new(pool.clone());
 
-        let flow_id = instantiate_flow(test_flow_fail(), &sched).await.unwrap();
+        let completed_job = job_with_condition(Some("Complete"));
+        let failed_job = job_with_condition(Some("Failed"));
+        let active_job = job_with_condition(None);
 
-        for _ in 0..30 {
-            tokio::time::sleep(Duration::from_millis(1000)).await;
-            schedule_and_run_tasks(&sched, &config, &secrets).await;
-        }
+        // The pod of the task has been garbage collected
+        let pod_status = || {
+            get_pods_task_statuses(1, &[0], &[], &config, Utc::now())
+                .remove(&0)
+                .unwrap()
+        };
 
         assert_eq!(
-            get_task_status(flow_id, 2, &config).await.unwrap(),
-            TaskStatus::Finished
+            reconcile_task_status(pod_status(), Some(&failed_job)).unwrap(),
+            TaskStatus::Failed
+        );
+        assert!(matches!(
+            reconcile_task_status(pod_status(), Some(&active_job)),
+            Err(ExecutorError::UnexpectedRunnerState(1, 0))
+        ));
+        assert!(matches!(
+            reconcile_task_status(pod_status(), None),
+            Err(ExecutorError::UnexpectedRunnerState(1, 0))
+        ));
+        assert_eq!(
+            reconcile_task_status(Ok(TaskStatus::Running), Some(&completed_job)).unwrap(),
+            TaskStatus::Running
         );
 
+        let status = reconcile_task_status(pod_status(), Some(&completed_job));
+        assert_eq!(status.as_ref().unwrap(), &TaskStatus::Finished);
+
+        let flow = Flow {
+            name: "reconcile".to_owned(),
+            tasks: vec![Task {
+                name: "hello".to_owned(),
+                image: "registry:5000/flowmium-debug".to_owned(),
+                cmd: vec!["echo".to_owned(), "hello".to_owned()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let flow_id = instantiate_flow(flow, &sched, &secrets, &config)
+            .await
+            .unwrap();
+        sched.schedule_tasks(flow_id).await.unwrap();
+        sched.mark_task_running(flow_id, 0).await.unwrap();
+
+        mark_task_with_status(&sched, flow_id, 0, status)
+            .await
+            .unwrap();
+
+        let record = sched.get_flow(flow_id).await.unwrap();
+        assert_eq!(record.status, FlowStatus::Success);
+        assert_eq!(record.finished_tasks, vec![0]);
+    }
+
+    #[test]
+    fn test_get_attempts_task_status() {
+        let config = test_executor_config();
+
+        let attempt = |attempt: u32, phase: &str| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": {
+                    "name": format!("flow-1-task-0-{}", attempt),
+                    "creationTimestamp": format!("2026-10-17T12:00:0{}Z", attempt),
+                    "labels": {
+                        "flowmium.io/flow-id": "1",
+                        "flowmium.io/task-id": "0",
+                    },
+                    "annotations": {
+                        BACKOFF_LIMIT_ANNOTATION: "2",
+                    },
+                },
+                "status": {"phase": phase},
+            }))
+            .unwrap()
+        };
+
+        let status = |pods: &[Pod]| {
+            let pods: Vec<&Pod> = pods.iter().collect();
+            get_attempts_task_status(1, 0, &pods, &config, Utc::now())
+        };
+
         assert_eq!(
-            get_task_status(flow_id, 0, &config).await.unwrap(),
+            status(&[attempt(0, "Running")]).unwrap(),
+            TaskStatus::Running
+        );
+        assert_eq!(
+            status(&[attempt(0, "Failed")]).unwrap(),
+            TaskStatus::Running
+        );
+        assert_eq!(
+            status(&[attempt(0, "Failed"), attempt(1, "Pending")]).unwrap(),
+            TaskStatus::Pending
+        );
+        assert_eq!(
+            status(&[attempt(1, "Succeeded"), attempt(0, "Failed")]).unwrap(),
+            TaskStatus::Finished
+        );
+        assert_eq!(
+            status(&[attempt(0, "Failed"), attempt(1, "Failed")]).unwrap(),
+            TaskStatus::Running
+        );
+        assert_eq!(
+            status(&[
+                attempt(0, "Failed"),
+                attempt(1, "Failed"),
+                attempt(2, "Failed")
+            ])
+            .unwrap(),
             TaskStatus::Failed
         );
-
-        match get_task_status(flow_id, 1, &config).await {
-            Err(ExecutorError::UnexpectedRunnerState(..)) => (),
-            _ => panic!(),
-        }
+        assert!(matches!(
+            status(&[
+                attempt(0, "Failed"),
+                attempt(1, "Failed"),
+                attempt(2, "Failed"),
+                attempt(3, "Running")
+            ]),
+            Err(ExecutorError::UnexpectedRunnerState(1, 0))
+        ));
     }
 }
@@ -0,0 +1,468 @@
+use std::time::Duration;
+
+use futures::{
+    io::AsyncBufReadExt,
+    stream::{self, BoxStream, Stream, StreamExt},
+};
+use k8s_openapi::{
+    api::core::v1::Pod,
+    chrono::{DateTime, Utc},
+};
+use kube::{api::LogParams, Api};
+
+use super::{
+    executor::{
+        get_kubernetes_client, get_pod_backoff_limit, get_pod_phase, get_pod_task_id,
+        list_flow_pods, phase_to_task_status, ExecutorConfig, ExecutorError, TaskStatus,
+    },
+    scheduler::Scheduler,
+};
+
+/// Interval to check for a pod replacing the followed pod of a task, see [`next_followed_pod`].
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+fn prefix_line(task_name: &str, line: &str) -> String {
+    format!("[{}] {}", task_name, line)
+}
+
+/// Lines of a task's logs prefixed with the task's name, followed by a note if the task's pod has terminated.
+fn prefix_task_logs(
+    task_name: String,
+    lines: impl Stream<Item = std::io::Result<String>> + Send + 'static,
+    terminated_phase: Option<String>,
+) -> BoxStream<'static, String> {
+    let prefix = task_name.clone();
+
+    let lines = lines.map(move |line| match line {
+        Ok(line) => prefix_line(&prefix, &line),
+        Err(error) => prefix_line(&prefix, &format!("unable to read logs: {}", error)),
+    });
+
+    let note = terminated_phase
+        .map(|phase| prefix_line(&task_name, &format!("pod terminated with phase {}", phase)));
+
+    lines.chain(stream::iter(note)).boxed()
+}
+
+/// Stream logs of all tasks of a flow interleaved, each line prefixed with the name of the task like `[task-name] line`.
+/// Pods that are pending are skipped, logs of pods that have terminated are followed by a note with the pod's phase.
+/// If `follow` is set, logs of running tasks are streamed until the task terminates.
+#[tracing::instrument(skip(sched, config))]
+pub async fn stream_flow_logs(
+    flow_id: i32,
+    follow: bool,
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+) -> Result<BoxStream<'static, String>, ExecutorError> {
+    let flow = sched.get_flow(flow_id).await?;
+
+    let mut pods: Vec<(i32, Pod)> = list_flow_pods(flow_id, config)
+        .await?
+        .into_iter()
+        .filter_map(|pod| Some((get_pod_task_id(&pod, config)?, pod)))
+        .collect();
+    pods.sort_by_key(|(task_id, _)| *task_id);
+
+    let pods_api: Api<Pod> = Api::namespaced(get_kubernetes_client().await?, &config.namespace);
+
+    let mut notes = vec![];
+    let mut task_logs = vec![];
+
+    for (task_id, pod) in pods {
+        let Some(task_name) = flow.task_index_to_name.get(&task_id).cloned() else {
+            continue;
+        };
+
+        let Some(pod_name) = pod.metadata.name.clone() else {
+            continue;
+        };
+
+        let phase = get_pod_phase(pod).unwrap_or_else(|| "Unknown".to_owned());
+
+        let (follow, terminated_phase) = match phase_to_task_status(&phase) {
+            Some(TaskStatus::Running) => (follow, None),
+            Some(TaskStatus::Finished) | Some(TaskStatus::Failed) => (false, Some(phase)),
+            _ => {
+                notes.push(prefix_line(
+                    &task_name,
+                    &format!("skipping pod in phase {}", phase),
+                ));
+                continue;
+            }
+        };
+
+        let log_params = LogParams {
+            follow,
+            ..Default::default()
+        };
+
+        match pods_api.log_stream(&pod_name, &log_params).await {
+            Ok(log) => task_logs.push(prefix_task_logs(task_name, log.lines(), terminated_phase)),
+            Err(error) => {
+                tracing::warn!(%error, pod_name, "Unable to stream logs for pod");
+                notes.push(prefix_line(
+                    &task_name,
+                    &format!("unable to stream logs: {}", error),
+                ));
+            }
+        }
+    }
+
+    Ok(stream::iter(notes)
+        .chain(stream::select_all(task_logs))
+        .boxed())
+}
+
+/// Pod of a task among pods of its flow, the most recently created one if the task has been retried.
+fn find_task_pod(pods: Vec<Pod>, task_id: i32, config: &ExecutorConfig) -> Option<Pod> {
+    pods.into_iter()
+        .filter(|pod| get_pod_task_id(pod, config) == Some(task_id))
+        .max_by_key(|pod| pod.metadata.creation_timestamp.clone())
+}
+
+/// What to do once the logs of the followed pod of a task have ended.
+#[derive(Debug, PartialEq)]
+enum FollowNext {
+    /// Follow logs of the pod, either a pod replacing the followed pod or the followed pod itself
+    /// if its logs ended while it is still running, like when the connection to Kubernetes was closed.
+    Follow(String),
+    /// Kubernetes is yet to replace the failed pod ([`crate::model::Task::backoff_limit`]) or the pod replacing
+    /// it is pending.
+    Wait,
+    /// The task has terminated.
+    Stop,
+}
+
+/// Next pod to follow logs of after logs of `followed` have ended, among pods of the task's flow.
+fn next_followed_pod(
+    pods: Vec<Pod>,
+    task_id: i32,
+    followed: &str,
+    config: &ExecutorConfig,
+) -> FollowNext {
+    let attempts = pods
+        .iter()
+        .filter(|pod| get_pod_task_id(pod, config) == Some(task_id))
+        .count();
+
+    let Some(pod) = find_task_pod(pods, task_id, config) else {
+        return FollowNext::Stop;
+    };
+
+    let Some(pod_name) = pod.metadata.name.clone() else {
+        return FollowNext::Stop;
+    };
+
+    let is_followed = pod_name == followed;
+    let backoff_limit = get_pod_backoff_limit(&pod);
+    let phase = get_pod_phase(pod).unwrap_or_default();
+
+    match phase_to_task_status(&phase) {
+        Some(TaskStatus::Pending) => FollowNext::Wait,
+        Some(TaskStatus::Running) => FollowNext::Follow(pod_name),
+        Some(TaskStatus::Finished) | Some(TaskStatus::Failed) if !is_followed => {
+            FollowNext::Follow(pod_name)
+        }
+        Some(TaskStatus::Failed) if attempts <= backoff_limit => FollowNext::Wait,
+        _ => FollowNext::Stop,
+    }
+}
+
+async fn get_log_lines(
+    pods_api: &Api<Pod>,
+    pod_name: &str,
+    follow: bool,
+    since_time: Option<DateTime<Utc>>,
+) -> Result<BoxStream<'static, String>, kube::Error> {
+    let log_params = LogParams {
+        follow,
+        since_time,
+        ..Default::default()
+    };
+
+    let log = pods_api.log_stream(pod_name, &log_params).await?;
+
+    Ok(log
+        .lines()
+        .map(|line| line.unwrap_or_else(|error| format!("unable to read logs: {}", error)))
+        .boxed())
+}
+
+struct FollowedTask {
+    pods_api: Api<Pod>,
+    config: ExecutorConfig,
+    flow_id: i32,
+    task_id: i32,
+    pod_name: String,
+    lines: BoxStream<'static, String>,
+}
+
+/// Keep streaming logs of a task after logs of its pod have ended, until the task terminates.
+/// Logs of pods replacing the pod are streamed after it, see [`next_followed_pod`].
+fn follow_task_logs(task: FollowedTask) -> BoxStream<'static, String> {
+    stream::unfold(Some(task), |task| async move {
+        let mut task = task?;
+
+        loop {
+            if let Some(line) = task.lines.next().await {
+                return Some((line, Some(task)));
+            }
+
+            task.lines = stream::empty().boxed();
+            let ended_at = Utc::now();
+
+            let pods = match list_flow_pods(task.flow_id, &task.config).await {
+                Ok(pods) => pods.items,
+                Err(error) => return Some((format!("unable to follow logs: {}", error), None)),
+            };
+
+            let since_time =
+                match next_followed_pod(pods, task.task_id, &task.pod_name, &task.config) {
+                    FollowNext::Stop => return None,
+                    FollowNext::Wait => {
+                        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+                        continue;
+                    }
+                    // Pod's status may not have been updated yet after its container has exited
+                    FollowNext::Follow(pod_name) if pod_name == task.pod_name => {
+                        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+                        Some(ended_at)
+                    }
+                    FollowNext::Follow(pod_name) => {
+                        tracing::info!(pod_name, "Following logs of pod replacing task's pod");
+                        task.pod_name = pod_name;
+                        None
+                    }
+                };
+
+            task.lines = match get_log_lines(&task.pods_api, &task.pod_name, true, since_time).await
+            {
+                Ok(lines) => lines,
+                Err(error) => return Some((format!("unable to follow logs: {}", error), None)),
+            };
+        }
+    })
+    .boxed()
+}
+
+/// Stream logs of a single task of a flow as they are, without prefixing lines with the task's name.
+/// If `follow` is set and the task has not finished, logs are streamed until the task terminates,
+/// including logs of pods Kubernetes creates to replace failed pods of the task.
+#[tracing::instrument(skip(sched, config))]
+pub async fn stream_task_logs(
+    flow_id: i32,
+    task_id: i32,
+    follow: bool,
+    sched: &Scheduler,
+    config: &ExecutorConfig,
+) -> Result<BoxStream<'static, String>, ExecutorError> {
+    let flow = sched.get_flow(flow_id).await?;
+
+    if !flow.task_index_to_name.contains_key(&task_id) {
+        return Err(ExecutorError::TaskDoesNotExist(flow_id, task_id));
+    }
+
+    let pods = list_flow_pods(flow_id, config).await?;
+
+    let Some(pod) = find_task_pod(pods.items, task_id, config) else {
+        return Err(ExecutorError::TaskPodNotFound(flow_id, task_id));
+    };
+
+    let Some(pod_name) = pod.metadata.name.clone() else {
+        return Err(ExecutorError::UnexpectedRunnerState(flow_id, task_id));
+    };
+
+    let phase = get_pod_phase(pod).unwrap_or_else(|| "Unknown".to_owned());
+
+    let follow = match phase_to_task_status(&phase) {
+        Some(TaskStatus::Running) | Some(TaskStatus::Failed) => follow,
+        Some(TaskStatus::Finished) => false,
+        _ => {
+            return Err(ExecutorError::TaskLogsUnavailable(
+                flow_id,
+                task_id,
+                format!("pod is in phase {}", phase),
+            ))
+        }
+    };
+
+    let pods_api: Api<Pod> = Api::namespaced(get_kubernetes_client().await?, &config.namespace);
+
+    let lines = match get_log_lines(&pods_api, &pod_name, follow, None).await {
+        Ok(lines) => lines,
+        // Pod was garbage collected after it was listed
+        Err(kube::Error::Api(response)) if response.code == 404 => {
+            return Err(ExecutorError::TaskPodNotFound(flow_id, task_id))
+        }
+        Err(error) => {
+            tracing::error!(%error, pod_name, "Unable to stream logs for pod");
+            return Err(ExecutorError::UnableToConnectToKubernetes(error));
+        }
+    };
+
+    match follow {
+        true => Ok(follow_task_logs(FollowedTask {
+            pods_api,
+            config: config.clone(),
+            flow_id,
+            task_id,
+            pod_name,
+            lines,
+        })),
+        false => Ok(lines),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> ExecutorConfig {
+        serde_json::from_value(serde_json::json!({
+            "store_url": "http://localhost:9000",
+            "task_store_url": "http://localhost:9000",
+            "bucket_name": "flowmium-test",
+            "access_key": "minio",
+            "secret_key": "password",
+            "init_container_image": "flowmium",
+            "namespace": "default",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_prefix_task_logs() {
+        let lines = stream::iter(vec![
+            Ok("hello".to_owned()),
+            Err(std::io::Error::other("connection reset")),
+        ]);
+
+        let logs: Vec<String> =
+            prefix_task_logs("task-a".to_owned(), lines, Some("Failed".to_owned()))
+                .collect()
+                .await;
+
+        assert_eq!(
+            logs,
+            vec![
+                "[task-a] hello",
+                "[task-a] unable to read logs: connection reset",
+                "[task-a] pod terminated with phase Failed"
+            ]
+        );
+
+        let logs: Vec<String> = prefix_task_logs(
+            "task-b".to_owned(),
+            stream::iter(vec![Ok("world".to_owned())]),
+            None,
+        )
+        .collect()
+        .await;
+
+        assert_eq!(logs, vec!["[task-b] world"]);
+    }
+
+    #[test]
+    fn test_find_task_pod() {
+        let config = test_config();
+
+        let pod = |name: &str, task_id: i32, created_at: &str| {
+            serde_json::from_value::<Pod>(serde_json::json!({
+                "metadata": {
+                    "name": name,
+                    "creationTimestamp": created_at,
+                    "labels": {
+                        "flowmium.io/flow-id": "1",
+                        "flowmium.io/task-id": task_id.to_string(),
+                    },
+                },
+            }))
+            .unwrap()
+        };
+
+        let pods = vec![
+            pod("task-0-retried", 0, "2026-10-17T10:05:00Z"),
+            pod("task-1", 1, "2026-10-17T10:00:00Z"),
+            pod("task-0", 0, "2026-10-17T10:00:00Z"),
+        ];
+
+        let name = |pod: Option<Pod>| pod.and_then(|pod| pod.metadata.name);
+
+        assert_eq!(
+            name(find_task_pod(pods.clone(), 0, &config)),
+            Some("task-0-retried".to_owned())
+        );
+        assert_eq!(
+            name(find_task_pod(pods.clone(), 1, &config)),
+            Some("task-1".to_owned())
+        );
+        assert_eq!(name(find_task_pod(pods, 2, &config)), None);
+    }
+
+    #[test]
+    fn test_next_followed_pod() {
+        let config = test_config();
+
+        let pod = |name: &str, created_at: &str, phase: &str| {
+            serde_json::from_value::<Pod>(serde_json::json!({
+                "metadata": {
+                    "name": name,
+                    "creationTimestamp": created_at,
+                    "labels": {
+                        "flowmium.io/flow-id": "1",
+                        "flowmium.io/task-id": "0",
+                    },
+                    "annotations": {
+                        "flowmium.io/backoff-limit": "1",
+                    },
+                },
+                "status": {
+                    "phase": phase,
+                },
+            }))
+            .unwrap()
+        };
+
+        let next = |pods: Vec<Pod>| next_followed_pod(pods, 0, "task-0", &config);
+
+        assert_eq!(
+            next(vec![pod("task-0", "2026-10-17T10:00:00Z", "Running")]),
+            FollowNext::Follow("task-0".to_owned())
+        );
+        assert_eq!(
+            next(vec![pod("task-0", "2026-10-17T10:00:00Z", "Succeeded")]),
+            FollowNext::Stop
+        );
+        assert_eq!(
+            next(vec![pod("task-0", "2026-10-17T10:00:00Z", "Failed")]),
+            FollowNext::Wait
+        );
+        assert_eq!(
+            next(vec![
+                pod("task-0", "2026-10-17T10:00:00Z", "Failed"),
+                pod("task-0-retried", "2026-10-17T10:05:00Z", "Pending"),
+            ]),
+            FollowNext::Wait
+        );
+        assert_eq!(
+            next(vec![
+                pod("task-0", "2026-10-17T10:00:00Z", "Failed"),
+                pod("task-0-retried", "2026-10-17T10:05:00Z", "Running"),
+            ]),
+            FollowNext::Follow("task-0-retried".to_owned())
+        );
+        assert_eq!(
+            next_followed_pod(
+                vec![
+                    pod("task-0", "2026-10-17T10:00:00Z", "Failed"),
+                    pod("task-0-retried", "2026-10-17T10:05:00Z", "Failed"),
+                ],
+                0,
+                "task-0-retried",
+                &config
+            ),
+            FollowNext::Stop
+        );
+        assert_eq!(next(vec![]), FollowNext::Stop);
+    }
+}
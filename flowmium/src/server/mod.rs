@@ -1,11 +1,17 @@
-mod api;
+pub mod api;
 pub(crate) mod args;
 pub mod driver;
 pub mod event;
 pub mod executor;
+pub mod logs;
 pub mod model;
 pub mod planner;
 mod pool;
+mod rate_limit;
+pub mod reaper;
 pub mod record;
 pub mod scheduler;
 pub mod secrets;
+pub mod simulation;
+pub mod sink;
+mod usage;
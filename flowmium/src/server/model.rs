@@ -1,3 +1,6 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use k8s_openapi::api::core::v1::{EnvFromSource, Toleration, Volume, VolumeMount};
 use serde::{Deserialize, Serialize};
 
 /// String literal environment variable.
@@ -20,6 +23,30 @@ pub struct SecretRef {
     pub from_secret: String,
 }
 
+/// Environment variable whose value comes from a key of a Kubernetes secret in the namespace the tasks run in.
+/// The value is resolved by Kubernetes when the task's pod starts and is never read or stored by the server.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct K8sSecretRef {
+    /// Name for the environment variable.
+    pub name: String,
+    /// Name of the Kubernetes secret.
+    pub secret_name: String,
+    /// Key within the Kubernetes secret to extract the value from.
+    pub secret_key: String,
+}
+
+/// Environment variable whose value is read from an input of the task after it has been downloaded, right before the
+/// task's command is run. Whitespace around the contents of the input, like a trailing newline, is trimmed.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InputRef {
+    /// Name for the environment variable.
+    pub name: String,
+    /// Name of an input of the task as given by [`Input::from`], the input cannot be a directory.
+    pub from_input: String,
+}
+
 /// Define an environment variable for the task.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
@@ -28,37 +55,74 @@ pub enum EnvVar {
     KeyValuePair(KeyValuePair),
     /// Create an environment variable with a value from a secret stored in the server.
     SecretRef(SecretRef),
+    /// Create an environment variable with a value from a Kubernetes secret.
+    K8sSecretRef(K8sSecretRef),
+    /// Create an environment variable with a value from an input of the task.
+    InputRef(InputRef),
 }
 
 /// An input file consumed by the task.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Input {
-    /// Name of the output from a dependent task.
+    /// Name of the output from a task this task depends on, directly or through other tasks.
+    /// Can refer to the task's environment variables like `result-${SHARD}`.
     pub from: String,
     /// Path to which the output should be copied to within the task container.
+    /// Outputs that are a directory are extracted into a directory at this path.
     pub path: String,
 }
 
 /// An output file emitted by this task.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Output {
-    /// Name for the output.
+    /// Name for the output. Can refer to the task's environment variables like `result-${SHARD}`,
+    /// which are resolved when the flow is planned.
     pub name: String,
-    /// Path to the output file inside the task container.
+    /// Path to the output file inside the task container. Can be a directory, which is uploaded as a tar archive.
     pub path: String,
+    /// Retain the output for this many seconds after the flow has finished instead of the global artefact TTL,
+    /// see [`crate::reaper`]. Can be used to keep important outputs like final reports longer than scratch data.
+    pub retention_seconds: Option<u64>,
+    /// Check that the task wrote the output before uploading any of the task's outputs, the task fails with the
+    /// missing outputs as the reason instead. Outputs that are not required and are missing fail the task when
+    /// they are uploaded, after outputs uploaded before them.
+    #[serde(default)]
+    pub required: bool,
 }
 
 // TODO: Add kubernetes config
-// active_deadline_seconds: 34
 // affinity: 34
-// tolerations: 34
-// image_pull_secrets: 34
 // priority: 3
-// limits: 23
-// requests: 23
+
+/// CPU and memory resources of a task's container. Values are Kubernetes quantities like `500m` or `1` for CPU
+/// and `256Mi` or `1Gi` for memory. Unset values are left to the cluster's defaults.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct ResourceRequirements {
+    /// CPU reserved for the container, Kubernetes only schedules the pod on a node with this much CPU available.
+    pub cpu_request: Option<String>,
+    /// Memory reserved for the container, Kubernetes only schedules the pod on a node with this much memory available.
+    pub memory_request: Option<String>,
+    /// CPU the container is throttled to.
+    pub cpu_limit: Option<String>,
+    /// Memory the container is killed for exceeding, which fails the task.
+    pub memory_limit: Option<String>,
+}
+
+/// When a task runs depending on how the tasks it depends on have terminated, see [`Task::on`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RunCondition {
+    /// Run if all tasks the task depends on have finished successfully, the task is skipped otherwise.
+    #[default]
+    OnSuccess,
+    /// Run if a task the task depends on, directly or indirectly, has failed, the task is skipped otherwise.
+    OnFailure,
+    /// Run once all tasks the task depends on have terminated, whether they have finished, failed or were skipped.
+    Always,
+}
 
 /// Defines a single task belonging to a flow.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Task {
     /// Name for the task.
     pub name: String,
@@ -66,24 +130,547 @@ pub struct Task {
     pub image: String,
     /// List of names of the task that this task depends on.
     pub depends: Vec<String>,
-    /// Command to be executed inside the container image to run that task.
+    /// When the task runs depending on how the tasks it depends on have terminated, like a task that cleans up
+    /// or sends a notification when a task fails. The flow keeps running after a task fails if it has tasks
+    /// that run on failure or always, and fails once all of its tasks have terminated or were skipped.
+    /// Tasks only run if all tasks they depend on have finished successfully by default.
+    #[serde(default)]
+    pub on: RunCondition,
+    /// Command to be executed inside the container image to run that task. Tasks without inputs, outputs,
+    /// a timeout, a working directory or stdin run the image's entrypoint if it is empty.
     pub cmd: Vec<String>,
     /// List of environment variables for the task.
     pub env: Vec<EnvVar>,
+    /// Kubernetes config maps or secrets whose keys are all set as environment variables of the task's container,
+    /// like `[{configMapRef: {name: app-config}}, {secretRef: {name: db-credentials}}]`. Unlike `env`, these are
+    /// passed through to Kubernetes and are not secrets stored in the server.
+    pub env_from: Option<Vec<EnvFromSource>>,
+    /// Working directory the task's command is run in. The container image's working directory is used if not set.
+    pub working_dir: Option<String>,
+    /// Text written to the standard input of the task's command, which reads an empty input if not set.
+    pub stdin: Option<String>,
     /// List of input files that this task will consume. Each input will refer to
     /// an output file from a dependent task.
     pub inputs: Option<Vec<Input>>,
     /// List of output files emitted by this task.
     pub outputs: Option<Vec<Output>>,
+    /// Deadline in seconds for the task's pod, after which Kubernetes kills the pod and the task fails.
+    /// The time is measured from when the pod starts, which includes downloading inputs and uploading outputs.
+    pub active_deadline_seconds: Option<u64>,
+    /// Timeout in seconds for the task's command. When the command does not finish in time,
+    /// it is killed and the task fails with the timeout recorded as the pod's termination message.
+    /// Has to be shorter than `active_deadline_seconds` so that the timeout fires before Kubernetes kills the pod.
+    /// Defaults to `active_deadline_seconds` minus a grace period of a tenth of it (at most 30 seconds).
+    pub timeout_seconds: Option<u64>,
+    /// Experimental, spawn the task along with the stage before its own stage instead of waiting for that stage
+    /// to finish, so that inputs are downloaded as soon as they are uploaded by the task producing them.
+    /// The task has to have an input from every task it depends on and has to set `active_deadline_seconds`,
+    /// so that it does not wait forever if a dependency fails.
+    #[serde(default)]
+    pub eager_inputs: bool,
+    /// Seconds the task's pod is given to shut down gracefully after it is sent a termination signal,
+    /// like when the flow is cancelled or the pod's active deadline is exceeded. Zero kills the pod immediately.
+    /// Kubernetes' default of 30 seconds is used if not set.
+    pub termination_grace_period_seconds: Option<i64>,
+    /// Extra Kubernetes volumes for the task's pod, like a shared persistent volume claim or a projected
+    /// service account token. The name `executable` is reserved by flowmium.
+    pub volumes: Option<Vec<Volume>>,
+    /// Mounts for the task's container, each referring to one of the task's `volumes`.
+    /// The path of the volume the flowmium executable is copied into, `/var/run` unless configured otherwise
+    /// ([`crate::executor::ExecutorConfig::shared_volume_path`]), and paths under the executable are reserved.
+    pub volume_mounts: Option<Vec<VolumeMount>>,
+    /// Number of times the task is spawned again after it fails before the flow fails. Tasks that depend on it
+    /// wait until it succeeds and never run if it has run out of retries. The task is not retried by default.
+    pub retries: Option<u32>,
+    /// Seconds to wait before spawning the task again after it fails, doubled on every further retry.
+    /// The task is retried right away if not set.
+    pub retry_backoff_seconds: Option<u64>,
+    /// Number of times Kubernetes creates a new pod for the task's job after its pod fails, before the task fails.
+    /// Unlike `retries`, the failed pod is replaced by Kubernetes with its own backoff between attempts, so this
+    /// should only be set for idempotent tasks. The task is considered running until the last attempt has failed.
+    /// Defaults to zero.
+    pub backoff_limit: Option<i32>,
+    /// CPU and memory requests and limits for the task's container.
+    pub resources: Option<ResourceRequirements>,
+    /// Labels a node has to have for the task's pod to be scheduled on it, like a label of nodes with GPUs.
+    pub node_selector: Option<BTreeMap<String, String>>,
+    /// Taints of nodes the task's pod tolerates, so that it can be scheduled on nodes reserved for such tasks.
+    pub tolerations: Option<Vec<Toleration>>,
+    /// Names of Kubernetes secrets with credentials for pulling the task's image from a private registry.
+    /// Replaces the server's default list ([`crate::executor::ExecutorConfig::image_pull_secrets`]) if set.
+    pub image_pull_secrets: Option<Vec<String>>,
+    /// Extra labels added to the task's pod, like labels selected by network policies or cost reports.
+    /// Labels flowmium uses to find the task's pods replace labels with the same key.
+    pub pod_labels: Option<BTreeMap<String, String>>,
+    /// Extra annotations added to the task's pod, like annotations for a service mesh or a metrics scraper.
+    /// Annotations with the prefix `flowmium.io/` are reserved by flowmium.
+    pub pod_annotations: Option<BTreeMap<String, String>>,
+}
+
+impl Task {
+    /// Resolve `${VAR}` references in `template` against the task's literal environment variables
+    /// ([`EnvVar::KeyValuePair`]). Secrets are not resolved, so that their values never end up in artefact names.
+    /// Returns the name of the first variable that could not be resolved as error.
+    pub fn resolve_env_refs(&self, template: &str) -> Result<String, String> {
+        let mut resolved = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+
+            let var_name = &rest[start + 2..start + 2 + len];
+
+            let value = self.env.iter().find_map(|env| match env {
+                EnvVar::KeyValuePair(KeyValuePair { name, value }) if name == var_name => {
+                    Some(value)
+                }
+                _ => None,
+            });
+
+            let Some(value) = value else {
+                return Err(var_name.to_owned());
+            };
+
+            resolved.push_str(&rest[..start]);
+            resolved.push_str(value);
+            rest = &rest[start + 2 + len + 1..];
+        }
+
+        resolved.push_str(rest);
+
+        Ok(resolved)
+    }
+
+    /// Copy of the task with `${VAR}` references in [`Output::name`], [`Input::from`] and [`InputRef::from_input`]
+    /// resolved, see [`Task::resolve_env_refs`].
+    pub fn with_resolved_artefact_names(&self) -> Result<Task, String> {
+        let mut task = self.clone();
+
+        for input in task.inputs.iter_mut().flatten() {
+            input.from = self.resolve_env_refs(&input.from)?;
+        }
+
+        for env in task.env.iter_mut() {
+            if let EnvVar::InputRef(InputRef { from_input, .. }) = env {
+                *from_input = self.resolve_env_refs(from_input)?;
+            }
+        }
+
+        for output in task.outputs.iter_mut().flatten() {
+            output.name = self.resolve_env_refs(&output.name)?;
+        }
+
+        Ok(task)
+    }
+}
+
+/// Secret values submitted along with a flow, mapping secret key to value. Values are never printed in debug output.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(transparent)]
+pub struct InlineSecrets(pub BTreeMap<String, String>);
+
+impl InlineSecrets {
+    /// Returns `true` if there are no inline secrets.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if there is an inline secret for the key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+}
+
+impl fmt::Debug for InlineSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.keys().map(|key| (key, "<redacted>")))
+            .finish()
+    }
+}
+
+/// Environment variable overrides for re-running an existing flow with a tweaked environment,
+/// see [`crate::executor::rerun_flow`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct EnvOverrides {
+    /// Environment variables to set, mapping name to value. Replaces variables with the same name
+    /// in the original definition, including ones referring to a secret.
+    pub env: BTreeMap<String, String>,
+    /// Names of the tasks to apply the overrides to, overrides are applied to all tasks if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<String>,
 }
 
 /// Defines a workflow composed of multiple tasks that depend on each other in a DAG.
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
 pub struct Flow {
     /// Name for the flow.
     pub name: String,
     /// Set of tasks in a DAG.
     pub tasks: Vec<Task>,
+    /// Environment variables set for every task of the flow, like `ENVIRONMENT=prod`. A task's own variable with
+    /// the same name takes precedence. Variables with values from inputs ([`InputRef`]) are task specific and cannot
+    /// be set for the flow, and the variables are not used to resolve `${VAR}` references in names of artefacts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<EnvVar>,
+    /// Secrets scoped to this flow only, that can be referred by [`SecretRef`] like secrets stored in the server.
+    /// They take precedence over secrets stored in the server with the same key and are deleted once the flow
+    /// has terminated, without ever being added to the server's secrets.
+    #[serde(default, skip_serializing_if = "InlineSecrets::is_empty")]
+    pub secrets: InlineSecrets,
+    /// Re-run the flow periodically, this many seconds after the previous run has terminated.
+    /// Runs never overlap, the interval is measured from when the previous run succeeded or failed, not wall-clock time.
+    pub interval_seconds: Option<u64>,
+    /// Cron expression to run the flow on, submitting the flow registers it as a recurring flow instead of running it.
+    /// A new run is created at each time the expression matches in UTC. Either five fields (minute, hour, day of month,
+    /// month and day of week) or six to seven fields with seconds and year, like `0 */15 * * * *`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// Image to use for the init container that copies the flowmium executable into the flow's tasks,
+    /// overriding [`crate::executor::ExecutorConfig::init_container_image`]. Useful for pinning a flow to a specific
+    /// flowmium version during a canary rollout. The init container and the server may be incompatible if their
+    /// versions do not match.
+    pub init_container_image: Option<String>,
+    /// Name of a task in `tasks` to run when any other task of the flow fails, like a cleanup task that tears down
+    /// resources created by the flow. The task is not part of the flow's plan, it runs at most once and the flow is
+    /// marked as failed only after it has terminated. It cannot depend on other tasks and other tasks cannot depend on it.
+    pub on_failure: Option<String>,
+    /// Maximum number of tasks of the flow that run at the same time, so that a stage with many tasks does not
+    /// overwhelm a small cluster. Remaining tasks of the stage are spawned as running tasks terminate, tasks waiting
+    /// to be retried count as running. Unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel_tasks: Option<usize>,
+    /// Seconds after the flow was created by which it has to have terminated. The flow is aborted once the deadline
+    /// has passed, its running tasks are marked as failed and their pods are deleted. Each run of a periodic or
+    /// recurring flow has its own deadline. The flow has no deadline if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_seconds: Option<u64>,
+    /// Bucket to store artefacts of the flow in, overriding [`crate::executor::ExecutorConfig::bucket_name`], like a
+    /// bucket for data of a different classification. The bucket is on the same store and accessed with the same
+    /// credentials as the server's bucket, and is created by the flow's tasks if it does not exist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket_name: Option<String>,
+}
+
+impl Flow {
+    /// Index of the [`Flow::on_failure`] task in `tasks`.
+    pub fn get_on_failure_task_id(&self) -> Option<usize> {
+        let on_failure = self.on_failure.as_ref()?;
+
+        self.tasks.iter().position(|task| &task.name == on_failure)
+    }
+}
+
+/// Builder for a [`Flow`], an alternative to filling out the flow's fields by hand.
+///
+/// ```
+/// use flowmium::model::*;
+///
+/// let flow = FlowBuilder::new("hello-world")
+///     .task(
+///         TaskBuilder::new("producer", "debian:latest")
+///             .cmd(["sh", "-c", "echo $MESSAGE > /greeting.txt"])
+///             .secret_env("MESSAGE", "super-secret-message")
+///             .output("greeting", "/greeting.txt"),
+///     )
+///     .task(
+///         TaskBuilder::new("consumer", "debian:latest")
+///             .depends_on("producer")
+///             .cmd(["cat", "/greeting.txt"])
+///             .env_literal("LANG", "C")
+///             .input("greeting", "/greeting.txt"),
+///     )
+///     .build();
+///
+/// let expected = Flow {
+///     name: "hello-world".to_string(),
+///     tasks: vec![
+///         Task {
+///             name: "producer".to_string(),
+///             image: "debian:latest".to_string(),
+///             cmd: vec![
+///                 "sh".to_string(),
+///                 "-c".to_string(),
+///                 "echo $MESSAGE > /greeting.txt".to_string(),
+///             ],
+///             env: vec![EnvVar::SecretRef(SecretRef {
+///                 name: "MESSAGE".to_string(),
+///                 from_secret: "super-secret-message".to_string(),
+///             })],
+///             outputs: Some(vec![Output {
+///                 name: "greeting".to_string(),
+///                 path: "/greeting.txt".to_string(),
+///                 ..Default::default()
+///             }]),
+///             ..Default::default()
+///         },
+///         Task {
+///             name: "consumer".to_string(),
+///             image: "debian:latest".to_string(),
+///             depends: vec!["producer".to_string()],
+///             cmd: vec!["cat".to_string(), "/greeting.txt".to_string()],
+///             env: vec![EnvVar::KeyValuePair(KeyValuePair {
+///                 name: "LANG".to_string(),
+///                 value: "C".to_string(),
+///             })],
+///             inputs: Some(vec![Input {
+///                 from: "greeting".to_string(),
+///                 path: "/greeting.txt".to_string(),
+///             }]),
+///             ..Default::default()
+///         },
+///     ],
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(flow, expected);
+/// ```
+#[derive(Debug, Default)]
+pub struct FlowBuilder {
+    flow: Flow,
+}
+
+impl FlowBuilder {
+    /// Start building a flow with the given name and no tasks.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            flow: Flow {
+                name: name.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Add a task to the flow, see [`TaskBuilder`].
+    pub fn task(mut self, task: impl Into<Task>) -> Self {
+        self.flow.tasks.push(task.into());
+        self
+    }
+
+    /// Add an environment variable with a string literal value to every task of the flow, see [`Flow::env`].
+    pub fn env_literal(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.flow.env.push(EnvVar::KeyValuePair(KeyValuePair {
+            name: name.into(),
+            value: value.into(),
+        }));
+        self
+    }
+
+    /// Add an environment variable with a value from a secret stored in the server to every task of the flow,
+    /// see [`Flow::env`] and [`SecretRef`].
+    pub fn secret_env(mut self, name: impl Into<String>, from_secret: impl Into<String>) -> Self {
+        self.flow.env.push(EnvVar::SecretRef(SecretRef {
+            name: name.into(),
+            from_secret: from_secret.into(),
+        }));
+        self
+    }
+
+    /// Set [`Flow::interval_seconds`].
+    pub fn interval_seconds(mut self, interval_seconds: u64) -> Self {
+        self.flow.interval_seconds = Some(interval_seconds);
+        self
+    }
+
+    /// Set [`Flow::schedule`].
+    pub fn schedule(mut self, schedule: impl Into<String>) -> Self {
+        self.flow.schedule = Some(schedule.into());
+        self
+    }
+
+    /// Set [`Flow::deadline_seconds`].
+    pub fn deadline_seconds(mut self, deadline_seconds: u64) -> Self {
+        self.flow.deadline_seconds = Some(deadline_seconds);
+        self
+    }
+
+    /// Set [`Flow::max_parallel_tasks`].
+    pub fn max_parallel_tasks(mut self, max_parallel_tasks: usize) -> Self {
+        self.flow.max_parallel_tasks = Some(max_parallel_tasks);
+        self
+    }
+
+    /// Set [`Flow::bucket_name`].
+    pub fn bucket_name(mut self, bucket_name: impl Into<String>) -> Self {
+        self.flow.bucket_name = Some(bucket_name.into());
+        self
+    }
+
+    /// Set [`Flow::init_container_image`].
+    pub fn init_container_image(mut self, image: impl Into<String>) -> Self {
+        self.flow.init_container_image = Some(image.into());
+        self
+    }
+
+    /// Set [`Flow::on_failure`] to the name of one of the flow's tasks.
+    pub fn on_failure(mut self, task_name: impl Into<String>) -> Self {
+        self.flow.on_failure = Some(task_name.into());
+        self
+    }
+
+    /// Add an inline secret, see [`Flow::secrets`].
+    pub fn inline_secret(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.flow.secrets.0.insert(key.into(), value.into());
+        self
+    }
+
+    /// Finish building the flow. The flow is not validated, that happens when it is submitted.
+    pub fn build(self) -> Flow {
+        self.flow
+    }
+}
+
+/// Builder for a [`Task`] of a flow, see [`FlowBuilder`] for an example.
+#[derive(Debug, Clone, Default)]
+pub struct TaskBuilder {
+    task: Task,
+}
+
+impl TaskBuilder {
+    /// Start building a task with the given name and container image.
+    pub fn new(name: impl Into<String>, image: impl Into<String>) -> Self {
+        Self {
+            task: Task {
+                name: name.into(),
+                image: image.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Add a task this task depends on by its name.
+    pub fn depends_on(mut self, task_name: impl Into<String>) -> Self {
+        self.task.depends.push(task_name.into());
+        self
+    }
+
+    /// Set the command to run inside the container, see [`Task::cmd`].
+    pub fn cmd<I, S>(mut self, cmd: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.task.cmd = cmd.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add an environment variable with a string literal value.
+    pub fn env_literal(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.task.env.push(EnvVar::KeyValuePair(KeyValuePair {
+            name: name.into(),
+            value: value.into(),
+        }));
+        self
+    }
+
+    /// Add an environment variable with a value from a secret stored in the server, see [`SecretRef`].
+    pub fn secret_env(mut self, name: impl Into<String>, from_secret: impl Into<String>) -> Self {
+        self.task.env.push(EnvVar::SecretRef(SecretRef {
+            name: name.into(),
+            from_secret: from_secret.into(),
+        }));
+        self
+    }
+
+    /// Add an environment variable with a value from a key of a Kubernetes secret, see [`K8sSecretRef`].
+    pub fn k8s_secret_env(
+        mut self,
+        name: impl Into<String>,
+        secret_name: impl Into<String>,
+        secret_key: impl Into<String>,
+    ) -> Self {
+        self.task.env.push(EnvVar::K8sSecretRef(K8sSecretRef {
+            name: name.into(),
+            secret_name: secret_name.into(),
+            secret_key: secret_key.into(),
+        }));
+        self
+    }
+
+    /// Add an input downloaded to `path` from the output named `from` of a task this task depends on.
+    pub fn input(mut self, from: impl Into<String>, path: impl Into<String>) -> Self {
+        self.task.inputs.get_or_insert_with(Vec::new).push(Input {
+            from: from.into(),
+            path: path.into(),
+        });
+        self
+    }
+
+    /// Add an environment variable with a value read from the task's input named `from_input`, see [`InputRef`].
+    pub fn input_env(mut self, name: impl Into<String>, from_input: impl Into<String>) -> Self {
+        self.task.env.push(EnvVar::InputRef(InputRef {
+            name: name.into(),
+            from_input: from_input.into(),
+        }));
+        self
+    }
+
+    /// Add an output uploaded from `path` once the task has succeeded.
+    pub fn output(mut self, name: impl Into<String>, path: impl Into<String>) -> Self {
+        self.task.outputs.get_or_insert_with(Vec::new).push(Output {
+            name: name.into(),
+            path: path.into(),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Set when the task runs depending on the tasks it depends on, see [`Task::on`].
+    pub fn on(mut self, condition: RunCondition) -> Self {
+        self.task.on = condition;
+        self
+    }
+
+    /// Set [`Task::retries`].
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.task.retries = Some(retries);
+        self
+    }
+
+    /// Set [`Task::backoff_limit`].
+    pub fn backoff_limit(mut self, backoff_limit: i32) -> Self {
+        self.task.backoff_limit = Some(backoff_limit);
+        self
+    }
+
+    /// Set [`Task::active_deadline_seconds`].
+    pub fn active_deadline_seconds(mut self, seconds: u64) -> Self {
+        self.task.active_deadline_seconds = Some(seconds);
+        self
+    }
+
+    /// Set [`Task::timeout_seconds`].
+    pub fn timeout_seconds(mut self, seconds: u64) -> Self {
+        self.task.timeout_seconds = Some(seconds);
+        self
+    }
+
+    /// Set [`Task::resources`].
+    pub fn resources(mut self, resources: ResourceRequirements) -> Self {
+        self.task.resources = Some(resources);
+        self
+    }
+
+    /// Finish building the task.
+    pub fn build(self) -> Task {
+        self.task
+    }
+}
+
+impl From<TaskBuilder> for Task {
+    fn from(builder: TaskBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Parse a cron expression of [`Flow::schedule`], five field expressions without seconds run at the start of the minute.
+pub fn parse_cron_schedule(schedule: &str) -> Result<cron::Schedule, cron::error::Error> {
+    match schedule.split_whitespace().count() {
+        5 => cron::Schedule::from_str(&format!("0 {}", schedule)),
+        _ => cron::Schedule::from_str(schedule),
+    }
 }
 
 #[cfg(test)]
@@ -144,10 +731,50 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "some-random-output".to_owned(),
                     path: "/some/random/output/path".to_owned(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         assert_eq!(job, job_expected);
     }
+
+    #[test]
+    fn test_inline_secrets_debug_redacted() {
+        let secrets = InlineSecrets(BTreeMap::from([(
+            "api-token".to_owned(),
+            "hunter2".to_owned(),
+        )]));
+
+        let debug = format!("{:?}", secrets);
+
+        assert!(debug.contains("api-token"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_resolve_env_refs() {
+        let task = TaskBuilder::new("shard", "foo/bar")
+            .env_literal("SHARD", "3")
+            .env_literal("PREFIX", "result")
+            .secret_env("TOKEN", "api-token")
+            .build();
+
+        assert_eq!(
+            task.resolve_env_refs("${PREFIX}-${SHARD}.csv"),
+            Ok("result-3.csv".to_owned())
+        );
+        assert_eq!(task.resolve_env_refs("plain"), Ok("plain".to_owned()));
+        assert_eq!(
+            task.resolve_env_refs("open-${SHARD"),
+            Ok("open-${SHARD".to_owned())
+        );
+        assert_eq!(task.resolve_env_refs("${TOKEN}"), Err("TOKEN".to_owned()));
+        assert_eq!(
+            task.resolve_env_refs("x-${MISSING}"),
+            Err("MISSING".to_owned())
+        );
+    }
 }
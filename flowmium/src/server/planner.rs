@@ -1,4 +1,4 @@
-use super::model::Task;
+use super::model::{EnvVar, InputRef, RunCondition, Task};
 use serde::{Deserialize, Serialize};
 use std::collections::{btree_set::BTreeSet, BTreeMap};
 use thiserror::Error;
@@ -6,9 +6,10 @@ use thiserror::Error;
 /// Error constructing an execution plan from a flow definition ([`crate::model::Flow`]).
 #[derive(Error, Debug, PartialEq)]
 pub enum PlannerError {
-    /// Cyclic dependencies are present in flow definition.
-    #[error("cyclic dependencies found at task {0}")]
-    CyclicDependencies(usize),
+    /// Cyclic dependencies are present in flow definition. Contains names of the tasks forming the cycle,
+    /// each task depending on the next, with the first task repeated at the end.
+    #[error("cyclic dependencies found: {}", .0.join(" -> "))]
+    CyclicDependencies(Vec<String>),
     /// A task in the definition is trying to depend on a task that does not exist.
     #[error("dependent task {0} does not exist")]
     DependentTaskDoesNotExist(String),
@@ -16,12 +17,21 @@ pub enum PlannerError {
     /// Output names are expected to be unique across tasks in the same flow.
     #[error("output {0} not unique")]
     OutputNotUnique(String),
-    /// A task in the definition is referring to an output from a parent it does not depend on.
-    #[error("input ref {1} for task {0} not from a parent task")]
+    /// A task in the definition is referring to an output from a task it does not depend on, directly or indirectly.
+    #[error("input ref {1} for task {0} not from an ancestor task")]
     OutputNotFromParent(String, String),
     /// A task in the definition is referring to an output which does not exist.
     #[error("input ref {1} for task {0} does not exist")]
     OutputDoesNotExist(String, String),
+    /// A task that runs on failure does not depend on any task, so it would never run.
+    #[error("task {0} runs on failure but does not depend on any task")]
+    OnFailureWithoutDependencies(String),
+    /// An environment variable of a task refers to an input the task does not have.
+    #[error("input {1} referred by an environment variable of task {0} does not exist")]
+    InputRefDoesNotExist(String, String),
+    /// An output or input name of a task refers to a variable that is not a literal environment variable of the task.
+    #[error("variable {1} in input or output name of task {0} is not defined")]
+    UnresolvedVariable(String, String),
 }
 
 #[derive(PartialEq, Debug)]
@@ -39,6 +49,71 @@ pub(crate) struct Node {
 #[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Plan(pub Vec<BTreeSet<usize>>);
 
+impl Plan {
+    /// Remove a task from the plan, stages that become empty are removed.
+    pub(crate) fn without_task(self, task_id: usize) -> Plan {
+        Plan(
+            self.0
+                .into_iter()
+                .map(|mut stage| {
+                    stage.remove(&task_id);
+                    stage
+                })
+                .filter(|stage| !stage.is_empty())
+                .collect(),
+        )
+    }
+}
+
+/// A task of a stage of a [`Plan`] along with the tasks it depends on, which are all in earlier stages.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskExplanation {
+    /// Name of the task.
+    pub name: String,
+    /// Names of tasks the task depends on directly, in the order they are defined in the flow.
+    pub depends_on: Vec<String>,
+}
+
+/// Tasks of a stage of a [`Plan`], explaining why the tasks are grouped into the stage.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct StageExplanation {
+    /// Tasks of the stage in the order they are defined in the flow.
+    pub tasks: Vec<TaskExplanation>,
+}
+
+impl Plan {
+    /// Explain each stage of the plan constructed from `tasks`, by listing which tasks each task depends on.
+    /// Tasks of the plan that are not in `tasks` are left out.
+    pub fn explain(&self, tasks: &[Task]) -> Vec<StageExplanation> {
+        let task_id_map = construct_task_id_map(tasks);
+
+        self.0
+            .iter()
+            .map(|stage| StageExplanation {
+                tasks: stage
+                    .iter()
+                    .filter_map(|&task_id| tasks.get(task_id))
+                    .map(|task| {
+                        let depends_on: BTreeSet<usize> = task
+                            .depends
+                            .iter()
+                            .filter_map(|dep| task_id_map.get(dep).copied())
+                            .collect();
+
+                        TaskExplanation {
+                            name: task.name.clone(),
+                            depends_on: depends_on
+                                .into_iter()
+                                .map(|task_id| tasks[task_id].name.clone())
+                                .collect(),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
 fn construct_task_id_map(tasks: &[Task]) -> BTreeMap<&String, usize> {
     let mut task_id_map: BTreeMap<&String, usize> = BTreeMap::new();
 
@@ -75,21 +150,28 @@ fn construct_nodes(tasks: &[Task]) -> Result<Vec<Node>, PlannerError> {
 }
 
 fn is_cyclic_visit(
-    nodes: &Vec<Node>,
+    nodes: &[Node],
     node_id: usize,
     node: &Node,
     discovered: &mut BTreeSet<usize>,
     finished: &mut BTreeSet<usize>,
-) -> Option<usize> {
+    stack: &mut Vec<usize>,
+) -> Option<Vec<usize>> {
     discovered.insert(node_id);
+    stack.push(node_id);
 
     for v in node.children.iter() {
         if discovered.contains(v) {
-            return Some(*v);
+            // Discovered nodes that are not finished are exactly the nodes on the stack
+            let cycle_start = stack.iter().position(|id| id == v).unwrap_or(0);
+            let mut cycle = stack[cycle_start..].to_vec();
+            cycle.push(*v);
+
+            return Some(cycle);
         }
 
         if !finished.contains(v) {
-            match is_cyclic_visit(nodes, *v, &nodes[*v], discovered, finished) {
+            match is_cyclic_visit(nodes, *v, &nodes[*v], discovered, finished, stack) {
                 None => {
                     continue;
                 }
@@ -102,17 +184,28 @@ fn is_cyclic_visit(
 
     discovered.remove(&node_id);
     finished.insert(node_id);
+    stack.pop();
 
     None
 }
 
-fn is_cyclic(nodes: &Vec<Node>) -> Option<usize> {
+/// Find a cycle in the dependencies, returned as a chain of node ids each depending on the next,
+/// starting and ending with the same node.
+fn is_cyclic(nodes: &[Node]) -> Option<Vec<usize>> {
     let mut discovered = BTreeSet::new();
     let mut finished = BTreeSet::new();
+    let mut stack = vec![];
 
     for (node_id, node) in nodes.iter().enumerate() {
         if !discovered.contains(&node_id) && !finished.contains(&node_id) {
-            match is_cyclic_visit(nodes, node_id, node, &mut discovered, &mut finished) {
+            match is_cyclic_visit(
+                nodes,
+                node_id,
+                node,
+                &mut discovered,
+                &mut finished,
+                &mut stack,
+            ) {
                 None => {
                     continue;
                 }
@@ -126,7 +219,7 @@ fn is_cyclic(nodes: &Vec<Node>) -> Option<usize> {
     None
 }
 
-fn node_depends_on_node(dependent: &Node, dependee_id: usize, nodes: &Vec<Node>) -> bool {
+fn node_depends_on_node(dependent: &Node, dependee_id: usize, nodes: &[Node]) -> bool {
     if dependent.children.contains(&dependee_id) {
         return true;
     }
@@ -142,7 +235,7 @@ fn node_depends_on_node(dependent: &Node, dependee_id: usize, nodes: &Vec<Node>)
     false
 }
 
-fn node_depends_on_stage(node: &Node, stage: &BTreeSet<usize>, nodes: &Vec<Node>) -> bool {
+fn node_depends_on_stage(node: &Node, stage: &BTreeSet<usize>, nodes: &[Node]) -> bool {
     for stage_node_id in stage {
         if node_depends_on_node(node, *stage_node_id, nodes) {
             return true;
@@ -152,7 +245,7 @@ fn node_depends_on_stage(node: &Node, stage: &BTreeSet<usize>, nodes: &Vec<Node>
     false
 }
 
-fn stage_depends_on_node(node_id: usize, stage: &BTreeSet<usize>, nodes: &Vec<Node>) -> bool {
+fn stage_depends_on_node(node_id: usize, stage: &BTreeSet<usize>, nodes: &[Node]) -> bool {
     for stage_node_id in stage {
         let stage_node = &nodes[*stage_node_id];
 
@@ -164,12 +257,7 @@ fn stage_depends_on_node(node_id: usize, stage: &BTreeSet<usize>, nodes: &Vec<No
     false
 }
 
-fn add_node_to_plan(
-    node_id: usize,
-    node: &Node,
-    plan: &mut Vec<BTreeSet<usize>>,
-    nodes: &Vec<Node>,
-) {
+fn add_node_to_plan(node_id: usize, node: &Node, plan: &mut Vec<BTreeSet<usize>>, nodes: &[Node]) {
     for (stage_index, stage) in plan.iter_mut().enumerate() {
         if node_depends_on_stage(node, stage, nodes) {
             continue;
@@ -208,7 +296,7 @@ fn valid_input_outputs(tasks: &[Task], nodes: &[Node]) -> Result<(), PlannerErro
                     ));
                 };
 
-                if !nodes[task_id].children.contains(from_task_id) {
+                if !node_depends_on_node(&nodes[task_id], *from_task_id, nodes) {
                     return Err(PlannerError::OutputNotFromParent(
                         task.name.clone(),
                         input.from.clone(),
@@ -221,14 +309,117 @@ fn valid_input_outputs(tasks: &[Task], nodes: &[Node]) -> Result<(), PlannerErro
     Ok(())
 }
 
+fn valid_input_refs(tasks: &[Task]) -> Result<(), PlannerError> {
+    for task in tasks.iter() {
+        for env in task.env.iter() {
+            let EnvVar::InputRef(InputRef { from_input, .. }) = env else {
+                continue;
+            };
+
+            if !task
+                .inputs
+                .iter()
+                .flatten()
+                .any(|input| &input.from == from_input)
+            {
+                return Err(PlannerError::InputRefDoesNotExist(
+                    task.name.clone(),
+                    from_input.clone(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_artefact_names(tasks: &[Task]) -> Result<Vec<Task>, PlannerError> {
+    tasks
+        .iter()
+        .map(|task| {
+            task.with_resolved_artefact_names()
+                .map_err(|var_name| PlannerError::UnresolvedVariable(task.name.clone(), var_name))
+        })
+        .collect()
+}
+
+fn valid_run_conditions(tasks: &[Task]) -> Result<(), PlannerError> {
+    for task in tasks {
+        if task.on == RunCondition::OnFailure && task.depends.is_empty() {
+            return Err(PlannerError::OnFailureWithoutDependencies(
+                task.name.clone(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn any_ancestor_failed(
+    tasks: &[Task],
+    task_id_map: &BTreeMap<&String, usize>,
+    task_id: usize,
+    failed: &BTreeSet<usize>,
+    visited: &mut BTreeSet<usize>,
+) -> bool {
+    for dep in tasks[task_id].depends.iter() {
+        let Some(dep_id) = task_id_map.get(dep) else {
+            continue;
+        };
+
+        if !visited.insert(*dep_id) {
+            continue;
+        }
+
+        if failed.contains(dep_id)
+            || any_ancestor_failed(tasks, task_id_map, *dep_id, failed, visited)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Whether a task runs or is skipped according to its [`Task::on`] condition, once all tasks it depends on
+/// have terminated or were skipped. `finished` and `failed` are indices of tasks that have finished and failed,
+/// tasks in neither were skipped.
+pub(crate) fn should_run_task(
+    tasks: &[Task],
+    task_id: usize,
+    finished: &BTreeSet<usize>,
+    failed: &BTreeSet<usize>,
+) -> bool {
+    let task_id_map = construct_task_id_map(tasks);
+
+    match tasks[task_id].on {
+        RunCondition::Always => true,
+        RunCondition::OnSuccess => tasks[task_id]
+            .depends
+            .iter()
+            .all(|dep| task_id_map.get(dep).is_some_and(|id| finished.contains(id))),
+        RunCondition::OnFailure => {
+            any_ancestor_failed(tasks, &task_id_map, task_id, failed, &mut BTreeSet::new())
+        }
+    }
+}
+
 pub(crate) fn construct_plan(tasks: &[Task]) -> Result<Plan, PlannerError> {
     let nodes = construct_nodes(tasks)?;
 
-    if let Some(node_id) = is_cyclic(&nodes) {
-        return Err(PlannerError::CyclicDependencies(node_id));
+    if let Some(cycle) = is_cyclic(&nodes) {
+        return Err(PlannerError::CyclicDependencies(
+            cycle
+                .into_iter()
+                .map(|node_id| tasks[node_id].name.clone())
+                .collect(),
+        ));
     }
 
-    valid_input_outputs(tasks, &nodes)?;
+    let resolved_tasks = resolve_artefact_names(tasks)?;
+    valid_input_outputs(&resolved_tasks, &nodes)?;
+    valid_input_refs(&resolved_tasks)?;
+    valid_run_conditions(tasks)?;
 
     let mut stages: Vec<BTreeSet<usize>> = vec![];
 
@@ -239,9 +430,14 @@ pub(crate) fn construct_plan(tasks: &[Task]) -> Result<Plan, PlannerError> {
     Ok(Plan(stages))
 }
 
+/// Construct the plan of `tasks` and explain why tasks are grouped into each stage, see [`Plan::explain`].
+pub fn explain_plan(tasks: &[Task]) -> Result<Vec<StageExplanation>, PlannerError> {
+    Ok(construct_plan(tasks)?.explain(tasks))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::model::{Input, Output};
+    use crate::model::{Input, Output, TaskBuilder};
 
     use super::*;
 
@@ -284,7 +480,50 @@ mod tests {
         ];
 
         assert_eq!(is_cyclic(&test_acyclic_nodes), None);
-        assert_eq!(is_cyclic(&test_cyclic_nodes), Some(0));
+        assert_eq!(is_cyclic(&test_cyclic_nodes), Some(vec![0, 1, 3, 0]));
+
+        let test_self_cyclic_nodes = vec![
+            Node {
+                children: BTreeSet::new(),
+            },
+            Node {
+                children: BTreeSet::from([0, 1]),
+            },
+        ];
+
+        assert_eq!(is_cyclic(&test_self_cyclic_nodes), Some(vec![1, 1]));
+    }
+
+    #[test]
+    fn test_cyclic_dependencies_error() {
+        let task = |name: &str, depends: &[&str]| Task {
+            name: name.to_owned(),
+            image: "foo".to_owned(),
+            depends: depends.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let error = construct_plan(&[
+            task("extract", &[]),
+            task("transform", &["extract", "load"]),
+            task("validate", &["transform"]),
+            task("load", &["validate"]),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            PlannerError::CyclicDependencies(vec![
+                "transform".to_owned(),
+                "load".to_owned(),
+                "validate".to_owned(),
+                "transform".to_owned(),
+            ])
+        );
+        assert_eq!(
+            error.to_string(),
+            "cyclic dependencies found: transform -> load -> validate -> transform"
+        );
     }
 
     fn test_tasks() -> Vec<Task> {
@@ -297,6 +536,7 @@ mod tests {
                 env: vec![],
                 inputs: None,
                 outputs: None,
+                ..Default::default()
             },
             Task {
                 name: "B".to_string(),
@@ -306,6 +546,7 @@ mod tests {
                 env: vec![],
                 inputs: None,
                 outputs: None,
+                ..Default::default()
             },
             Task {
                 name: "A".to_string(),
@@ -320,6 +561,7 @@ mod tests {
                 env: vec![],
                 inputs: None,
                 outputs: None,
+                ..Default::default()
             },
             Task {
                 name: "D".to_string(),
@@ -329,6 +571,7 @@ mod tests {
                 env: vec![],
                 inputs: None,
                 outputs: None,
+                ..Default::default()
             },
             Task {
                 name: "C".to_string(),
@@ -338,6 +581,7 @@ mod tests {
                 env: vec![],
                 inputs: None,
                 outputs: None,
+                ..Default::default()
             },
         ]
     }
@@ -384,6 +628,32 @@ mod tests {
         assert_eq!(plan, expected_plan);
     }
 
+    #[test]
+    fn test_explain_plan() {
+        let explain = |name: &str, depends_on: &[&str]| TaskExplanation {
+            name: name.to_string(),
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        };
+
+        assert_eq!(
+            explain_plan(&test_tasks()),
+            Ok(vec![
+                StageExplanation {
+                    tasks: vec![explain("E", &[])],
+                },
+                StageExplanation {
+                    tasks: vec![explain("D", &["E"])],
+                },
+                StageExplanation {
+                    tasks: vec![explain("B", &["D"]), explain("C", &["D"])],
+                },
+                StageExplanation {
+                    tasks: vec![explain("A", &["E", "B", "D", "C"])],
+                },
+            ])
+        );
+    }
+
     #[test]
     fn test_output_not_unique() {
         let test_tasks = vec![
@@ -397,7 +667,9 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "foo".to_string(),
                     path: "/home/foo".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
             Task {
                 name: "B".to_string(),
@@ -409,7 +681,9 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "bar".to_string(),
                     path: "/home/bar".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
             Task {
                 name: "C".to_string(),
@@ -422,12 +696,15 @@ mod tests {
                     Output {
                         name: "foo".to_string(),
                         path: "/home/foo".to_string(),
+                        ..Default::default()
                     },
                     Output {
                         name: "alice".to_string(),
                         path: "/home/alice".to_string(),
+                        ..Default::default()
                     },
                 ]),
+                ..Default::default()
             },
         ];
 
@@ -450,7 +727,9 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "foo".to_string(),
                     path: "/home/foo".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
             Task {
                 name: "B".to_string(),
@@ -465,7 +744,9 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "bar".to_string(),
                     path: "/home/bar".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
         ];
 
@@ -491,19 +772,23 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "foo".to_string(),
                     path: "/home/foo".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
             Task {
                 name: "B".to_string(),
                 image: "".to_string(),
-                depends: vec!["A".to_string()],
+                depends: vec![],
                 cmd: vec![],
                 env: vec![],
                 inputs: None,
                 outputs: Some(vec![Output {
                     name: "bar".to_string(),
                     path: "/home/bar".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
             Task {
                 name: "C".to_string(),
@@ -524,7 +809,9 @@ mod tests {
                 outputs: Some(vec![Output {
                     name: "alice".to_string(),
                     path: "/home/alice".to_string(),
+                    ..Default::default()
                 }]),
+                ..Default::default()
             },
         ];
 
@@ -536,4 +823,204 @@ mod tests {
         ));
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_output_from_ancestor() {
+        let test_tasks = vec![
+            Task {
+                name: "A".to_string(),
+                outputs: Some(vec![Output {
+                    name: "foo".to_string(),
+                    path: "/home/foo".to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            Task {
+                name: "B".to_string(),
+                depends: vec!["A".to_string()],
+                ..Default::default()
+            },
+            Task {
+                name: "C".to_string(),
+                depends: vec!["B".to_string()],
+                inputs: Some(vec![Input {
+                    from: "foo".to_string(),
+                    path: "/user/foo".to_string(),
+                }]),
+                ..Default::default()
+            },
+        ];
+
+        let actual = construct_plan(&test_tasks);
+
+        let expected = Ok(Plan(vec![
+            BTreeSet::from([0]),
+            BTreeSet::from([1]),
+            BTreeSet::from([2]),
+        ]));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_templated_output_names() {
+        let shard = |shard: &str| {
+            TaskBuilder::new(format!("shard-{}", shard), "foo")
+                .env_literal("SHARD", shard)
+                .output("result-${SHARD}", "/result")
+                .build()
+        };
+
+        let merge = TaskBuilder::new("merge", "foo")
+            .depends_on("shard-0")
+            .depends_on("shard-1")
+            .env_literal("LAST_SHARD", "1")
+            .input("result-0", "/result-0")
+            .input("result-${LAST_SHARD}", "/result-1")
+            .build();
+
+        let tasks = vec![shard("0"), shard("1"), merge.clone()];
+
+        assert_eq!(
+            construct_plan(&tasks),
+            Ok(Plan(vec![BTreeSet::from([0, 1]), BTreeSet::from([2])]))
+        );
+
+        let duplicate_shard = vec![shard("0"), shard("0")];
+
+        assert_eq!(
+            construct_plan(&duplicate_shard),
+            Err(PlannerError::OutputNotUnique("result-0".to_owned()))
+        );
+
+        let unresolved = vec![
+            shard("0"),
+            shard("1"),
+            TaskBuilder::new("merge", "foo")
+                .depends_on("shard-0")
+                .input("result-${SHARD}", "/result")
+                .build(),
+        ];
+
+        let error = construct_plan(&unresolved).unwrap_err();
+
+        assert_eq!(
+            error,
+            PlannerError::UnresolvedVariable("merge".to_owned(), "SHARD".to_owned())
+        );
+        assert_eq!(
+            error.to_string(),
+            "variable SHARD in input or output name of task merge is not defined"
+        );
+    }
+
+    #[test]
+    fn test_input_refs() {
+        let producer = TaskBuilder::new("producer", "foo")
+            .output("greeting", "/greeting.txt")
+            .build();
+
+        let consumer = |from_input: &str| {
+            TaskBuilder::new("consumer", "foo")
+                .depends_on("producer")
+                .env_literal("NAME", "greeting")
+                .input("greeting", "/greeting.txt")
+                .input_env("GREETING", from_input)
+                .build()
+        };
+
+        assert!(construct_plan(&[producer.clone(), consumer("greeting")]).is_ok());
+        assert!(construct_plan(&[producer.clone(), consumer("${NAME}")]).is_ok());
+
+        assert_eq!(
+            construct_plan(&[producer, consumer("farewell")]),
+            Err(PlannerError::InputRefDoesNotExist(
+                "consumer".to_owned(),
+                "farewell".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_plan_without_task() {
+        let plan = Plan(vec![
+            BTreeSet::from([0, 3]),
+            BTreeSet::from([1]),
+            BTreeSet::from([2]),
+        ]);
+
+        assert_eq!(
+            plan.clone().without_task(3),
+            Plan(vec![
+                BTreeSet::from([0]),
+                BTreeSet::from([1]),
+                BTreeSet::from([2])
+            ])
+        );
+
+        assert_eq!(
+            plan.without_task(1),
+            Plan(vec![BTreeSet::from([0, 3]), BTreeSet::from([2])])
+        );
+    }
+
+    fn test_conditional_tasks() -> Vec<Task> {
+        let task = |name: &str, depends: &[&str], on: RunCondition| Task {
+            name: name.to_owned(),
+            depends: depends.iter().map(|dep| dep.to_string()).collect(),
+            on,
+            ..Default::default()
+        };
+
+        vec![
+            task("build", &[], RunCondition::OnSuccess),
+            task("test", &["build"], RunCondition::OnSuccess),
+            task("notify", &["test"], RunCondition::OnFailure),
+            task("deploy", &["test"], RunCondition::OnSuccess),
+            task("report", &["deploy"], RunCondition::OnFailure),
+            task("cleanup", &["deploy"], RunCondition::Always),
+        ]
+    }
+
+    #[test]
+    fn test_should_run_task() {
+        let tasks = test_conditional_tasks();
+
+        let should_run = |finished: &[usize], failed: &[usize]| -> Vec<bool> {
+            let finished = BTreeSet::from_iter(finished.iter().copied());
+            let failed = BTreeSet::from_iter(failed.iter().copied());
+
+            (2..tasks.len())
+                .map(|task_id| should_run_task(&tasks, task_id, &finished, &failed))
+                .collect()
+        };
+
+        assert_eq!(should_run(&[0, 1, 3], &[]), vec![false, true, false, true]);
+        assert_eq!(should_run(&[0], &[1]), vec![true, false, true, true]);
+        assert_eq!(should_run(&[], &[0]), vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn test_on_failure_without_dependencies() {
+        let mut tasks = test_conditional_tasks();
+
+        assert_eq!(
+            construct_plan(&tasks),
+            Ok(Plan(vec![
+                BTreeSet::from([0]),
+                BTreeSet::from([1]),
+                BTreeSet::from([2, 3]),
+                BTreeSet::from([4, 5]),
+            ]))
+        );
+
+        tasks[0].on = RunCondition::OnFailure;
+
+        assert_eq!(
+            construct_plan(&tasks),
+            Err(PlannerError::OnFailureWithoutDependencies(
+                "build".to_owned()
+            ))
+        );
+    }
 }
@@ -4,6 +4,7 @@ use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct PostgresConfig {
     postgres_url: String,
+    postgres_read_replica_url: Option<String>,
 }
 
 #[tracing::instrument(skip(config))]
@@ -29,6 +30,27 @@ pub async fn init_db_and_get_pool(config: PostgresConfig) -> Option<Pool<Postgre
     }
 }
 
+/// Connect to the read replica if one is configured, returns `Some(None)` if it is not configured
+/// and `None` if unable to connect. Migrations are not run on the replica.
+#[tracing::instrument(skip(config))]
+pub async fn get_read_replica_pool(config: PostgresConfig) -> Option<Option<Pool<Postgres>>> {
+    let Some(postgres_read_replica_url) = config.postgres_read_replica_url else {
+        return Some(None);
+    };
+
+    match PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&postgres_read_replica_url)
+        .await
+    {
+        Ok(pool) => Some(Some(pool)),
+        Err(error) => {
+            tracing::error!(%error, "Unable to create read replica connection pool");
+            None
+        }
+    }
+}
+
 pub fn check_rows_updated<T>(rows_updated: u64, error: T) -> Result<(), T> {
     if rows_updated != 1 {
         return Err(error);
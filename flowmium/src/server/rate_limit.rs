@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Number of tracked clients after which clients whose bucket has refilled are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket rate limiter keyed by client. Each client can burst up to the number of requests allowed per
+/// period, tokens are refilled continuously at that rate.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests_per_minute: u32) -> Self {
+        Self::with_rate(requests_per_minute, 60.0)
+    }
+
+    pub(crate) fn per_second(requests_per_second: u32) -> Self {
+        Self::with_rate(requests_per_second, 1.0)
+    }
+
+    fn with_rate(requests: u32, period_seconds: f64) -> Self {
+        Self {
+            capacity: requests as f64,
+            refill_per_second: requests as f64 / period_seconds,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn refill(&self, bucket: &mut TokenBucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * self.refill_per_second).min(self.capacity);
+        bucket.last_refill = now;
+    }
+
+    /// Take a token for the client, returns how long to wait until a token is available if there are none left.
+    pub(crate) fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| {
+                self.refill(bucket, now);
+                bucket.tokens < self.capacity
+            });
+        }
+
+        let bucket = buckets.entry(key.to_owned()).or_insert(TokenBucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+
+        self.refill(bucket, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / self.refill_per_second,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(60);
+        let now = Instant::now();
+
+        for _ in 0..60 {
+            assert!(limiter.check("10.0.0.1", now).is_ok());
+        }
+
+        let retry_after = limiter.check("10.0.0.1", now).unwrap_err();
+        assert!(retry_after > Duration::from_millis(900) && retry_after <= Duration::from_secs(1));
+
+        assert!(limiter.check("10.0.0.2", now).is_ok());
+
+        let later = now + Duration::from_secs(2);
+        assert!(limiter.check("10.0.0.1", later).is_ok());
+        assert!(limiter.check("10.0.0.1", later).is_ok());
+        assert!(limiter.check("10.0.0.1", later).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_per_second() {
+        let limiter = RateLimiter::per_second(2);
+        let now = Instant::now();
+
+        assert!(limiter.check("", now).is_ok());
+        assert!(limiter.check("", now).is_ok());
+
+        let retry_after = limiter.check("", now).unwrap_err();
+        assert!(
+            retry_after > Duration::from_millis(450) && retry_after <= Duration::from_millis(500)
+        );
+
+        assert!(limiter.check("", now + Duration::from_millis(500)).is_ok());
+    }
+}
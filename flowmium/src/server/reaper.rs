@@ -0,0 +1,347 @@
+use std::time::Duration;
+
+use s3::Bucket;
+use serde::Deserialize;
+
+use crate::task::{
+    bucket::{delete_artefact, get_named_bucket},
+    layout::{FlowStoreLayout, StoreLayout},
+};
+
+use super::{
+    model::Task,
+    scheduler::{Scheduler, SchedulerError},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn default_artefact_sweep_interval_seconds() -> u64 {
+    60 * 60
+}
+
+/// Configuration for deleting artefacts and records of flows once they are no longer needed.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct ReaperConfig {
+    /// Global artefact TTL, days after a flow has terminated after which its artefacts and its record are deleted.
+    /// Flows and their artefacts are kept forever if not set.
+    pub artefact_ttl_days: Option<u64>,
+    /// Number of seconds between sweeps for expired flows. Default is an hour.
+    #[serde(default = "default_artefact_sweep_interval_seconds")]
+    pub artefact_sweep_interval_seconds: u64,
+}
+
+impl ReaperConfig {
+    /// Global artefact TTL, `None` if flows are kept forever or if the TTL is too long to be represented in seconds,
+    /// see [`ReaperConfig::is_artefact_ttl_valid`].
+    pub fn get_artefact_ttl(&self) -> Option<Duration> {
+        self.artefact_ttl_days
+            .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+            .map(Duration::from_secs)
+    }
+
+    /// Returns `false` if the global artefact TTL is set but too long to be represented in seconds.
+    pub fn is_artefact_ttl_valid(&self) -> bool {
+        self.artefact_ttl_days.is_none() || self.get_artefact_ttl().is_some()
+    }
+}
+
+/// Store paths of artefacts belonging to a flow that have outlived their retention and should be deleted.
+/// `finished_for` is how long ago the flow reached a terminal state and `artefact_ttl` is the global artefact TTL.
+/// Outputs with [`crate::model::Output::retention_seconds`] set are retained for that long instead of the global TTL,
+/// this can be longer or shorter than the global TTL.
+pub fn get_expired_artefacts(
+    layout: &FlowStoreLayout,
+    tasks: &[Task],
+    finished_for: Duration,
+    artefact_ttl: Duration,
+) -> Vec<String> {
+    tasks
+        .iter()
+        .filter_map(|task| task.with_resolved_artefact_names().ok())
+        .filter_map(|task| task.outputs)
+        .flatten()
+        .filter(|output| {
+            let retention = output
+                .retention_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(artefact_ttl);
+
+            finished_for > retention
+        })
+        .map(|output| layout.get_store_path(&output.name))
+        .collect()
+}
+
+/// Delete artefacts of terminated flows that have outlived their retention ([`get_expired_artefacts`]), and delete
+/// flows along with their record once they have terminated more than `artefact_ttl` ago and none of their outputs
+/// are retained for longer, see [`crate::scheduler::Scheduler::delete_flow`]. Returns IDs of the deleted flows.
+/// Flows that could not be deleted are left for the next sweep.
+#[tracing::instrument(skip(sched, bucket, layout))]
+pub async fn sweep_expired_flows(
+    sched: &Scheduler,
+    bucket: &Bucket,
+    layout: &StoreLayout,
+    artefact_ttl: Duration,
+) -> Result<Vec<i32>, SchedulerError> {
+    let mut deleted_flow_ids = vec![];
+
+    for flow in sched.list_expired_flows(artefact_ttl).await? {
+        let flow_layout = match sched.get_flow_store_layout(flow.id, layout).await {
+            Ok(flow_layout) => flow_layout,
+            Err(error) => {
+                tracing::warn!(%error, flow_id = flow.id, "Unable to get artefact layout of expired flow");
+                continue;
+            }
+        };
+
+        let num_outputs: usize = flow
+            .tasks
+            .iter()
+            .filter_map(|task| task.outputs.as_ref())
+            .map(Vec::len)
+            .sum();
+
+        let expired_artefacts =
+            get_expired_artefacts(&flow_layout, &flow.tasks, flow.finished_for, artefact_ttl);
+
+        if flow.finished_for > artefact_ttl && expired_artefacts.len() == num_outputs {
+            match sched.delete_flow(flow.id, bucket, layout).await {
+                Ok(()) => deleted_flow_ids.push(flow.id),
+                Err(error) => {
+                    tracing::warn!(%error, flow_id = flow.id, "Unable to delete expired flow")
+                }
+            }
+
+            continue;
+        }
+
+        let flow_bucket = get_named_bucket(bucket, flow.bucket_name.as_deref());
+
+        for store_path in expired_artefacts {
+            if let Err(error) = delete_artefact(&flow_bucket, store_path).await {
+                tracing::warn!(%error, flow_id = flow.id, "Unable to delete expired artefact");
+            }
+        }
+    }
+
+    Ok(deleted_flow_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
+
+    use serial_test::serial;
+
+    use crate::{
+        model::{Flow, Output},
+        planner::Plan,
+        server::pool::get_test_pool,
+        task::bucket::{get_test_bucket, serve_test_bucket},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_get_artefact_ttl() {
+        let config = |artefact_ttl_days| ReaperConfig {
+            artefact_ttl_days,
+            artefact_sweep_interval_seconds: 60,
+        };
+
+        assert_eq!(
+            config(Some(7)).get_artefact_ttl(),
+            Some(Duration::from_secs(7 * SECONDS_PER_DAY))
+        );
+        assert!(config(Some(7)).is_artefact_ttl_valid());
+
+        assert_eq!(config(None).get_artefact_ttl(), None);
+        assert!(config(None).is_artefact_ttl_valid());
+
+        assert_eq!(config(Some(u64::MAX)).get_artefact_ttl(), None);
+        assert!(!config(Some(u64::MAX)).is_artefact_ttl_valid());
+    }
+
+    #[test]
+    fn test_get_expired_artefacts() {
+        let tasks = vec![
+            Task {
+                name: "task-a".to_owned(),
+                outputs: Some(vec![Output {
+                    name: "scratch".to_owned(),
+                    path: "/scratch".to_owned(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            Task {
+                name: "task-b".to_owned(),
+                outputs: Some(vec![
+                    Output {
+                        name: "report".to_owned(),
+                        path: "/report".to_owned(),
+                        retention_seconds: Some(30 * 24 * 60 * 60),
+                        required: false,
+                    },
+                    Output {
+                        name: "short-lived".to_owned(),
+                        path: "/short-lived".to_owned(),
+                        retention_seconds: Some(60),
+                        required: false,
+                    },
+                ]),
+                ..Default::default()
+            },
+            Task {
+                name: "task-c".to_owned(),
+                ..Default::default()
+            },
+        ];
+
+        let artefact_ttl = Duration::from_secs(7 * 24 * 60 * 60);
+        let layout = StoreLayout::default().for_flow(12, "nightly", "2026-10-17");
+
+        assert_eq!(
+            get_expired_artefacts(&layout, &tasks, Duration::from_secs(30), artefact_ttl),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            get_expired_artefacts(&layout, &tasks, Duration::from_secs(120), artefact_ttl),
+            vec!["12/short-lived".to_owned()]
+        );
+
+        assert_eq!(
+            get_expired_artefacts(
+                &layout,
+                &tasks,
+                Duration::from_secs(8 * 24 * 60 * 60),
+                artefact_ttl
+            ),
+            vec!["12/scratch".to_owned(), "12/short-lived".to_owned()]
+        );
+
+        assert_eq!(
+            get_expired_artefacts(
+                &layout,
+                &tasks,
+                Duration::from_secs(31 * 24 * 60 * 60),
+                artefact_ttl
+            ),
+            vec![
+                "12/scratch".to_owned(),
+                "12/report".to_owned(),
+                "12/short-lived".to_owned()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_sweep_expired_flows() {
+        let pool = get_test_pool(&["flows"]).await;
+        let sched = Scheduler::new(pool.clone());
+
+        let output = |name: &str, retention_seconds| Output {
+            name: name.to_owned(),
+            path: format!("/{}", name),
+            retention_seconds,
+            required: false,
+        };
+
+        let create_finished_flow = |outputs: Vec<Output>, interval_seconds, finished_days_ago| {
+            let sched = sched.clone();
+            let pool = pool.clone();
+
+            async move {
+                let flow = Flow {
+                    name: "sweep".to_owned(),
+                    tasks: vec![Task {
+                        name: "task-0".to_owned(),
+                        outputs: Some(outputs),
+                        ..Default::default()
+                    }],
+                    interval_seconds,
+                    ..Default::default()
+                };
+
+                let flow_id = sched
+                    .create_flow(flow, Plan(vec![BTreeSet::from([0])]))
+                    .await
+                    .unwrap();
+
+                sched.schedule_tasks(flow_id).await.unwrap();
+                sched.mark_task_running(flow_id, 0).await.unwrap();
+                sched.mark_task_finished(flow_id, 0).await.unwrap();
+
+                sqlx::query(
+                    "UPDATE flows SET finished_at = now() - $2 * interval '1 day' WHERE id = $1",
+                )
+                .bind(flow_id)
+                .bind(finished_days_ago)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+                flow_id
+            }
+        };
+
+        let old = create_finished_flow(vec![output("scratch", None)], None, 10.0).await;
+        let recent = create_finished_flow(vec![output("scratch", None)], None, 1.0).await;
+        let periodic = create_finished_flow(vec![output("scratch", None)], Some(3600), 10.0).await;
+        let retained = create_finished_flow(
+            vec![
+                output("scratch", None),
+                output("report", Some(30 * 24 * 60 * 60)),
+            ],
+            None,
+            10.0,
+        )
+        .await;
+
+        let keys = Arc::new(Mutex::new(BTreeSet::from([
+            format!("{}/scratch", old),
+            format!("{}/scratch", recent),
+            format!("{}/scratch", periodic),
+            format!("{}/scratch", retained),
+            format!("{}/report", retained),
+        ])));
+        let bucket = get_test_bucket(serve_test_bucket(keys.clone()).await);
+
+        let artefact_ttl = ReaperConfig {
+            artefact_ttl_days: Some(7),
+            artefact_sweep_interval_seconds: 60,
+        }
+        .get_artefact_ttl()
+        .unwrap();
+
+        assert_eq!(
+            sweep_expired_flows(&sched, &bucket, &StoreLayout::default(), artefact_ttl)
+                .await
+                .unwrap(),
+            vec![old]
+        );
+
+        assert!(matches!(
+            sched.get_flow(old).await,
+            Err(SchedulerError::FlowDoesNotExist(_))
+        ));
+
+        for flow_id in [recent, periodic, retained] {
+            assert!(sched.get_flow(flow_id).await.is_ok());
+        }
+
+        assert_eq!(
+            *keys.lock().unwrap(),
+            BTreeSet::from([
+                format!("{}/scratch", recent),
+                format!("{}/scratch", periodic),
+                format!("{}/report", retained),
+            ])
+        );
+    }
+}
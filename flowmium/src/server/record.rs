@@ -1,19 +1,29 @@
 use core::fmt;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
-/// Status of a flow.
+use super::model::{EnvVar, Flow, Task};
+
+/// Status of a flow, also deserialized from lowercase names like `running`.
 #[derive(sqlx::Type, Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[sqlx(rename_all = "snake_case", type_name = "flow_status")]
 pub enum FlowStatus {
     /// Flow is yet to run. None of the task has been spawned yet.
+    #[serde(alias = "pending")]
     Pending,
     /// Flow is running and at least one of the task has been spawned.
+    #[serde(alias = "running")]
     Running,
     /// Flow has finished successfully and all tasks in the flow has also been completed successfully.
+    #[serde(alias = "success")]
     Success,
     /// Flow has been aborted with a failure because one of the tasks terminated with a failure.
+    #[serde(alias = "failed")]
     Failed,
+    /// Flow has been aborted on request before it terminated, see [`crate::executor::abort_flow`].
+    #[serde(alias = "aborted")]
+    Aborted,
 }
 
 /// Status of a task belonging to a flow.
@@ -26,6 +36,8 @@ pub enum TaskStatus {
     Failed,
     /// Task has finished successfully.
     Finished,
+    /// Task was not run because its run condition was not met, see [`crate::model::Task::on`].
+    Skipped,
 }
 
 impl fmt::Display for TaskStatus {
@@ -34,6 +46,7 @@ impl fmt::Display for TaskStatus {
             TaskStatus::Running => write!(f, "running"),
             TaskStatus::Failed => write!(f, "failed"),
             TaskStatus::Finished => write!(f, "finished"),
+            TaskStatus::Skipped => write!(f, "skipped"),
         }
     }
 }
@@ -59,6 +72,8 @@ pub struct FlowRecord {
     /// Set of tasks in the same stage are spawned at the same time because they are not dependent on each other.
     pub current_stage: i32,
     /// Indices of tasks that are currently running.
+    /// Task indices used throughout the flow's record are positions of tasks in `task_definitions`,
+    /// use `task_index_to_name` to look up the name of a task by its index.
     pub running_tasks: Vec<i32>,
     /// Indices of tasks that have finished.
     pub finished_tasks: Vec<i32>,
@@ -66,6 +81,115 @@ pub struct FlowRecord {
     pub failed_tasks: Vec<i32>,
     /// List of tasks that belong to this flow, as define in [`crate::model::Flow`].
     pub task_definitions: serde_json::Value,
+    /// Names of outputs of finished tasks, these can already be downloaded while the flow is still running.
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub available_outputs: Vec<String>,
+    /// Name of each task by its index, the same index that is used in `plan`, `running_tasks`,
+    /// `finished_tasks` and `failed_tasks`. Indices are stable for the lifetime of the flow.
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub task_index_to_name: BTreeMap<i32, String>,
+    /// Image used for the init container of the flow's tasks if it overrides the server's default,
+    /// see [`crate::model::Flow::init_container_image`].
+    #[serde(default)]
+    pub init_container_image: Option<String>,
+    /// Index of the task that runs when any other task fails, see [`crate::model::Flow::on_failure`].
+    /// The flow stays running with failed tasks until this task has terminated.
+    #[serde(default)]
+    pub on_failure_task: Option<i32>,
+    /// Indices of tasks that have been retried after failing, a task appears once for every retry,
+    /// see [`crate::model::Task::retries`].
+    #[serde(default)]
+    pub retried_tasks: Vec<i32>,
+    /// Indices of tasks that have failed and are waiting to be retried.
+    #[serde(default)]
+    pub retrying_tasks: Vec<i32>,
+    /// Indices of tasks whose command has exceeded its timeout, these also appear in `failed_tasks` or
+    /// `retried_tasks`, see [`crate::model::Task::timeout_seconds`].
+    #[serde(default)]
+    pub timed_out_tasks: Vec<i32>,
+    /// Indices of tasks that were not run because their run condition was not met, see [`crate::model::Task::on`].
+    #[serde(default)]
+    pub skipped_tasks: Vec<i32>,
+    /// Maximum number of tasks of the flow that run at the same time, see [`crate::model::Flow::max_parallel_tasks`].
+    #[serde(default)]
+    pub max_parallel_tasks: Option<i32>,
+    /// Seconds after the flow was created by which it has to have terminated, see [`crate::model::Flow::deadline_seconds`].
+    #[serde(default)]
+    pub deadline_seconds: Option<i64>,
+    /// Bucket artefacts of the flow are stored in if it overrides the server's bucket,
+    /// see [`crate::model::Flow::bucket_name`].
+    #[serde(default)]
+    pub bucket_name: Option<String>,
+    /// Environment variables set for every task of the flow, see [`crate::model::Flow::env`].
+    #[sqlx(json)]
+    #[serde(default)]
+    pub env: Vec<EnvVar>,
+    /// Times tasks of the flow started and terminated by task index, tasks that have not been spawned yet are left out.
+    #[sqlx(json)]
+    #[serde(default)]
+    pub task_timestamps: BTreeMap<i32, TaskTimestamps>,
+    /// Reasons tasks failed for when their pod could not run their command by task index, like when the image of
+    /// the task could not be pulled. Only the reason of the latest attempt of a retried task is kept.
+    #[sqlx(json)]
+    #[serde(default)]
+    pub task_failures: BTreeMap<i32, String>,
+    /// Time the flow was created in UTC as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub created_at: String,
+    /// Time the flow succeeded, failed or was aborted in UTC as an RFC 3339 timestamp, `None` while it is active.
+    #[serde(default)]
+    pub finished_at: Option<String>,
+}
+
+impl FlowRecord {
+    /// Reconstruct the definition of the flow from its record, to submit it again as a new flow.
+    /// Inline secrets and the interval or schedule of recurring flows are not stored with the record,
+    /// so they are not part of the returned definition.
+    pub fn into_flow(self) -> Result<Flow, serde_json::Error> {
+        let tasks = serde_json::from_value::<Vec<Task>>(self.task_definitions)?;
+
+        Ok(Flow {
+            name: self.flow_name,
+            tasks,
+            init_container_image: self.init_container_image,
+            on_failure: self
+                .on_failure_task
+                .and_then(|task_id| self.task_index_to_name.get(&task_id).cloned()),
+            max_parallel_tasks: self
+                .max_parallel_tasks
+                .map(|max_parallel_tasks| max_parallel_tasks as usize),
+            deadline_seconds: self
+                .deadline_seconds
+                .map(|deadline_seconds| deadline_seconds as u64),
+            bucket_name: self.bucket_name,
+            env: self.env,
+            ..Default::default()
+        })
+    }
+}
+
+/// Times a task of a flow started and terminated in UTC as RFC 3339 timestamps.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct TaskTimestamps {
+    /// Time the task was spawned, the time of the latest attempt if the task has been retried.
+    pub started_at: Option<String>,
+    /// Time the task finished or failed, `None` while the task is running.
+    pub finished_at: Option<String>,
+}
+
+/// A change of status of a flow, transitions are also queryable in Postgres from the `flow_transitions_view` view.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, sqlx::FromRow)]
+pub struct FlowTransition {
+    /// Status of the flow before the transition, `None` for the transition that created the flow.
+    pub from_status: Option<FlowStatus>,
+    /// Status of the flow after the transition.
+    pub to_status: FlowStatus,
+    /// Index of the task whose status update caused the transition, `None` for the transition that created the flow.
+    pub task_id: Option<i32>,
+    /// Time of the transition in UTC as an RFC 3339 timestamp.
+    pub transitioned_at: String,
 }
 
 /// Brief status summary of a flow.
@@ -85,4 +209,144 @@ pub struct FlowListRecord {
     pub num_failed: Option<i32>,
     /// Total number of tasks defined in the flow.
     pub num_total: Option<i32>,
+    /// Time the flow was created in UTC as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub created_at: String,
+    /// Time the flow succeeded, failed or was aborted in UTC as an RFC 3339 timestamp, `None` while it is active.
+    #[serde(default)]
+    pub finished_at: Option<String>,
+}
+
+/// Reason a flow in the schedule backlog is not making progress right now.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum BlockingReason {
+    /// Flow is pending and is waiting for the executor to spawn its first stage.
+    Queued,
+    /// Flow is running and later stages are waiting for the running tasks of the current stage to finish.
+    WaitingForStage {
+        /// Indices of the running tasks the flow is waiting on.
+        running_tasks: Vec<i32>,
+    },
+    /// Flow is running but has no running tasks, its next stage is about to be spawned by the executor.
+    WaitingForSpawn,
+    /// Flow is running but has no running tasks, failed tasks are waiting for their retry backoff to pass.
+    WaitingForRetry {
+        /// Indices of the tasks waiting to be retried.
+        retrying_tasks: Vec<i32>,
+    },
+    /// A task has failed and the flow is running its [`crate::model::Flow::on_failure`] task before it is marked as failed.
+    RunningOnFailure {
+        /// Indices of the failed tasks.
+        failed_tasks: Vec<i32>,
+    },
+}
+
+/// A flow in the schedule backlog, that is yet to finish.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BacklogEntry {
+    /// Unique identifier for the flow.
+    pub id: i32,
+    /// Name of the flow as specified in [`crate::model::Flow`].
+    pub flow_name: String,
+    /// Status of the flow, either pending or running.
+    pub status: FlowStatus,
+    /// Position of the flow in the order the executor visits flows, starting from zero.
+    pub queue_position: usize,
+    /// Current stage of the flow's plan, see [`FlowRecord::current_stage`].
+    pub current_stage: i32,
+    /// Total number of stages in the flow's plan.
+    pub num_stages: i32,
+    /// Why the flow is waiting.
+    pub blocked_on: BlockingReason,
+}
+
+/// Storage used by artefacts in the bucket.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+pub struct StorageUsage {
+    /// Total size of the artefacts in bytes.
+    pub bytes: u64,
+    /// Number of artefacts.
+    pub objects: u64,
+}
+
+/// Reachability of the server's dependencies, reported by the health endpoint.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct HealthReport {
+    /// Dependencies that cannot be reached, `postgres` or `store`, along with the error reaching them.
+    /// Empty if the server is healthy.
+    pub failing: BTreeMap<String, String>,
+}
+
+/// Storage used by artefacts of all flows in the bucket.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct StorageUsageReport {
+    /// Storage used by artefacts of all flows.
+    pub total: StorageUsage,
+    /// Storage used by artefacts of each flow by flow id, this includes flows that have been deleted
+    /// but whose artefacts have not been reaped yet.
+    pub flows: BTreeMap<i32, StorageUsage>,
+}
+
+/// Projected schedule of a single flow, see [`crate::simulation::simulate_flows`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct FlowProjection {
+    /// Name of the flow.
+    pub flow_name: String,
+    /// Number of stages in the flow's plan.
+    pub num_stages: usize,
+    /// Number of tasks in the flow's plan, this does not include the on failure task.
+    pub num_tasks: usize,
+    /// Maximum number of tasks of the flow running at the same time.
+    pub peak_concurrency: usize,
+}
+
+/// Projected schedule of a set of flows submitted together, computed over virtual time where each stage of a flow
+/// takes one step, see [`crate::simulation::simulate_flows`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct ScheduleProjection {
+    /// Maximum number of tasks, and so pods, running at the same time.
+    pub peak_concurrency: usize,
+    /// Step at which the peak concurrency is first reached.
+    pub peak_step: usize,
+    /// Number of steps until all flows have finished, the number of stages of the longest flow.
+    pub num_steps: usize,
+    /// Sum of the number of stages of all flows.
+    pub total_stages: usize,
+    /// Number of tasks running at each step.
+    pub concurrency: Vec<usize>,
+    /// Projection of each flow, in the order they were submitted.
+    pub flows: Vec<FlowProjection>,
+}
+
+/// Flow registered to run on a cron schedule, see [`crate::model::Flow::schedule`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, sqlx::FromRow)]
+pub struct FlowTemplateRecord {
+    /// Unique identifier for the recurring flow, different from IDs of the flows created at each run.
+    pub id: i32,
+    /// Name of the flow as specified in [`crate::model::Flow`].
+    pub flow_name: String,
+    /// Cron expression the flow is run on.
+    pub schedule: String,
+    /// Time of the next run in UTC as an RFC 3339 timestamp, `None` if the schedule never matches again.
+    pub next_run_at: Option<String>,
+}
+
+/// Response to submitting a recurring flow, IDs of recurring flows are different from IDs of the flows created at
+/// each run, so they are returned in a field of their own instead of as a flow ID.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct FlowTemplateRegistration {
+    /// ID of the recurring flow, see [`FlowTemplateRecord::id`].
+    pub schedule_id: i32,
+}
+
+/// Key of a secret stored in the server along with when it was created and last changed, the value is never included.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, sqlx::FromRow)]
+pub struct SecretRecord {
+    /// Key of the secret.
+    pub key: String,
+    /// Time the secret was created in UTC as an RFC 3339 timestamp.
+    pub created_at: String,
+    /// Time the secret's value was last changed in UTC as an RFC 3339 timestamp.
+    pub updated_at: String,
 }
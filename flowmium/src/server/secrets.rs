@@ -1,8 +1,12 @@
-use sqlx::{Pool, Postgres};
+use std::{num::NonZeroUsize, path::PathBuf, sync::Arc};
+
+use serde::Deserialize;
+use sqlx::{PgConnection, Pool, Postgres};
+use tokio::sync::Semaphore;
 
 use thiserror::Error;
 
-use super::pool::check_rows_updated;
+use super::{model::InlineSecrets, pool::check_rows_updated, record::SecretRecord};
 
 /// Error on modifying or creating secrets.
 #[derive(Error, Debug)]
@@ -18,16 +22,34 @@ pub enum SecretsCrudError {
     DatabaseQuery(#[source] sqlx::error::Error),
 }
 
+/// Configuration for secrets loaded into the database when the server starts.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct SecretsConfig {
+    /// Optional directory of files to create or update secrets from on startup, the name of each file is the key of
+    /// the secret and its contents are the value. Works with Kubernetes secrets mounted as a volume.
+    pub secrets_dir: Option<PathBuf>,
+}
+
 /// Manage secrets stored in the database. The secrets can be referred in the flow definition, see [`crate::model`] and [`crate::model::SecretRef`].
 #[derive(Clone)]
 pub struct SecretsCrud {
     pool: Pool<Postgres>,
+    fetch_permits: Option<Arc<Semaphore>>,
 }
 
 impl SecretsCrud {
     /// Create a new secrets CRUD.
     pub fn new(pool: Pool<Postgres>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            fetch_permits: None,
+        }
+    }
+
+    /// Limit the number of concurrent queries fetching secrets for spawning tasks, shared by all clones of this CRUD.
+    pub fn with_max_concurrent_fetches(mut self, max_concurrent_fetches: NonZeroUsize) -> Self {
+        self.fetch_permits = Some(Arc::new(Semaphore::new(max_concurrent_fetches.get())));
+        self
     }
 
     /// Create a new secret. This secret will be stored in the database.
@@ -56,6 +78,27 @@ impl SecretsCrud {
         }
     }
 
+    /// Create a secret or update its value if it already exists.
+    pub async fn upsert_secret(&self, key: &str, value: &str) -> Result<(), SecretsCrudError> {
+        let query = r#"
+        INSERT INTO secrets (secret_key, secret_value) VALUES ($1, $2)
+        ON CONFLICT (secret_key) DO UPDATE SET secret_value = EXCLUDED.secret_value, updated_at = now()
+        "#;
+
+        match sqlx::query(query)
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                tracing::error!(%error, "Unable to create or update secret {}", key);
+                Err(SecretsCrudError::DatabaseQuery(error))
+            }
+        }
+    }
+
     /// Delete an existing secret.
     pub async fn delete_secret(&self, key: &str) -> Result<(), SecretsCrudError> {
         let rows_updated = match sqlx::query(r#"DELETE from secrets WHERE secret_key = $1"#)
@@ -78,19 +121,22 @@ impl SecretsCrud {
 
     /// Update an existing secret.
     pub async fn update_secret(&self, key: &str, value: &str) -> Result<(), SecretsCrudError> {
-        let rows_updated =
-            match sqlx::query(r#"UPDATE secrets SET secret_value = $2 WHERE secret_key = $1"#)
-                .bind(key)
-                .bind(value)
-                .execute(&self.pool)
-                .await
-            {
-                Ok(result) => result.rows_affected(),
-                Err(error) => {
-                    tracing::error!(%error, "Unable to update secret {}", key);
-                    return Err(SecretsCrudError::DatabaseQuery(error));
-                }
-            };
+        let query = r#"
+        UPDATE secrets SET secret_value = $2, updated_at = now() WHERE secret_key = $1
+        "#;
+
+        let rows_updated = match sqlx::query(query)
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(error) => {
+                tracing::error!(%error, "Unable to update secret {}", key);
+                return Err(SecretsCrudError::DatabaseQuery(error));
+            }
+        };
 
         check_rows_updated(
             rows_updated,
@@ -98,6 +144,127 @@ impl SecretsCrud {
         )
     }
 
+    /// List keys of all secrets along with when they were created and last changed, values are never fetched.
+    pub async fn list_secrets(&self) -> Result<Vec<SecretRecord>, SecretsCrudError> {
+        let query = r#"
+        SELECT
+            secret_key AS key,
+            to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS created_at,
+            to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS updated_at
+        FROM secrets
+        ORDER BY secret_key ASC
+        "#;
+
+        match sqlx::query_as(query).fetch_all(&self.pool).await {
+            Ok(secrets) => Ok(secrets),
+            Err(error) => {
+                tracing::error!(%error, "Unable to list secrets");
+                Err(SecretsCrudError::DatabaseQuery(error))
+            }
+        }
+    }
+
+    /// Check if a secret exists without fetching its value.
+    pub async fn secret_exists(&self, key: &str) -> Result<bool, SecretsCrudError> {
+        match sqlx::query_as(r#"SELECT EXISTS (SELECT 1 FROM secrets WHERE secret_key = $1)"#)
+            .bind(key)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok((exists,)) => Ok(exists),
+            Err(error) => {
+                tracing::error!(%error, "Could not check if secret exists in secrets database");
+                Err(SecretsCrudError::DatabaseQuery(error))
+            }
+        }
+    }
+
+    /// Store secrets scoped to a single flow, see [`crate::model::Flow::secrets`].
+    /// Expected to be called in the same transaction that creates the flow.
+    pub(crate) async fn create_flow_secrets(
+        conn: &mut PgConnection,
+        flow_id: i32,
+        secrets: &InlineSecrets,
+    ) -> Result<(), SecretsCrudError> {
+        let (keys, values): (Vec<&String>, Vec<&String>) = secrets.0.iter().unzip();
+
+        let query = r#"
+        INSERT INTO flow_secrets (flow_id, secret_key, secret_value)
+        SELECT $1, * FROM UNNEST($2::text[], $3::text[])
+        "#;
+
+        match sqlx::query(query)
+            .bind(flow_id)
+            .bind(keys)
+            .bind(values)
+            .execute(conn)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                tracing::error!(%error, "Unable to create secrets for flow {}", flow_id);
+                Err(SecretsCrudError::DatabaseQuery(error))
+            }
+        }
+    }
+
+    /// Delete secrets scoped to flows that have terminated, either successfully, with failure or have been aborted.
+    pub(crate) async fn delete_terminated_flow_secrets(&self) -> Result<(), SecretsCrudError> {
+        let query = r#"
+        DELETE FROM flow_secrets
+        USING flows
+        WHERE flows.id = flow_secrets.flow_id AND flows.status IN ('success', 'failed', 'aborted')
+        "#;
+
+        match sqlx::query(query).execute(&self.pool).await {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                tracing::error!(%error, "Unable to delete secrets of terminated flows");
+                Err(SecretsCrudError::DatabaseQuery(error))
+            }
+        }
+    }
+
+    /// Fetch a secret for a flow, secrets scoped to the flow take precedence over secrets stored in the server.
+    /// Waits for a permit if the number of concurrent fetches is limited, see [`SecretsCrud::with_max_concurrent_fetches`].
+    pub(crate) async fn get_flow_secret(
+        &self,
+        flow_id: i32,
+        key: &str,
+    ) -> Result<String, SecretsCrudError> {
+        // SAFETY: The semaphore is never closed
+        let _permit = match &self.fetch_permits {
+            Some(fetch_permits) => Some(fetch_permits.acquire().await.unwrap()),
+            None => None,
+        };
+
+        let query = r#"
+        SELECT COALESCE(
+            (SELECT secret_value FROM flow_secrets WHERE flow_id = $1 AND secret_key = $2),
+            (SELECT secret_value FROM secrets WHERE secret_key = $2)
+        )
+        "#;
+
+        let record: (Option<String>,) = match sqlx::query_as(query)
+            .bind(flow_id)
+            .bind(key)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(record) => record,
+            Err(error) => {
+                tracing::error!(%error, "Could not fetch secret for flow {}", flow_id);
+                return Err(SecretsCrudError::DatabaseQuery(error));
+            }
+        };
+
+        let Some(value) = record.0 else {
+            return Err(SecretsCrudError::SecretDoesNotExist(key.to_string()));
+        };
+
+        Ok(value)
+    }
+
     /// Fetch an existing secret.
     pub async fn get_secret(&self, key: &str) -> Result<String, SecretsCrudError> {
         let record: Option<(String,)> =
@@ -123,16 +290,24 @@ impl SecretsCrud {
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeMap, num::NonZeroUsize, time::Duration};
+
+    use serial_test::serial;
+
     use crate::server::{
+        model::{Flow, InlineSecrets},
+        planner::construct_plan,
         pool::get_test_pool,
+        scheduler::Scheduler,
         secrets::{SecretsCrud, SecretsCrudError},
     };
 
     #[tokio::test]
+    #[serial]
     async fn test_secrets_crud() {
         let pool = get_test_pool(["secrets"].as_slice()).await;
 
-        let test_crud = SecretsCrud { pool };
+        let test_crud = SecretsCrud::new(pool);
 
         fn assert_does_not_exist_error(result: SecretsCrudError, key: &str) {
             assert!(match result {
@@ -156,10 +331,25 @@ mod tests {
 
         assert_eq!(test_crud.get_secret("another").await.unwrap(), "yeah");
 
+        assert!(test_crud.secret_exists("another").await.unwrap());
+        assert!(!test_crud.secret_exists("doesNotExist").await.unwrap());
+
+        let secrets = test_crud.list_secrets().await.unwrap();
+        assert_eq!(
+            secrets.iter().map(|s| s.key.as_str()).collect::<Vec<_>>(),
+            vec!["another", "foo"]
+        );
+        assert_eq!(secrets[0].created_at, secrets[0].updated_at);
+
         test_crud.update_secret("another", "ye").await.unwrap();
 
         assert_eq!(test_crud.get_secret("another").await.unwrap(), "ye");
 
+        let secrets = test_crud.list_secrets().await.unwrap();
+        assert_eq!(secrets[0].key, "another");
+        assert!(secrets[0].updated_at > secrets[0].created_at);
+        assert_eq!(secrets[1].created_at, secrets[1].updated_at);
+
         test_crud.delete_secret("another").await.unwrap();
 
         assert_does_not_exist_error(
@@ -180,4 +370,110 @@ mod tests {
             "another",
         );
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_flow_secrets() {
+        let pool = get_test_pool(["flows", "secrets"].as_slice()).await;
+
+        let test_crud = SecretsCrud::new(pool.clone());
+        let sched = Scheduler::new(pool);
+
+        test_crud.create_secret("shared", "global").await.unwrap();
+        test_crud
+            .create_secret("global-only", "global")
+            .await
+            .unwrap();
+
+        let inline_secrets = InlineSecrets(BTreeMap::from([
+            ("shared".to_owned(), "inline".to_owned()),
+            ("inline-only".to_owned(), "inline".to_owned()),
+        ]));
+
+        let flow_id = sched
+            .create_flow(
+                Flow {
+                    name: "flow-secrets".to_owned(),
+                    tasks: vec![],
+                    secrets: inline_secrets,
+                    ..Default::default()
+                },
+                construct_plan(&[]).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            test_crud.get_flow_secret(flow_id, "shared").await.unwrap(),
+            "inline"
+        );
+        assert_eq!(
+            test_crud
+                .get_flow_secret(flow_id, "inline-only")
+                .await
+                .unwrap(),
+            "inline"
+        );
+        assert_eq!(
+            test_crud
+                .get_flow_secret(flow_id, "global-only")
+                .await
+                .unwrap(),
+            "global"
+        );
+        assert!(!test_crud.secret_exists("inline-only").await.unwrap());
+
+        test_crud.delete_terminated_flow_secrets().await.unwrap();
+
+        assert_eq!(
+            test_crud.get_flow_secret(flow_id, "shared").await.unwrap(),
+            "inline"
+        );
+
+        sched.mark_task_failed(flow_id, 0).await.unwrap();
+
+        test_crud.delete_terminated_flow_secrets().await.unwrap();
+
+        assert_eq!(
+            test_crud.get_flow_secret(flow_id, "shared").await.unwrap(),
+            "global"
+        );
+        assert!(matches!(
+            test_crud.get_flow_secret(flow_id, "inline-only").await,
+            Err(SecretsCrudError::SecretDoesNotExist(key)) if key == "inline-only"
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_max_concurrent_fetches() {
+        let pool = get_test_pool(["secrets"].as_slice()).await;
+
+        let test_crud =
+            SecretsCrud::new(pool).with_max_concurrent_fetches(NonZeroUsize::new(2).unwrap());
+
+        test_crud.create_secret("foo", "bar").await.unwrap();
+
+        let fetch_permits = test_crud.fetch_permits.clone().unwrap();
+        let held_permits = fetch_permits.acquire_many(2).await.unwrap();
+
+        let fetches: Vec<_> = (0..8)
+            .map(|_| {
+                let test_crud = test_crud.clone();
+                tokio::spawn(async move { test_crud.get_flow_secret(0, "foo").await })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(fetches.iter().all(|fetch| !fetch.is_finished()));
+
+        drop(held_permits);
+
+        for fetch in fetches {
+            assert_eq!(fetch.await.unwrap().unwrap(), "bar");
+        }
+
+        assert_eq!(fetch_permits.available_permits(), 2);
+    }
 }
@@ -0,0 +1,208 @@
+//! Dry-run scheduling of flows for capacity planning, without touching the database or the cluster.
+
+use super::{
+    executor::{validate_and_plan_flow, ExecutorConfig, ExecutorError},
+    model::Flow,
+    planner::Plan,
+    record::{FlowProjection, ScheduleProjection},
+};
+
+/// Number of tasks of the flow running at each step, a stage runs along with the tasks of the next stage that have
+/// [`crate::model::Task::eager_inputs`] set, as they are spawned early and keep running until their own stage finishes.
+/// A stage of a flow with [`crate::model::Flow::max_parallel_tasks`] set takes a step for every batch of tasks that fit
+/// within the limit.
+fn get_stage_concurrency(flow: &Flow, plan: &Plan) -> Vec<usize> {
+    let stages = &plan.0;
+
+    stages
+        .iter()
+        .enumerate()
+        .flat_map(|(i, task_ids)| {
+            let num_eager = stages.get(i + 1).map_or(0, |next_task_ids| {
+                next_task_ids
+                    .iter()
+                    .filter(|task_id| flow.tasks[**task_id].eager_inputs)
+                    .count()
+            });
+
+            let num_tasks = task_ids.len() + num_eager;
+
+            match flow.max_parallel_tasks.filter(|max| *max > 0) {
+                Some(max) => (0..num_tasks.div_ceil(max))
+                    .map(|batch| (num_tasks - batch * max).min(max))
+                    .collect(),
+                None => vec![num_tasks],
+            }
+        })
+        .collect()
+}
+
+/// Project the schedule of flows if they were submitted together right now. Each stage is assumed to take one step
+/// of virtual time, or a step per batch of tasks for flows with limited parallelism, and all flows start at the first
+/// step and progress concurrently, as the executor does not limit the number of running flows. The flows are validated the same way as [`crate::executor::instantiate_flow`]
+/// except that referred secrets are not checked. Failures and the on failure task are not part of the projection.
+pub fn simulate_flows(
+    flows: &[Flow],
+    config: &ExecutorConfig,
+) -> Result<ScheduleProjection, ExecutorError> {
+    let mut projection = ScheduleProjection::default();
+
+    for flow in flows {
+        let plan = validate_and_plan_flow(flow, config)?;
+        let stage_concurrency = get_stage_concurrency(flow, &plan);
+
+        if projection.concurrency.len() < stage_concurrency.len() {
+            projection.concurrency.resize(stage_concurrency.len(), 0);
+        }
+
+        for (step, num_tasks) in stage_concurrency.iter().enumerate() {
+            projection.concurrency[step] += num_tasks;
+        }
+
+        projection.total_stages += plan.0.len();
+        projection.flows.push(FlowProjection {
+            flow_name: flow.name.clone(),
+            num_stages: plan.0.len(),
+            num_tasks: plan.0.iter().map(|task_ids| task_ids.len()).sum(),
+            peak_concurrency: stage_concurrency.into_iter().max().unwrap_or(0),
+        });
+    }
+
+    projection.num_steps = projection.concurrency.len();
+
+    for (step, num_tasks) in projection.concurrency.iter().enumerate() {
+        if *num_tasks > projection.peak_concurrency {
+            projection.peak_concurrency = *num_tasks;
+            projection.peak_step = step;
+        }
+    }
+
+    Ok(projection)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Input, Output, Task};
+
+    use super::*;
+
+    fn test_config() -> ExecutorConfig {
+        serde_json::from_value(serde_json::json!({
+            "store_url": "http://localhost:9000",
+            "task_store_url": "http://localhost:9000",
+            "bucket_name": "flowmium-test",
+            "access_key": "minio",
+            "secret_key": "password",
+            "init_container_image": "flowmium",
+            "namespace": "default",
+        }))
+        .unwrap()
+    }
+
+    fn task(name: &str, depends: &[&str]) -> Task {
+        Task {
+            name: name.to_owned(),
+            image: "debian:latest".to_owned(),
+            depends: depends.iter().map(|dep| dep.to_string()).collect(),
+            cmd: vec!["true".to_owned()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_simulate_flows() {
+        let config = test_config();
+
+        let diamond = Flow {
+            name: "diamond".to_owned(),
+            tasks: vec![
+                task("a", &[]),
+                task("b", &["a"]),
+                task("c", &["a"]),
+                task("d", &["b", "c"]),
+                task("cleanup", &[]),
+            ],
+            on_failure: Some("cleanup".to_owned()),
+            ..Default::default()
+        };
+
+        let eager = Flow {
+            name: "eager".to_owned(),
+            tasks: vec![
+                Task {
+                    outputs: Some(vec![Output {
+                        name: "out".to_owned(),
+                        path: "/out".to_owned(),
+                        ..Default::default()
+                    }]),
+                    ..task("produce", &[])
+                },
+                Task {
+                    inputs: Some(vec![Input {
+                        from: "out".to_owned(),
+                        path: "/in".to_owned(),
+                    }]),
+                    eager_inputs: true,
+                    active_deadline_seconds: Some(600),
+                    ..task("consume", &["produce"])
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            simulate_flows(&[diamond, eager], &config).unwrap(),
+            ScheduleProjection {
+                peak_concurrency: 3,
+                peak_step: 0,
+                num_steps: 3,
+                total_stages: 5,
+                concurrency: vec![3, 3, 1],
+                flows: vec![
+                    FlowProjection {
+                        flow_name: "diamond".to_owned(),
+                        num_stages: 3,
+                        num_tasks: 4,
+                        peak_concurrency: 2,
+                    },
+                    FlowProjection {
+                        flow_name: "eager".to_owned(),
+                        num_stages: 2,
+                        num_tasks: 2,
+                        peak_concurrency: 2,
+                    },
+                ],
+            }
+        );
+
+        let limited = Flow {
+            name: "limited".to_owned(),
+            tasks: ["a", "b", "c", "d", "e"]
+                .into_iter()
+                .map(|name| task(name, &[]))
+                .collect(),
+            max_parallel_tasks: Some(2),
+            ..Default::default()
+        };
+
+        let projection = simulate_flows(&[limited], &config).unwrap();
+        assert_eq!(projection.concurrency, vec![2, 2, 1]);
+        assert_eq!(projection.flows[0].peak_concurrency, 2);
+
+        assert_eq!(
+            simulate_flows(&[], &config).unwrap(),
+            ScheduleProjection::default()
+        );
+
+        let cyclic = Flow {
+            name: "cyclic".to_owned(),
+            tasks: vec![task("a", &["b"]), task("b", &["a"])],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            simulate_flows(&[cyclic], &config),
+            Err(ExecutorError::UnableToConstructPlan(_))
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+
+use super::scheduler::Scheduler;
+
+fn default_nats_subject() -> String {
+    "flowmium.events".to_owned()
+}
+
+/// Configuration for publishing scheduler events to a message broker.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct EventSinkConfig {
+    /// URL of the NATS server to publish scheduler events to. No events are published if this is not set.
+    pub nats_url: Option<String>,
+    /// NATS subject to publish scheduler events on. Default is `flowmium.events`.
+    #[serde(default = "default_nats_subject")]
+    pub nats_subject: String,
+}
+
+/// Spawn a tokio task that publishes every scheduler event ([`crate::event::SchedulerEvent`]) as JSON to NATS,
+/// in parallel to the websocket subscribers. Returns `None` without spawning anything if `nats_url` is not configured.
+/// The connection is retried with a backoff in the background when the broker is unreachable or disconnects,
+/// events that could not be kept up with during that time are skipped, the scheduler is never blocked.
+pub fn spawn_event_sink(sched: &Scheduler, config: EventSinkConfig) -> Option<JoinHandle<()>> {
+    let nats_url = config.nats_url?;
+    let subject = config.nats_subject;
+
+    let mut rx = sched.subscribe();
+
+    tracing::info!("Starting NATS event sink");
+
+    Some(tokio::spawn(async move {
+        let client = match async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect(&nats_url)
+            .await
+        {
+            Ok(client) => client,
+            Err(error) => {
+                tracing::error!(%error, "Unable to connect to NATS");
+                return;
+            }
+        };
+
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(count)) => {
+                    tracing::warn!("NATS event sink lagged, skipped {} events", count);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            // SAFETY: SchedulerEvent doesn't implement custom serializer methods or have non string keys
+            let payload = serde_json::to_vec(&event).unwrap();
+
+            if let Err(error) = client.publish(subject.clone(), payload.into()).await {
+                tracing::error!(%error, "Unable to publish event to NATS");
+            }
+        }
+    }))
+}
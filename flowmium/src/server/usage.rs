@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use s3::Bucket;
+
+use crate::task::{
+    bucket::list_artefact_sizes,
+    errors::ArtefactError,
+    layout::{FlowStoreLayout, StoreLayout},
+};
+
+use super::record::{StorageUsage, StorageUsageReport};
+
+fn sum_sizes(objects: &[(String, u64)]) -> StorageUsage {
+    StorageUsage {
+        bytes: objects.iter().map(|(_, size)| size).sum(),
+        objects: objects.len() as u64,
+    }
+}
+
+/// Group artefacts by the flow id in their keys as laid out by `layout`,
+/// objects that do not belong to a flow only count towards the total.
+fn summarize(objects: &[(String, u64)], layout: &StoreLayout) -> StorageUsageReport {
+    let mut report = StorageUsageReport {
+        total: sum_sizes(objects),
+        ..Default::default()
+    };
+
+    for (key, size) in objects {
+        let Some(flow_id) = layout.get_flow_id(key) else {
+            continue;
+        };
+
+        let usage = report.flows.entry(flow_id as i32).or_default();
+        usage.bytes += size;
+        usage.objects += 1;
+    }
+
+    report
+}
+
+#[derive(Debug, Default)]
+struct CachedUsage {
+    flows: HashMap<i32, (Instant, StorageUsage)>,
+    report: Option<(Instant, StorageUsageReport)>,
+}
+
+/// Storage used by artefacts, listing the bucket can be expensive so results are cached for `ttl`.
+#[derive(Debug, Clone)]
+pub(crate) struct StorageUsageCache {
+    ttl: Duration,
+    layout: StoreLayout,
+    cached: Arc<Mutex<CachedUsage>>,
+}
+
+impl StorageUsageCache {
+    pub(crate) fn new(ttl: Duration, layout: StoreLayout) -> Self {
+        Self {
+            ttl,
+            layout,
+            cached: Arc::new(Mutex::new(CachedUsage::default())),
+        }
+    }
+
+    fn is_fresh(&self, cached_at: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(cached_at) < self.ttl
+    }
+
+    /// Storage used by artefacts of a single flow, `flow_layout` is the layout of keys of the flow's artefacts.
+    pub(crate) async fn get_flow_usage(
+        &self,
+        bucket: &Bucket,
+        flow_id: i32,
+        flow_layout: &FlowStoreLayout,
+    ) -> Result<StorageUsage, ArtefactError> {
+        if let Some((cached_at, usage)) = self.cached.lock().unwrap().flows.get(&flow_id) {
+            if self.is_fresh(*cached_at, Instant::now()) {
+                return Ok(*usage);
+            }
+        }
+
+        let mut objects = list_artefact_sizes(bucket, flow_layout.prefix().to_owned()).await?;
+        objects.retain(|(key, _)| flow_layout.matches(key));
+        let usage = sum_sizes(&objects);
+
+        let now = Instant::now();
+        let mut cached = self.cached.lock().unwrap();
+        cached
+            .flows
+            .retain(|_, (cached_at, _)| self.is_fresh(*cached_at, now));
+        cached.flows.insert(flow_id, (now, usage));
+
+        Ok(usage)
+    }
+
+    /// Storage used by artefacts of all flows across `buckets`, which are the server's bucket and
+    /// buckets that flows store their artefacts in instead, see [`crate::model::Flow::bucket_name`].
+    pub(crate) async fn get_report(
+        &self,
+        buckets: &[Box<Bucket>],
+    ) -> Result<StorageUsageReport, ArtefactError> {
+        if let Some((cached_at, report)) = &self.cached.lock().unwrap().report {
+            if self.is_fresh(*cached_at, Instant::now()) {
+                return Ok(report.clone());
+            }
+        }
+
+        let mut objects = vec![];
+        for bucket in buckets {
+            objects.extend(list_artefact_sizes(bucket, "".to_owned()).await?);
+        }
+
+        let report = summarize(&objects, &self.layout);
+
+        self.cached.lock().unwrap().report = Some((Instant::now(), report.clone()));
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let objects = vec![
+            ("12/report".to_owned(), 100),
+            ("12/scratch".to_owned(), 50),
+            ("13/report".to_owned(), 7),
+            ("stray-object".to_owned(), 3),
+        ];
+
+        assert_eq!(
+            summarize(&objects, &StoreLayout::default()),
+            StorageUsageReport {
+                total: StorageUsage {
+                    bytes: 160,
+                    objects: 4
+                },
+                flows: BTreeMap::from([
+                    (
+                        12,
+                        StorageUsage {
+                            bytes: 150,
+                            objects: 2
+                        }
+                    ),
+                    (
+                        13,
+                        StorageUsage {
+                            bytes: 7,
+                            objects: 1
+                        }
+                    ),
+                ]),
+            }
+        );
+
+        assert_eq!(
+            summarize(&[], &StoreLayout::default()),
+            StorageUsageReport::default()
+        );
+
+        let layout = StoreLayout::parse("{date}/{flow_name}/{flow_id}/{output_name}").unwrap();
+        let report = summarize(
+            &[
+                ("2026-10-17/nightly/12/report".to_owned(), 100),
+                ("12/report".to_owned(), 50),
+            ],
+            &layout,
+        );
+        assert_eq!(report.total.objects, 2);
+        assert_eq!(report.flows.keys().collect::<Vec<_>>(), vec![&12]);
+        assert_eq!(report.flows[&12].bytes, 100);
+    }
+}
@@ -1,4 +1,15 @@
-use s3::{creds::Credentials, request::ResponseData, Bucket, BucketConfiguration, Region};
+use std::io::{Read, Write};
+
+use bytes::{Buf, Bytes};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::{Stream, StreamExt};
+use s3::{
+    bucket_ops::CreateBucketResponse, creds::Credentials, error::S3Error, request::ResponseData,
+    serde_types::HeadObjectResult, Bucket, BucketConfiguration, Region,
+};
+use sha2::{Digest, Sha256};
+
+use crate::retry::with_exp_backoff_retry_if;
 
 use super::errors::ArtefactError;
 
@@ -15,6 +26,32 @@ pub async fn bucket_exists(bucket: &Bucket) -> Result<bool, ArtefactError> {
     }
 }
 
+async fn create_bucket(
+    bucket: &Bucket,
+    credentials: Credentials,
+) -> Result<CreateBucketResponse, S3Error> {
+    match bucket.is_path_style() {
+        true => {
+            Bucket::create_with_path_style(
+                &bucket.name,
+                bucket.region.clone(),
+                credentials,
+                BucketConfiguration::public(),
+            )
+            .await
+        }
+        false => {
+            Bucket::create(
+                &bucket.name,
+                bucket.region.clone(),
+                credentials,
+                BucketConfiguration::public(),
+            )
+            .await
+        }
+    }
+}
+
 pub async fn create_if_does_not_exist(bucket: Box<Bucket>) -> Result<Box<Bucket>, ArtefactError> {
     let credentials = bucket.credentials().await.unwrap();
 
@@ -23,14 +60,7 @@ pub async fn create_if_does_not_exist(bucket: Box<Bucket>) -> Result<Box<Bucket>
             tracing::info!("Using existing bucket");
             Ok(bucket)
         }
-        false => match Bucket::create_with_path_style(
-            &bucket.name,
-            bucket.region.clone(),
-            credentials,
-            BucketConfiguration::public(),
-        )
-        .await
-        {
+        false => match create_bucket(&bucket, credentials).await {
             Ok(response) => match response.success() {
                 true => {
                     tracing::info!("Created a new bucket");
@@ -54,12 +84,16 @@ pub async fn create_if_does_not_exist(bucket: Box<Bucket>) -> Result<Box<Bucket>
     }
 }
 
-#[tracing::instrument(skip(access_key, secret_key))]
-pub async fn get_bucket(
+/// Region used for signing requests if none is configured, MinIO accepts any region.
+const DEFAULT_BUCKET_REGION: &str = "custom";
+
+fn open_bucket(
     access_key: &str,
     secret_key: &str,
     bucket_name: &str,
     store_url: String,
+    region: Option<&str>,
+    path_style: Option<bool>,
 ) -> Result<Box<Bucket>, ArtefactError> {
     let bucket_creds = match Credentials::new(Some(access_key), Some(secret_key), None, None, None)
     {
@@ -73,21 +107,88 @@ pub async fn get_bucket(
     };
 
     let bucket_region = Region::Custom {
-        region: "custom".to_owned(),
+        region: region.unwrap_or(DEFAULT_BUCKET_REGION).to_owned(),
         endpoint: store_url,
     };
 
-    let bucket = match Bucket::new(bucket_name, bucket_region.clone(), bucket_creds.clone()) {
-        Ok(bucket) => bucket.with_path_style(),
+    match Bucket::new(bucket_name, bucket_region, bucket_creds) {
+        Ok(bucket) => match path_style.unwrap_or(true) {
+            true => Ok(bucket.with_path_style()),
+            false => Ok(bucket),
+        },
         Err(error) => {
             tracing::error!(%error, "Unable to open bucket");
-            return Err(ArtefactError::UnableToExistingOpenBucket(error));
+            Err(ArtefactError::UnableToExistingOpenBucket(error))
         }
-    };
+    }
+}
+
+/// Open the bucket for flow artefacts, creating it if it does not exist. Requests are signed for `region` and use
+/// path-style URLs unless `path_style` is `false`, the defaults work for MinIO. AWS S3 needs the region of the bucket
+/// and virtual-hosted style URLs, where the bucket name is part of the host.
+#[tracing::instrument(skip(access_key, secret_key))]
+pub async fn get_bucket(
+    access_key: &str,
+    secret_key: &str,
+    bucket_name: &str,
+    store_url: String,
+    region: Option<&str>,
+    path_style: Option<bool>,
+) -> Result<Box<Bucket>, ArtefactError> {
+    let bucket = open_bucket(
+        access_key,
+        secret_key,
+        bucket_name,
+        store_url,
+        region,
+        path_style,
+    )?;
 
     create_if_does_not_exist(bucket).await
 }
 
+/// Bucket on the same store with the same credentials as `bucket` but named `bucket_name`, for flows that store their
+/// artefacts in their own bucket, see [`crate::model::Flow::bucket_name`]. Returns a copy of `bucket` if not set.
+pub fn get_named_bucket(bucket: &Bucket, bucket_name: Option<&str>) -> Box<Bucket> {
+    let mut named_bucket = Box::new(bucket.clone());
+
+    if let Some(bucket_name) = bucket_name {
+        named_bucket.name = bucket_name.to_owned();
+    }
+
+    named_bucket
+}
+
+/// Same as [`get_bucket`] but retries with exponential backoff if the store is unreachable,
+/// errors that are not transient like a misconfigured bucket or bad credentials fail immediately.
+pub async fn get_bucket_with_retry(
+    access_key: &str,
+    secret_key: &str,
+    bucket_name: &str,
+    store_url: &str,
+    region: Option<&str>,
+    path_style: Option<bool>,
+    max_retry_count: i32,
+) -> Result<Box<Bucket>, ArtefactError> {
+    with_exp_backoff_retry_if(
+        || {
+            get_bucket(
+                access_key,
+                secret_key,
+                bucket_name,
+                store_url.to_owned(),
+                region,
+                path_style,
+            )
+        },
+        ArtefactError::is_transient,
+        "Store is unreachable",
+        max_retry_count,
+        true,
+    )
+    .await
+}
+
 pub async fn create_parent_directories(local_path: &String) -> tokio::io::Result<()> {
     let path = std::path::Path::new(&local_path);
     let prefix = match path.parent() {
@@ -100,35 +201,358 @@ pub async fn create_parent_directories(local_path: &String) -> tokio::io::Result
     tokio::fs::create_dir_all(prefix).await
 }
 
-pub async fn get_artefact(
+/// Content type of artefacts that have been compressed with gzip before uploading, other artefacts are uploaded
+/// as `application/octet-stream`. Artefacts are marked by their content type instead of their key so that
+/// keys of outputs stay the same whether they are compressed or not.
+const COMPRESSED_CONTENT_TYPE: &str = "application/gzip";
+
+/// Metadata header of artefacts with the SHA-256 checksum of their contents as a hex string, computed before
+/// compression. Artefacts uploaded before checksums were added do not have it and are not verified.
+const CHECKSUM_METADATA_HEADER: &str = "x-amz-meta-flowmium-sha256";
+
+/// Metadata header of artefacts uploaded from a directory, which are stored as a tar archive of the directory and
+/// extracted when downloaded as an input ([`download_input`]).
+const DIRECTORY_METADATA_HEADER: &str = "x-amz-meta-flowmium-directory";
+
+fn get_checksum(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn compress_artefact(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+fn decompress_artefact(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(content).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+fn archive_directory(path: &str) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", path)?;
+    builder.into_inner()
+}
+
+fn extract_directory(content: &[u8], path: &str) -> std::io::Result<()> {
+    let mut archive = tar::Archive::new(content);
+    archive.set_overwrite(true);
+    archive.unpack(path)
+}
+
+fn is_compressed(response: &ResponseData) -> bool {
+    response
+        .headers()
+        .get("content-type")
+        .is_some_and(|content_type| content_type == COMPRESSED_CONTENT_TYPE)
+}
+
+fn get_download_error(error: s3::error::S3Error, store_path: String) -> ArtefactError {
+    match error {
+        s3::error::S3Error::HttpFailWithBody(404, _) => {
+            tracing::error!("Got 404 response while downloading artefact");
+            ArtefactError::ArtefactDoesNotExist(store_path)
+        }
+        error => {
+            tracing::error!(%error, "Could not download artefact");
+            ArtefactError::UnableToDownloadInput(error)
+        }
+    }
+}
+
+fn check_download_status(status_code: u16) -> Result<(), ArtefactError> {
+    if status_code != 200 {
+        tracing::error!(
+            "Response was non ok code {} while downloading artefact",
+            status_code
+        );
+        return Err(ArtefactError::UnableToDownloadInputApi(status_code));
+    }
+
+    Ok(())
+}
+
+async fn get_artefact_response(
     bucket: &Bucket,
     store_path: String,
 ) -> Result<ResponseData, ArtefactError> {
     let response = match bucket.get_object(&store_path).await {
+        Ok(response) => response,
+        Err(error) => return Err(get_download_error(error, store_path)),
+    };
+
+    check_download_status(response.status_code())?;
+
+    Ok(response)
+}
+
+/// Download the contents of an artefact, artefacts that were compressed when uploaded ([`upload_output`])
+/// are decompressed. The contents are verified against the checksum the artefact was uploaded with,
+/// so that a corrupted or truncated artefact is never used. Artefacts uploaded from a directory are
+/// downloaded as a tar archive of the directory, along with whether the artefact was uploaded from a directory.
+async fn get_artefact_content(
+    bucket: &Bucket,
+    store_path: String,
+) -> Result<(Vec<u8>, bool), ArtefactError> {
+    let response = get_artefact_response(bucket, store_path.clone()).await?;
+
+    let expected_checksum = response.headers().get(CHECKSUM_METADATA_HEADER).cloned();
+    let is_directory = response.headers().contains_key(DIRECTORY_METADATA_HEADER);
+
+    let content = match is_compressed(&response) {
+        false => response.into(),
+        true => match decompress_artefact(response.as_slice()) {
+            Ok(content) => content,
+            Err(error) => {
+                tracing::error!(%error, "Unable to decompress artefact");
+                return Err(ArtefactError::UnableToDecompressArtefact(error));
+            }
+        },
+    };
+
+    if let Some(expected_checksum) = expected_checksum {
+        let checksum = get_checksum(&content);
+
+        if checksum != expected_checksum {
+            tracing::error!(
+                expected_checksum,
+                checksum,
+                "Checksum of downloaded artefact does not match"
+            );
+            return Err(ArtefactError::ChecksumMismatch(
+                store_path,
+                expected_checksum,
+                checksum,
+            ));
+        }
+    }
+
+    Ok((content, is_directory))
+}
+
+/// Maximum size of a chunk of decompressed contents when streaming an artefact, so that a small chunk of a highly
+/// compressed artefact is not decompressed into a large chunk all at once.
+const MAX_DECOMPRESSED_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Decompresses an artefact and computes its checksum one chunk at a time while it is streamed ([`stream_artefact`]).
+struct ArtefactDecoder {
+    store_path: String,
+    decompressor: Option<flate2::write::GzDecoder<Vec<u8>>>,
+    hasher: Sha256,
+    expected_checksum: Option<String>,
+}
+
+impl ArtefactDecoder {
+    fn new(store_path: String, head: &HeadObjectResult) -> Self {
+        let metadata = |header: &str| {
+            let name = header.strip_prefix("x-amz-meta-")?;
+            head.metadata.as_ref()?.get(name).cloned()
+        };
+
+        let is_compressed = head.content_type.as_deref() == Some(COMPRESSED_CONTENT_TYPE);
+
+        Self {
+            store_path,
+            decompressor: is_compressed.then(|| flate2::write::GzDecoder::new(Vec::new())),
+            hasher: Sha256::new(),
+            expected_checksum: metadata(CHECKSUM_METADATA_HEADER),
+        }
+    }
+
+    /// Decode the start of `chunk` and advance it past the decoded part, `chunk` is empty once all of it is decoded.
+    fn decode(&mut self, chunk: &mut Bytes) -> Result<Bytes, ArtefactError> {
+        let content = match &mut self.decompressor {
+            None => std::mem::take(chunk),
+            Some(decompressor) => {
+                while !chunk.is_empty()
+                    && decompressor.get_ref().len() < MAX_DECOMPRESSED_CHUNK_SIZE
+                {
+                    match decompressor.write(chunk) {
+                        Ok(0) => {
+                            let error = std::io::Error::from(std::io::ErrorKind::WriteZero);
+                            tracing::error!(%error, "Unable to decompress artefact");
+                            return Err(ArtefactError::UnableToDecompressArtefact(error));
+                        }
+                        Ok(written) => chunk.advance(written),
+                        Err(error) => {
+                            tracing::error!(%error, "Unable to decompress artefact");
+                            return Err(ArtefactError::UnableToDecompressArtefact(error));
+                        }
+                    }
+                }
+
+                Bytes::from(std::mem::take(decompressor.get_mut()))
+            }
+        };
+
+        self.hasher.update(&content);
+
+        Ok(content)
+    }
+
+    fn finish(mut self) -> Result<Bytes, ArtefactError> {
+        let content = match self
+            .decompressor
+            .take()
+            .map(|decompressor| decompressor.finish())
+        {
+            None => Bytes::new(),
+            Some(Ok(remaining)) => Bytes::from(remaining),
+            Some(Err(error)) => {
+                tracing::error!(%error, "Unable to decompress artefact");
+                return Err(ArtefactError::UnableToDecompressArtefact(error));
+            }
+        };
+
+        self.hasher.update(&content);
+
+        if let Some(expected_checksum) = self.expected_checksum {
+            let checksum = format!("{:x}", self.hasher.finalize());
+
+            if checksum != expected_checksum {
+                tracing::error!(
+                    expected_checksum,
+                    checksum,
+                    "Checksum of downloaded artefact does not match"
+                );
+                return Err(ArtefactError::ChecksumMismatch(
+                    self.store_path,
+                    expected_checksum,
+                    checksum,
+                ));
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+/// Same as [`get_artefact_content`] but the contents are streamed in chunks as they are downloaded instead of being
+/// held in memory all at once. Since the checksum can only be verified once the whole artefact has been downloaded,
+/// a mismatch is returned as the last item of the stream after all of the contents.
+pub async fn stream_artefact(
+    bucket: &Bucket,
+    store_path: String,
+) -> Result<impl Stream<Item = Result<Bytes, ArtefactError>>, ArtefactError> {
+    // Headers of the object are not returned with the streamed response
+    let head = head_artefact(bucket, store_path.clone()).await?;
+
+    let response = match bucket.get_object_stream(&store_path).await {
+        Ok(response) => response,
+        Err(error) => return Err(get_download_error(error, store_path)),
+    };
+
+    check_download_status(response.status_code)?;
+
+    let decoder = ArtefactDecoder::new(store_path, &head);
+
+    Ok(futures::stream::unfold(
+        Some((response.bytes, decoder, Bytes::new())),
+        |state| async move {
+            let (mut bytes, mut decoder, mut pending) = state?;
+
+            loop {
+                while pending.is_empty() {
+                    pending = match bytes.next().await {
+                        Some(Ok(chunk)) => chunk,
+                        Some(Err(error)) => {
+                            tracing::error!(%error, "Could not download artefact");
+                            return Some((Err(ArtefactError::UnableToDownloadInput(error)), None));
+                        }
+                        None => {
+                            // Stream ends after the rest of the contents or the checksum mismatch
+                            return match decoder.finish() {
+                                Ok(content) if content.is_empty() => None,
+                                result => Some((result, None)),
+                            };
+                        }
+                    };
+                }
+
+                match decoder.decode(&mut pending) {
+                    Ok(content) if content.is_empty() => continue,
+                    Ok(content) => return Some((Ok(content), Some((bytes, decoder, pending)))),
+                    Err(error) => return Some((Err(error), None)),
+                }
+            }
+        },
+    ))
+}
+
+pub async fn head_artefact(
+    bucket: &Bucket,
+    store_path: String,
+) -> Result<HeadObjectResult, ArtefactError> {
+    let (head, status_code) = match bucket.head_object(&store_path).await {
         Ok(response) => response,
         Err(error) => match error {
             s3::error::S3Error::HttpFailWithBody(404, _) => {
-                tracing::error!("Got 404 response while downloading artefact");
                 return Err(ArtefactError::ArtefactDoesNotExist(store_path));
             }
             error => {
-                tracing::error!(%error, "Could not download artefact");
-                return Err(ArtefactError::UnableToDownloadInput(error));
+                tracing::error!(%error, "Could not check if artefact exists");
+                return Err(ArtefactError::UnableToCheckArtefact(error));
             }
         },
     };
 
-    let status_code = response.status_code();
-
-    if status_code != 200 {
-        tracing::error!(
-            "Response was non ok code {} while downloading artefact",
-            status_code
-        );
-        return Err(ArtefactError::UnableToDownloadInputApi(status_code));
+    match status_code {
+        200 => Ok(head),
+        404 => Err(ArtefactError::ArtefactDoesNotExist(store_path)),
+        status_code => {
+            tracing::error!(
+                "Response was non ok code {} while checking if artefact exists",
+                status_code
+            );
+            Err(ArtefactError::UnableToCheckArtefactApi(status_code))
+        }
     }
+}
 
-    Ok(response)
+/// Keys and sizes in bytes of all objects in the bucket whose key starts with `prefix`.
+pub async fn list_artefact_sizes(
+    bucket: &Bucket,
+    prefix: String,
+) -> Result<Vec<(String, u64)>, ArtefactError> {
+    let results = match bucket.list(prefix, None).await {
+        Ok(results) => results,
+        Err(error) => {
+            tracing::error!(%error, "Could not list artefacts");
+            return Err(ArtefactError::UnableToListArtefacts(error));
+        }
+    };
+
+    Ok(results
+        .into_iter()
+        .flat_map(|result| result.contents)
+        .map(|object| (object.key, object.size))
+        .collect())
+}
+
+/// Delete an artefact, deleting an artefact that does not exist succeeds.
+pub async fn delete_artefact(bucket: &Bucket, store_path: String) -> Result<(), ArtefactError> {
+    let response = match bucket.delete_object(&store_path).await {
+        Ok(response) => response,
+        Err(error) => {
+            tracing::error!(%error, store_path, "Could not delete artefact");
+            return Err(ArtefactError::UnableToDeleteArtefact(error));
+        }
+    };
+
+    match response.status_code() {
+        200 | 204 | 404 => Ok(()),
+        status_code => {
+            tracing::error!(
+                store_path,
+                "Response was non ok code {} while deleting artefact",
+                status_code
+            );
+            Err(ArtefactError::UnableToDeleteArtefactApi(status_code))
+        }
+    }
 }
 
 #[tracing::instrument(skip(bucket))]
@@ -139,14 +563,28 @@ pub async fn download_input(
 ) -> Result<(), ArtefactError> {
     tracing::info!("Downloading input");
 
-    let response = get_artefact(bucket, store_path).await?;
+    let (content, is_directory) = get_artefact_content(bucket, store_path).await?;
+
+    if is_directory {
+        if let Err(error) = tokio::fs::create_dir_all(&local_path).await {
+            tracing::error!(%error, "Unable to create directory for input");
+            return Err(ArtefactError::UnableToWriteInput(error));
+        }
+
+        if let Err(error) = extract_directory(&content, &local_path) {
+            tracing::error!(%error, "Unable to extract input directory");
+            return Err(ArtefactError::UnableToExtractInput(error));
+        }
+
+        return Ok(());
+    }
 
     if let Err(error) = create_parent_directories(&local_path).await {
         tracing::error!(%error, "Unable to create parent directories for input");
         return Err(ArtefactError::UnableToWriteInput(error));
     }
 
-    if let std::io::Result::Err(error) = tokio::fs::write(local_path, &response.bytes()).await {
+    if let std::io::Result::Err(error) = tokio::fs::write(local_path, &content).await {
         tracing::error!(%error, "File error while downloading input");
         return Err(ArtefactError::UnableToWriteInput(error));
     }
@@ -154,23 +592,63 @@ pub async fn download_input(
     Ok(())
 }
 
+/// Upload an output along with its checksum, the output is compressed with gzip first if `compress` is set.
+/// Compressed outputs are decompressed and checksums are verified when downloaded ([`get_artefact_content`]).
+/// An output that is a directory is uploaded as a tar archive of the directory.
 #[tracing::instrument(skip(bucket))]
 pub async fn upload_output(
     bucket: &Bucket,
     local_path: String,
     store_path: String,
+    compress: bool,
 ) -> Result<(), ArtefactError> {
     tracing::info!("Uploading output");
 
-    let content = match tokio::fs::read(local_path).await {
-        Ok(content) => content,
+    let is_directory = match tokio::fs::metadata(&local_path).await {
+        Ok(metadata) => metadata.is_dir(),
         Err(error) => {
             tracing::error!(%error, "File error while uploading output");
             return Err(ArtefactError::UnableToReadOutput(error));
         }
     };
 
-    let response = match bucket.put_object(store_path, &content).await {
+    let content = match is_directory {
+        false => tokio::fs::read(local_path)
+            .await
+            .map_err(ArtefactError::UnableToReadOutput),
+        true => archive_directory(&local_path).map_err(ArtefactError::UnableToArchiveOutput),
+    };
+
+    let content = match content {
+        Ok(content) => content,
+        Err(error) => {
+            tracing::error!(%error, "Unable to read output");
+            return Err(error);
+        }
+    };
+
+    let mut bucket = bucket.clone();
+    bucket.add_header(CHECKSUM_METADATA_HEADER, &get_checksum(&content));
+
+    if is_directory {
+        bucket.add_header(DIRECTORY_METADATA_HEADER, "true");
+    }
+
+    let (content, content_type) = match compress {
+        false => (content, "application/octet-stream"),
+        true => match compress_artefact(&content) {
+            Ok(compressed) => (compressed, COMPRESSED_CONTENT_TYPE),
+            Err(error) => {
+                tracing::error!(%error, "Unable to compress output");
+                return Err(ArtefactError::UnableToCompressOutput(error));
+            }
+        },
+    };
+
+    let response = match bucket
+        .put_object_with_content_type(store_path, &content, content_type)
+        .await
+    {
         Ok(response) => response,
         Err(error) => {
             tracing::error!(%error, "Could not upload output");
@@ -190,3 +668,652 @@ pub async fn upload_output(
 
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) fn get_test_bucket(url: String) -> Box<Bucket> {
+    let credentials =
+        s3::creds::Credentials::new(Some("minio"), Some("password"), None, None, None).unwrap();
+    let region = s3::Region::Custom {
+        region: "custom".to_owned(),
+        endpoint: url,
+    };
+
+    Bucket::new("flowmium-test", region, credentials)
+        .unwrap()
+        .with_path_style()
+}
+
+/// Serve a fake bucket holding objects with `keys`, supporting listing objects by prefix and deleting objects.
+/// Returns URL of the store the bucket is served from.
+#[cfg(test)]
+pub(crate) async fn serve_test_bucket(
+    keys: std::sync::Arc<std::sync::Mutex<std::collections::BTreeSet<String>>>,
+) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = [0; 4096];
+            let len = socket.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..len]);
+
+            let mut request_line = request.split_whitespace();
+            let method = request_line.next().unwrap_or_default();
+            let path = request_line
+                .next()
+                .unwrap_or_default()
+                .replace("%2F", "/")
+                .replace("%2f", "/");
+
+            let (status, body) = match method {
+                "DELETE" => {
+                    let key = path.trim_start_matches("/flowmium-test/");
+                    keys.lock().unwrap().remove(key);
+
+                    ("204 No Content", String::new())
+                }
+                _ => {
+                    let prefix = path
+                        .split(['?', '&'])
+                        .find_map(|param| param.strip_prefix("prefix="))
+                        .unwrap_or_default();
+
+                    let contents: String = keys
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|key| key.starts_with(prefix))
+                        .map(|key| {
+                            format!(
+                                "<Contents><Key>{}</Key><LastModified>2026-10-17T00:00:00.000Z</LastModified><Size>4</Size></Contents>",
+                                key
+                            )
+                        })
+                        .collect();
+
+                    (
+                        "200 OK",
+                        format!(
+                            "<ListBucketResult><Name>flowmium-test</Name><IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+                            contents
+                        ),
+                    )
+                }
+            };
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    url
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct StoredObject {
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Objects stored by [`serve_test_objects`] by path of their request, which starts with the bucket's name.
+#[cfg(test)]
+pub(crate) type StoredObjects =
+    std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, StoredObject>>>;
+
+/// Serve `PUT`, `GET` and `HEAD` requests for objects and listing objects by prefix from memory, storing each
+/// object's content type and metadata headers along with it.
+#[cfg(test)]
+async fn serve_objects(listener: tokio::net::TcpListener, objects: StoredObjects) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    loop {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let objects = objects.clone();
+
+        tokio::spawn(async move {
+            let mut buffer = vec![];
+
+            loop {
+                let header_end = loop {
+                    if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+
+                    let mut chunk = [0; 4096];
+                    match socket.read(&mut chunk).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                    }
+                };
+
+                let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+                let mut lines = head.lines();
+                let mut request_line = lines.next().unwrap().split(' ');
+                let (method, path) = (request_line.next().unwrap(), request_line.next().unwrap());
+
+                let headers: std::collections::HashMap<String, String> = lines
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(name, value)| (name.to_lowercase(), value.trim().to_owned()))
+                    .collect();
+
+                let content_length: usize = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+
+                while buffer.len() < header_end + content_length {
+                    let mut chunk = [0; 4096];
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..read]);
+                }
+
+                let body: Vec<u8> = buffer
+                    .drain(..header_end + content_length)
+                    .skip(header_end)
+                    .collect();
+
+                let xml_headers = vec![("content-type".to_owned(), "application/xml".to_owned())];
+
+                let (status_line, object_headers, body) = match method {
+                    "PUT" => {
+                        let object_headers = headers
+                            .into_iter()
+                            .filter(|(name, _)| {
+                                name == "content-type" || name.starts_with("x-amz-meta-")
+                            })
+                            .collect();
+                        objects.lock().unwrap().insert(
+                            path.to_owned(),
+                            StoredObject {
+                                headers: object_headers,
+                                body,
+                            },
+                        );
+                        ("HTTP/1.1 200 OK", xml_headers, vec![])
+                    }
+                    "GET" if path.contains("list-type=2") => {
+                        let (bucket_path, query) = path.split_once('?').unwrap();
+                        let bucket_path = format!("{}/", bucket_path.trim_end_matches('/'));
+                        let prefix = query
+                            .split('&')
+                            .find_map(|param| param.strip_prefix("prefix="))
+                            .unwrap_or_default()
+                            .replace("%2F", "/");
+
+                        let mut keys: Vec<String> = objects
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .filter_map(|(path, object)| {
+                                let key = path.strip_prefix(&bucket_path)?;
+                                key.starts_with(&prefix).then(|| {
+                                    format!(
+                                        "<Contents><Key>{}</Key><LastModified>2026-10-17T00:00:00.000Z</LastModified><Size>{}</Size></Contents>",
+                                        key,
+                                        object.body.len()
+                                    )
+                                })
+                            })
+                            .collect();
+                        keys.sort();
+
+                        let body = format!(
+                            "<ListBucketResult><Name>{}</Name><IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+                            bucket_path.trim_matches('/'),
+                            keys.concat()
+                        );
+
+                        ("HTTP/1.1 200 OK", xml_headers, body.into_bytes())
+                    }
+                    _ => match objects.lock().unwrap().get(path) {
+                        Some(object) => (
+                            "HTTP/1.1 200 OK",
+                            object.headers.clone(),
+                            object.body.clone(),
+                        ),
+                        None => ("HTTP/1.1 404 Not Found", xml_headers, vec![]),
+                    },
+                };
+
+                let object_headers: String = object_headers
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                    .collect();
+
+                let response = format!(
+                    "{}\r\n{}ETag: \"etag\"\r\nContent-Length: {}\r\n\r\n",
+                    status_line,
+                    object_headers,
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+
+                if method != "HEAD" {
+                    socket.write_all(&body).await.unwrap();
+                }
+            }
+        });
+    }
+}
+
+/// Serve objects of any bucket from memory like [`serve_test_bucket`], supporting uploading, downloading, listing
+/// and checking whether objects exist. Returns URL of the store and its objects.
+#[cfg(test)]
+pub(crate) async fn serve_test_objects() -> (String, StoredObjects) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let objects = StoredObjects::default();
+    tokio::spawn(serve_objects(listener, objects.clone()));
+
+    (url, objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    const LIST_BUCKETS_RESPONSE: &str = "<ListAllMyBucketsResult><Owner><ID>id</ID><DisplayName>flowmium</DisplayName></Owner>\
+        <Buckets><Bucket><Name>flowmium-test</Name><CreationDate>2023-01-01T00:00:00.000Z</CreationDate></Bucket></Buckets>\
+        </ListAllMyBucketsResult>";
+
+    async fn serve_forever(listener: TcpListener, status_line: &'static str, body: &'static str) {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = [0; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+
+            let response = format!(
+                "{}\r\nContent-Type: application/xml\r\nContent-Length: {}\r\n\r\n{}",
+                status_line,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    async fn reserve_url() -> (std::net::SocketAddr, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        (addr, format!("http://{}", addr))
+    }
+
+    #[test]
+    fn test_open_bucket_region_and_path_style() {
+        let bucket = open_bucket(
+            "minio",
+            "password",
+            "flowmium-test",
+            "http://localhost:9000".to_owned(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(bucket.is_path_style());
+        assert_eq!(bucket.region().to_string(), "custom");
+        assert_eq!(bucket.url(), "http://localhost:9000/flowmium-test");
+
+        let bucket = open_bucket(
+            "minio",
+            "password",
+            "flowmium-test",
+            "https://s3.eu-west-1.amazonaws.com".to_owned(),
+            Some("eu-west-1"),
+            Some(false),
+        )
+        .unwrap();
+
+        assert!(!bucket.is_path_style());
+        assert_eq!(bucket.region().to_string(), "eu-west-1");
+        assert_eq!(
+            bucket.url(),
+            "https://flowmium-test.s3.eu-west-1.amazonaws.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_bucket_with_retry_store_delayed() {
+        let (addr, url) = reserve_url().await;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            let listener = TcpListener::bind(addr).await.unwrap();
+            serve_forever(listener, "HTTP/1.1 200 OK", LIST_BUCKETS_RESPONSE).await;
+        });
+
+        let bucket =
+            get_bucket_with_retry("minio", "password", "flowmium-test", &url, None, None, 4)
+                .await
+                .unwrap();
+        assert_eq!(bucket.name, "flowmium-test");
+
+        let (_, unreachable_url) = reserve_url().await;
+        let error = get_bucket_with_retry(
+            "minio",
+            "password",
+            "flowmium-test",
+            &unreachable_url,
+            None,
+            None,
+            0,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_get_bucket_with_retry_misconfigured() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(serve_forever(
+            listener,
+            "HTTP/1.1 403 Forbidden",
+            "<Error><Code>InvalidAccessKeyId</Code></Error>",
+        ));
+
+        let start = Instant::now();
+        let error =
+            get_bucket_with_retry("minio", "password", "flowmium-test", &url, None, None, 4)
+                .await
+                .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ArtefactError::UnableToCheckExistence(s3::error::S3Error::HttpFailWithBody(403, _))
+        ));
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    async fn get_test_bucket() -> (Box<Bucket>, StoredObjects) {
+        let (url, objects) = serve_test_objects().await;
+
+        let credentials =
+            Credentials::new(Some("minio"), Some("password"), None, None, None).unwrap();
+        let region = Region::Custom {
+            region: "custom".to_owned(),
+            endpoint: url,
+        };
+        let bucket = Bucket::new("flowmium-test", region, credentials)
+            .unwrap()
+            .with_path_style();
+
+        (bucket, objects)
+    }
+
+    fn get_stored_header<'a>(object: &'a StoredObject, name: &str) -> Option<&'a str> {
+        object
+            .headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[tokio::test]
+    async fn test_compressed_artefact_round_trip() {
+        let (bucket, objects) = get_test_bucket().await;
+
+        let dir = std::env::temp_dir().join("flowmium-test-compressed-artefact");
+        let output_path = dir.join("output").to_string_lossy().to_string();
+        let input_path = dir.join("input").to_string_lossy().to_string();
+
+        let payload = "flowmium ".repeat(1000).into_bytes();
+        create_parent_directories(&output_path).await.unwrap();
+        tokio::fs::write(&output_path, &payload).await.unwrap();
+
+        upload_output(
+            &bucket,
+            output_path.clone(),
+            "1/compressed".to_owned(),
+            true,
+        )
+        .await
+        .unwrap();
+        upload_output(&bucket, output_path, "1/uncompressed".to_owned(), false)
+            .await
+            .unwrap();
+
+        {
+            let objects = objects.lock().unwrap();
+
+            let compressed = &objects["/flowmium-test/1/compressed"];
+            assert_eq!(
+                get_stored_header(compressed, "content-type"),
+                Some(COMPRESSED_CONTENT_TYPE)
+            );
+            assert!(compressed.body.len() < payload.len());
+            assert_eq!(decompress_artefact(&compressed.body).unwrap(), payload);
+
+            let uncompressed = &objects["/flowmium-test/1/uncompressed"];
+            assert_eq!(
+                get_stored_header(uncompressed, "content-type"),
+                Some("application/octet-stream")
+            );
+            assert_eq!(uncompressed.body, payload);
+        }
+
+        for store_path in ["1/compressed", "1/uncompressed"] {
+            assert_eq!(
+                get_artefact_content(&bucket, store_path.to_owned())
+                    .await
+                    .unwrap()
+                    .0,
+                payload
+            );
+
+            download_input(&bucket, input_path.clone(), store_path.to_owned())
+                .await
+                .unwrap();
+            assert_eq!(tokio::fs::read(&input_path).await.unwrap(), payload);
+        }
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    async fn collect_artefact_stream(
+        bucket: &Bucket,
+        store_path: &str,
+    ) -> Vec<Result<Bytes, ArtefactError>> {
+        stream_artefact(bucket, store_path.to_owned())
+            .await
+            .unwrap()
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_stream_large_artefact() {
+        let (bucket, _) = get_test_bucket().await;
+
+        let dir = std::env::temp_dir().join("flowmium-test-stream-artefact");
+        let output_path = dir.join("output").to_string_lossy().to_string();
+
+        let payload: Vec<u8> = (0..8 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        create_parent_directories(&output_path).await.unwrap();
+        tokio::fs::write(&output_path, &payload).await.unwrap();
+
+        for (store_path, compress) in [("1/uncompressed", false), ("1/compressed", true)] {
+            upload_output(
+                &bucket,
+                output_path.clone(),
+                store_path.to_owned(),
+                compress,
+            )
+            .await
+            .unwrap();
+
+            let chunks: Vec<Bytes> = collect_artefact_stream(&bucket, store_path)
+                .await
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            // Contents are sent on as they are downloaded instead of after the whole artefact has been downloaded
+            assert!(chunks.len() > 1);
+            assert!(chunks.iter().all(|chunk| chunk.len() < payload.len() / 4));
+            assert_eq!(chunks.concat(), payload);
+        }
+
+        assert!(matches!(
+            stream_artefact(&bucket, "1/missing".to_owned()).await,
+            Err(ArtefactError::ArtefactDoesNotExist(_))
+        ));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_directory_artefact_round_trip() {
+        let (bucket, objects) = get_test_bucket().await;
+
+        let dir = std::env::temp_dir().join("flowmium-test-directory-artefact");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+
+        // Output of the producing task
+        let output_path = dir.join("producer/report");
+        tokio::fs::create_dir_all(output_path.join("plots"))
+            .await
+            .unwrap();
+        tokio::fs::write(output_path.join("summary.txt"), b"summary")
+            .await
+            .unwrap();
+        tokio::fs::write(output_path.join("plots/loss.svg"), b"<svg/>")
+            .await
+            .unwrap();
+
+        for (store_path, compress) in [("1/report", false), ("1/report-compressed", true)] {
+            upload_output(
+                &bucket,
+                output_path.to_string_lossy().to_string(),
+                store_path.to_owned(),
+                compress,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                get_stored_header(
+                    &objects.lock().unwrap()[&format!("/flowmium-test/{}", store_path)],
+                    DIRECTORY_METADATA_HEADER
+                ),
+                Some("true")
+            );
+
+            // Input of the consuming task
+            let input_path = dir.join("consumer").join(store_path);
+
+            download_input(
+                &bucket,
+                input_path.to_string_lossy().to_string(),
+                store_path.to_owned(),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                tokio::fs::read(input_path.join("summary.txt"))
+                    .await
+                    .unwrap(),
+                b"summary"
+            );
+            assert_eq!(
+                tokio::fs::read(input_path.join("plots/loss.svg"))
+                    .await
+                    .unwrap(),
+                b"<svg/>"
+            );
+        }
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_artefact_checksum_mismatch() {
+        let (bucket, objects) = get_test_bucket().await;
+
+        let dir = std::env::temp_dir().join("flowmium-test-artefact-checksum");
+        let output_path = dir.join("output").to_string_lossy().to_string();
+        let input_path = dir.join("input").to_string_lossy().to_string();
+
+        let payload = b"flowmium artefact".to_vec();
+        create_parent_directories(&output_path).await.unwrap();
+        tokio::fs::write(&output_path, &payload).await.unwrap();
+
+        upload_output(&bucket, output_path, "1/output".to_owned(), false)
+            .await
+            .unwrap();
+
+        let checksum = get_checksum(&payload);
+
+        assert_eq!(
+            get_stored_header(
+                &objects.lock().unwrap()["/flowmium-test/1/output"],
+                CHECKSUM_METADATA_HEADER
+            ),
+            Some(checksum.as_str())
+        );
+        assert_eq!(
+            get_artefact_content(&bucket, "1/output".to_owned())
+                .await
+                .unwrap()
+                .0,
+            payload
+        );
+
+        objects
+            .lock()
+            .unwrap()
+            .get_mut("/flowmium-test/1/output")
+            .unwrap()
+            .body
+            .truncate(8);
+
+        let mut items = collect_artefact_stream(&bucket, "1/output").await;
+        assert!(matches!(
+            items.pop(),
+            Some(Err(ArtefactError::ChecksumMismatch(..)))
+        ));
+        assert_eq!(
+            items.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+            vec![Bytes::from_static(b"flowmium")]
+        );
+
+        let error = download_input(&bucket, input_path.clone(), "1/output".to_owned())
+            .await
+            .unwrap_err();
+
+        match error {
+            ArtefactError::ChecksumMismatch(store_path, expected, actual) => {
+                assert_eq!(store_path, "1/output");
+                assert_eq!(expected, checksum);
+                assert_eq!(actual, get_checksum(b"flowmium"));
+            }
+            error => panic!("unexpected error {}", error),
+        }
+        assert!(!tokio::fs::try_exists(&input_path).await.unwrap());
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
@@ -2,52 +2,167 @@ use s3::Bucket;
 use serde::Deserialize;
 use serde_json;
 
-use std::process::{Command, ExitCode, Stdio};
+use std::process::{ExitCode, Output as CommandOutput, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
 
 use crate::model::{Input, Output};
 
-use super::bucket::{download_input, get_bucket, upload_output};
+use super::bucket::{download_input, get_bucket_with_retry, head_artefact, upload_output};
 use super::errors::ArtefactError;
+use super::layout::{FlowStoreLayout, StoreLayout};
 
-pub fn get_store_path(flow_id: usize, output_name: &str) -> String {
-    flow_id.to_string() + "/" + output_name
+/// Start of the termination message of a task's container when its command has exceeded the task's timeout,
+/// see [`crate::model::Task::timeout_seconds`].
+pub const TIMEOUT_TERMINATION_MESSAGE: &str = "Task timed out";
+
+/// Time to wait before checking for an input again after the first check, doubled after every check.
+const INITIAL_INPUT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest time to wait between checks for an input, so that a task does not start much later than its input
+/// was uploaded after waiting for a long time.
+const MAX_INPUT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Wait until an input has been uploaded by the task producing it. Outputs are uploaded only after
+/// the producing task's command has succeeded, so an existing output can be downloaded right away.
+/// Tasks only have access to the store and not to the server's events, so the store is polled, with backoff
+/// to limit requests to the store. The wait is bounded by the task's `active_deadline_seconds`.
+async fn wait_for_input(bucket: &Bucket, store_path: &str) -> Result<(), ArtefactError> {
+    let mut poll_interval = INITIAL_INPUT_POLL_INTERVAL;
+
+    loop {
+        match head_artefact(bucket, store_path.to_owned()).await {
+            Ok(_) => return Ok(()),
+            Err(ArtefactError::ArtefactDoesNotExist(_)) => (),
+            Err(error) if error.is_transient() => (),
+            Err(error) => return Err(error),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+        poll_interval = (poll_interval * 2).min(MAX_INPUT_POLL_INTERVAL);
+    }
 }
 
 async fn download_all_inputs(
     bucket: &Bucket,
-    flow_id: usize,
+    layout: &FlowStoreLayout,
     inputs: Vec<Input>,
+    eager_inputs: bool,
 ) -> Result<(), ArtefactError> {
     for input in inputs {
-        let store_path = get_store_path(flow_id, &input.from);
+        let store_path = layout.get_store_path(&input.from);
+
+        if eager_inputs {
+            tracing::info!(store_path, "Waiting for input");
+            wait_for_input(bucket, &store_path).await?;
+        }
+
         download_input(bucket, input.path, store_path).await?;
     }
 
     Ok(())
 }
 
+/// Check that every declared output was produced by the task before uploading any of them,
+/// so that a task which succeeds without producing an output fails with a clear reason.
+async fn check_outputs_exist(outputs: &[Output]) -> Result<(), ArtefactError> {
+    let mut missing_outputs = vec![];
+
+    for output in outputs.iter().filter(|output| output.required) {
+        if !tokio::fs::try_exists(&output.path).await.unwrap_or(false) {
+            missing_outputs.push(format!("{} ({})", output.name, output.path));
+        }
+    }
+
+    if !missing_outputs.is_empty() {
+        return Err(ArtefactError::MissingOutputs(missing_outputs));
+    }
+
+    Ok(())
+}
+
 async fn upload_all_outputs(
     bucket: &Bucket,
-    flow_id: usize,
+    layout: &FlowStoreLayout,
     outputs: Vec<Output>,
+    compress: bool,
 ) -> Result<(), ArtefactError> {
     for output in outputs {
-        let store_path = get_store_path(flow_id, &output.name);
-        upload_output(bucket, output.path, store_path).await?;
+        let store_path = layout.get_store_path(&output.name);
+        upload_output(bucket, output.path, store_path, compress).await?;
     }
 
     Ok(())
 }
 
+/// Environment variable of the task with a value read from an input of the task, see [`crate::model::InputRef`].
+#[derive(Deserialize, Debug)]
+struct InputEnv {
+    name: String,
+    path: String,
+}
+
+/// Read the inputs referred to by environment variables of the task, after the inputs have been downloaded.
+/// Returns the names and values of the environment variables.
+async fn read_input_envs(input_envs: &[InputEnv]) -> Result<Vec<(String, String)>, ArtefactError> {
+    let mut envs = vec![];
+
+    for input_env in input_envs {
+        match tokio::fs::read_to_string(&input_env.path).await {
+            Ok(content) => envs.push((input_env.name.clone(), content.trim().to_owned())),
+            Err(error) => {
+                return Err(ArtefactError::UnableToReadInputEnv(
+                    input_env.name.clone(),
+                    error,
+                ))
+            }
+        }
+    }
+
+    Ok(envs)
+}
+
 #[derive(Deserialize, Debug)]
 pub struct SidecarConfig {
     input_json: String,
     output_json: String,
+    input_env_json: Option<String>,
     flow_id: usize,
     access_key: String,
     secret_key: String,
     bucket_name: String,
     task_store_url: String,
+    bucket_region: Option<String>,
+    bucket_path_style: Option<bool>,
+    task_timeout: Option<u64>,
+    working_dir: Option<String>,
+    stdin: Option<String>,
+    #[serde(default = "default_store_retry_count")]
+    store_retry_count: i32,
+    #[serde(default)]
+    eager_inputs: bool,
+    #[serde(default)]
+    compress_artefacts: bool,
+    store_path_prefix: Option<String>,
+    store_path_suffix: Option<String>,
+}
+
+impl SidecarConfig {
+    /// Layout of keys of the flow's artefacts as set by the server, falls back to the default layout
+    /// for tasks spawned by a server that does not set it.
+    fn get_store_layout(&self) -> FlowStoreLayout {
+        match (&self.store_path_prefix, &self.store_path_suffix) {
+            (Some(prefix), suffix) => {
+                FlowStoreLayout::from_parts(prefix.clone(), suffix.clone().unwrap_or_default())
+            }
+            (None, _) => StoreLayout::default().for_flow(self.flow_id, "", ""),
+        }
+    }
+}
+
+fn default_store_retry_count() -> i32 {
+    5
 }
 
 fn get_command(cmd: Vec<String>) -> Option<Command> {
@@ -62,11 +177,78 @@ fn get_command(cmd: Vec<String>) -> Option<Command> {
         command.args(&cmd[1..]);
     }
 
+    command.stdin(Stdio::null());
     command.stdout(Stdio::inherit());
+    command.stderr(Stdio::piped());
+    command.process_group(0);
+    command.kill_on_drop(true);
 
     Some(command)
 }
 
+/// Kill every process in the process group of the command, so that processes started by the command,
+/// like those started by a shell, do not keep running after the command has timed out.
+fn kill_process_group(pid: u32) {
+    // SAFETY: killpg does not access memory, the command is the leader of its own process group
+    if unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } != 0 {
+        let error = std::io::Error::last_os_error();
+        tracing::warn!(%error, "Unable to kill process group of task");
+    }
+}
+
+/// Write `stdin` to the standard input of the command and close it, so that the command sees the end of its input.
+/// The command may exit without reading all of it, so failing to write is not an error of the task.
+async fn write_stdin(mut child_stdin: tokio::process::ChildStdin, stdin: String) {
+    if let Err(error) = child_stdin.write_all(stdin.as_bytes()).await {
+        tracing::warn!(%error, "Unable to write stdin of task");
+    }
+}
+
+/// Run the command until it exits, returns `None` if it did not exit within `timeout` seconds
+/// in which case the command and every process it has started is killed. `stdin` is piped to the command
+/// if set, otherwise the command reads an empty input.
+async fn run_command(
+    mut command: Command,
+    stdin: Option<String>,
+    timeout: Option<u64>,
+) -> std::io::Result<Option<CommandOutput>> {
+    if stdin.is_some() {
+        command.stdin(Stdio::piped());
+    }
+
+    let mut child = command.spawn()?;
+
+    // Written concurrently so that a command writing to stderr before reading its input does not block forever
+    if let (Some(stdin), Some(child_stdin)) = (stdin, child.stdin.take()) {
+        tokio::spawn(write_stdin(child_stdin, stdin));
+    }
+
+    let Some(timeout) = timeout else {
+        return child.wait_with_output().await.map(Some);
+    };
+
+    let pid = child.id();
+
+    match tokio::time::timeout(Duration::from_secs(timeout), child.wait_with_output()).await {
+        Ok(output) => output.map(Some),
+        Err(_) => {
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/// Write a message to the termination log of the container, so that the reason for
+/// failure appears in the pod's status.
+async fn write_termination_message(message: &str) {
+    if let Err(error) = tokio::fs::write("/dev/termination-log", message).await {
+        tracing::warn!(%error, "Unable to write termination message");
+    }
+}
+
 #[tracing::instrument(skip(config, cmd))]
 pub async fn run_task(config: SidecarConfig, cmd: Vec<String>) -> ExitCode {
     let option_inputs: Option<Vec<Input>> = match serde_json::from_str(&config.input_json) {
@@ -85,31 +267,74 @@ pub async fn run_task(config: SidecarConfig, cmd: Vec<String>) -> ExitCode {
         }
     };
 
-    let Ok(bucket) = get_bucket(
+    let input_envs: Vec<InputEnv> = match config
+        .input_env_json
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+    {
+        Ok(input_envs) => input_envs.unwrap_or_default(),
+        Err(error) => {
+            tracing::error!(%error, "Unable to parse input env json in env variable");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Ok(bucket) = get_bucket_with_retry(
         &config.access_key,
         &config.secret_key,
         &config.bucket_name,
-        config.task_store_url,
+        &config.task_store_url,
+        config.bucket_region.as_deref(),
+        config.bucket_path_style,
+        config.store_retry_count,
     )
     .await
     else {
         return ExitCode::FAILURE;
     };
 
+    let layout = config.get_store_layout();
+
     if let Some(inputs) = option_inputs {
-        if (download_all_inputs(&bucket, config.flow_id, inputs).await).is_err() {
+        if (download_all_inputs(&bucket, &layout, inputs, config.eager_inputs).await).is_err() {
             return ExitCode::FAILURE;
         }
     }
 
+    let envs = match read_input_envs(&input_envs).await {
+        Ok(envs) => envs,
+        Err(error) => {
+            let message = error.to_string();
+            tracing::error!(message);
+            write_termination_message(&message).await;
+            return ExitCode::FAILURE;
+        }
+    };
+
     let Some(mut command) = get_command(cmd) else {
         tracing::error!("Invalid command");
         return ExitCode::FAILURE;
     };
 
-    // TODO: Add timeout
-    let task_output = match command.output() {
-        Ok(task_output) => task_output,
+    command.envs(envs);
+
+    if let Some(working_dir) = &config.working_dir {
+        command.current_dir(working_dir);
+    }
+
+    let task_output = match run_command(command, config.stdin, config.task_timeout).await {
+        Ok(Some(task_output)) => task_output,
+        Ok(None) => {
+            let message = format!(
+                "{} after {} seconds",
+                TIMEOUT_TERMINATION_MESSAGE,
+                config.task_timeout.unwrap_or_default()
+            );
+            tracing::error!(message);
+            write_termination_message(&message).await;
+            return ExitCode::FAILURE;
+        }
         Err(error) => {
             tracing::error!(%error, "Failed to run task");
             return ExitCode::FAILURE;
@@ -135,10 +360,242 @@ pub async fn run_task(config: SidecarConfig, cmd: Vec<String>) -> ExitCode {
     }
 
     if let Some(outputs) = option_outputs {
-        if (upload_all_outputs(&bucket, config.flow_id, outputs).await).is_err() {
+        if let Err(error) = check_outputs_exist(&outputs).await {
+            let message = error.to_string();
+            tracing::error!(message);
+            write_termination_message(&message).await;
+            return ExitCode::FAILURE;
+        }
+
+        if (upload_all_outputs(&bucket, &layout, outputs, config.compress_artefacts).await).is_err()
+        {
             return ExitCode::FAILURE;
         }
     }
 
     ExitCode::SUCCESS
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use s3::{creds::Credentials, Region};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_outputs_exist() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let produced_path = dir.path().join("produced").to_str().unwrap().to_owned();
+        let omitted_path = dir.path().join("omitted").to_str().unwrap().to_owned();
+
+        let status = get_command(vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            format!("echo hello > {}", produced_path),
+        ])
+        .unwrap()
+        .status()
+        .await
+        .unwrap();
+        assert!(status.success());
+
+        let produced = Output {
+            name: "produced".to_owned(),
+            path: produced_path,
+            required: true,
+            ..Default::default()
+        };
+
+        let omitted = Output {
+            name: "omitted".to_owned(),
+            path: omitted_path.clone(),
+            required: true,
+            ..Default::default()
+        };
+
+        let omitted_optional = Output {
+            name: "omitted-optional".to_owned(),
+            path: omitted_path.clone(),
+            ..Default::default()
+        };
+
+        assert!(check_outputs_exist(std::slice::from_ref(&produced))
+            .await
+            .is_ok());
+
+        // Only outputs that opt in are checked
+        assert!(check_outputs_exist(&[produced.clone(), omitted_optional])
+            .await
+            .is_ok());
+
+        match check_outputs_exist(&[produced, omitted]).await {
+            Err(ArtefactError::MissingOutputs(missing)) => {
+                assert_eq!(missing, vec![format!("omitted ({})", omitted_path)])
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_input_envs() {
+        let dir = std::env::temp_dir().join("flowmium-test-input-envs");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        // Input downloaded from the output of an upstream task
+        let input_path = dir.join("greeting.txt").to_str().unwrap().to_owned();
+        let echoed_path = dir.join("echoed.txt").to_str().unwrap().to_owned();
+        tokio::fs::write(&input_path, "hello world\n")
+            .await
+            .unwrap();
+
+        let envs = read_input_envs(&[InputEnv {
+            name: "GREETING".to_owned(),
+            path: input_path,
+        }])
+        .await
+        .unwrap();
+
+        assert_eq!(
+            envs,
+            vec![("GREETING".to_owned(), "hello world".to_owned())]
+        );
+
+        let mut command = get_command(vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            format!("echo \"$GREETING!\" > {}", echoed_path),
+        ])
+        .unwrap();
+        command.envs(envs);
+
+        let output = run_command(command, None, None).await.unwrap().unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            tokio::fs::read_to_string(&echoed_path).await.unwrap(),
+            "hello world!\n"
+        );
+
+        let missing = read_input_envs(&[InputEnv {
+            name: "MISSING".to_owned(),
+            path: dir.join("missing.txt").to_str().unwrap().to_owned(),
+        }])
+        .await;
+
+        assert!(matches!(
+            missing,
+            Err(ArtefactError::UnableToReadInputEnv(name, _)) if name == "MISSING"
+        ));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_command_timeout() {
+        let command = get_command(vec!["sleep".to_owned(), "10".to_owned()]).unwrap();
+
+        let started = std::time::Instant::now();
+        let output = run_command(command, None, Some(1)).await.unwrap();
+
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let command =
+            get_command(vec!["sh".to_owned(), "-c".to_owned(), "exit 3".to_owned()]).unwrap();
+        let output = run_command(command, None, Some(1)).await.unwrap().unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_command_stdin_and_working_dir() {
+        let dir = std::env::temp_dir().join("flowmium-test-stdin");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let mut command = get_command(vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            "cat > piped.txt".to_owned(),
+        ])
+        .unwrap();
+        command.current_dir(&dir);
+
+        let output = run_command(command, Some("hello world\n".to_owned()), None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(
+            tokio::fs::read_to_string(dir.join("piped.txt"))
+                .await
+                .unwrap(),
+            "hello world\n"
+        );
+
+        let command = get_command(vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            "test -z \"$(cat)\"".to_owned(),
+        ])
+        .unwrap();
+        let output = run_command(command, None, None).await.unwrap().unwrap();
+
+        assert!(output.status.success());
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_input() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let num_requests = Arc::new(AtomicUsize::new(0));
+        let num_requests_server = num_requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+
+                let mut request = [0; 4096];
+                let _ = socket.read(&mut request).await.unwrap();
+                assert!(request.starts_with(b"HEAD /flowmium-test/12/output-a "));
+
+                let response: &[u8] = match num_requests_server.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+                    _ => b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+                };
+                socket.write_all(response).await.unwrap();
+            }
+        });
+
+        let bucket = Bucket::new(
+            "flowmium-test",
+            Region::Custom {
+                region: "custom".to_owned(),
+                endpoint: url,
+            },
+            Credentials::new(Some("minio"), Some("password"), None, None, None).unwrap(),
+        )
+        .unwrap()
+        .with_path_style();
+
+        let started_at = std::time::Instant::now();
+        wait_for_input(&bucket, "12/output-a").await.unwrap();
+
+        assert_eq!(num_requests.load(Ordering::SeqCst), 3);
+
+        // Checks back off, 500 milliseconds after the first check and a second after the second
+        assert!(started_at.elapsed() >= Duration::from_millis(1500));
+    }
+}
@@ -1,3 +1,4 @@
+use s3::error::S3Error;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -24,4 +25,89 @@ pub enum ArtefactError {
     UnableToUploadArtifactApi(u16),
     #[error("artefact {0} does not exist")]
     ArtefactDoesNotExist(String),
+    #[error("unable to check for existence of artefact: {0}")]
+    UnableToCheckArtefact(s3::error::S3Error),
+    #[error("unable to check for existence of artefact api errored with status {0}")]
+    UnableToCheckArtefactApi(u16),
+    #[error("unable to list artefacts: {0}")]
+    UnableToListArtefacts(s3::error::S3Error),
+    #[error("unable to delete artefact: {0}")]
+    UnableToDeleteArtefact(s3::error::S3Error),
+    #[error("unable to delete artefact api errored with status {0}")]
+    UnableToDeleteArtefactApi(u16),
+    #[error("unable to compress output: {0}")]
+    UnableToCompressOutput(std::io::Error),
+    #[error("unable to decompress artefact: {0}")]
+    UnableToDecompressArtefact(std::io::Error),
+    #[error("unable to archive output directory: {0}")]
+    UnableToArchiveOutput(std::io::Error),
+    #[error("unable to extract input directory: {0}")]
+    UnableToExtractInput(std::io::Error),
+    #[error("unable to read input into environment variable {0}: {1}")]
+    UnableToReadInputEnv(String, std::io::Error),
+    #[error("artefact {0} was uploaded with checksum {1} but downloaded with checksum {2}")]
+    ChecksumMismatch(String, String, String),
+    #[error("task succeeded but did not produce declared outputs: {}", .0.join(", "))]
+    MissingOutputs(Vec<String>),
+}
+
+fn is_transient_status(status: u16) -> bool {
+    status == 408 || status == 429 || status >= 500
+}
+
+fn is_transient_s3_error(error: &S3Error) -> bool {
+    match error {
+        S3Error::Hyper(_) | S3Error::Io(_) => true,
+        S3Error::HttpFailWithBody(status, _) => is_transient_status(*status),
+        _ => false,
+    }
+}
+
+impl ArtefactError {
+    /// Returns `true` if the error is likely because the store is unreachable or overloaded and
+    /// the operation can be retried. Errors like bad credentials or a misconfigured bucket are not transient.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ArtefactError::UnableToUploadArtifact(error)
+            | ArtefactError::UnableToDownloadInput(error)
+            | ArtefactError::UnableToCheckExistence(error)
+            | ArtefactError::UnableToCreateBucket(error)
+            | ArtefactError::UnableToExistingOpenBucket(error)
+            | ArtefactError::UnableToCheckArtefact(error)
+            | ArtefactError::UnableToListArtefacts(error)
+            | ArtefactError::UnableToDeleteArtefact(error) => is_transient_s3_error(error),
+            ArtefactError::UnableToDownloadInputApi(status)
+            | ArtefactError::UnableToUploadArtifactApi(status)
+            | ArtefactError::UnableToCheckArtefactApi(status)
+            | ArtefactError::UnableToDeleteArtefactApi(status) => is_transient_status(*status),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_transient() {
+        assert!(
+            ArtefactError::UnableToCheckExistence(S3Error::HttpFailWithBody(503, "".to_owned()))
+                .is_transient()
+        );
+        assert!(
+            ArtefactError::UnableToCheckExistence(S3Error::Io(std::io::Error::from(
+                std::io::ErrorKind::ConnectionRefused
+            )))
+            .is_transient()
+        );
+        assert!(ArtefactError::UnableToUploadArtifactApi(429).is_transient());
+
+        assert!(
+            !ArtefactError::UnableToCheckExistence(S3Error::HttpFailWithBody(403, "".to_owned()))
+                .is_transient()
+        );
+        assert!(!ArtefactError::UnableToCreateBucketFailResponse("".to_owned()).is_transient());
+        assert!(!ArtefactError::UnableToDownloadInputApi(404).is_transient());
+    }
 }
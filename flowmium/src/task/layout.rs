@@ -0,0 +1,330 @@
+//! Layout of keys of artefacts in the bucket, see [`StoreLayout`].
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Layout used if none is configured, artefacts are stored as `{flow_id}/{output_name}`.
+pub const DEFAULT_STORE_LAYOUT: &str = "{flow_id}/{output_name}";
+
+/// Error parsing an artefact key layout template.
+#[derive(Error, Debug, PartialEq)]
+pub enum StoreLayoutError {
+    /// Template refers to a placeholder that is not supported.
+    #[error("unknown placeholder {{{0}}} in artefact key layout")]
+    UnknownPlaceholder(String),
+    /// Template has a `{` without a matching `}`.
+    #[error("unclosed placeholder in artefact key layout")]
+    UnclosedPlaceholder,
+    /// Template does not contain `{flow_id}` or does not contain `{output_name}` exactly once,
+    /// so it would not yield a unique key for each output.
+    #[error("artefact key layout has to contain {{flow_id}} and exactly one {{output_name}}")]
+    NotUnique,
+    /// `{flow_id}` is directly followed by another placeholder or by a digit, or directly preceded by `{flow_name}`
+    /// or `{output_name}`, so that the end of the flow's ID in a key is ambiguous. Like `{flow_id}{output_name}`,
+    /// where flow 1 with output `2x` and flow 12 with output `x` would have the same key `12x`.
+    #[error("{{flow_id}} in artefact key layout has to be separated from other placeholders by a character that is not a digit")]
+    AmbiguousFlowId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    FlowId,
+    FlowName,
+    Date,
+    OutputName,
+}
+
+impl Segment {
+    fn from_placeholder(placeholder: &str) -> Result<Segment, StoreLayoutError> {
+        match placeholder {
+            "flow_id" => Ok(Segment::FlowId),
+            "flow_name" => Ok(Segment::FlowName),
+            "date" => Ok(Segment::Date),
+            "output_name" => Ok(Segment::OutputName),
+            _ => Err(StoreLayoutError::UnknownPlaceholder(placeholder.to_owned())),
+        }
+    }
+
+    /// Returns `true` if `value` can be the value of the placeholder.
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            Segment::Literal(literal) => value == literal,
+            Segment::FlowId => value.chars().all(|c| c.is_ascii_digit()),
+            Segment::Date => value.len() == 10,
+            Segment::FlowName | Segment::OutputName => true,
+        }
+    }
+}
+
+/// Template for keys of artefacts in the bucket, like `{date}/{flow_name}/{flow_id}/{output_name}`.
+/// Supported placeholders are `{flow_id}`, `{flow_name}`, `{date}` which is the date the flow was created
+/// on in UTC as `YYYY-MM-DD` and `{output_name}`. The template has to contain `{flow_id}` and exactly one
+/// `{output_name}`, and `{flow_id}` has to be separated from other placeholders by a character that is not a digit,
+/// so that each output of each flow has a unique key.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct StoreLayout {
+    segments: Vec<Segment>,
+}
+
+impl StoreLayout {
+    /// Parse and validate a template.
+    pub fn parse(template: &str) -> Result<StoreLayout, StoreLayoutError> {
+        let mut segments = vec![];
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_owned()));
+            }
+
+            let Some(end) = rest[start..].find('}') else {
+                return Err(StoreLayoutError::UnclosedPlaceholder);
+            };
+
+            segments.push(Segment::from_placeholder(&rest[start + 1..start + end])?);
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_owned()));
+        }
+
+        let count = |segment: Segment| segments.iter().filter(|s| **s == segment).count();
+
+        if count(Segment::FlowId) == 0 || count(Segment::OutputName) != 1 {
+            return Err(StoreLayoutError::NotUnique);
+        }
+
+        let is_separated = |pair: &[Segment]| match pair {
+            [Segment::FlowId, Segment::Literal(literal)] => {
+                !literal.starts_with(|c: char| c.is_ascii_digit())
+            }
+            [Segment::FlowId, _] | [Segment::FlowName | Segment::OutputName, Segment::FlowId] => {
+                false
+            }
+            _ => true,
+        };
+
+        if !segments.windows(2).all(is_separated) {
+            return Err(StoreLayoutError::AmbiguousFlowId);
+        }
+
+        Ok(StoreLayout { segments })
+    }
+
+    /// Returns `true` if keys depend on the flow's name or creation date and not just on its ID.
+    pub fn needs_flow_details(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::FlowName | Segment::Date))
+    }
+
+    /// Fill in placeholders of a flow, `date` is the date the flow was created on formatted as `YYYY-MM-DD`.
+    pub fn for_flow(&self, flow_id: usize, flow_name: &str, date: &str) -> FlowStoreLayout {
+        let mut prefix = String::new();
+        let mut suffix = String::new();
+        let mut current = &mut prefix;
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => current.push_str(literal),
+                Segment::FlowId => current.push_str(&flow_id.to_string()),
+                Segment::FlowName => current.push_str(flow_name),
+                Segment::Date => current.push_str(date),
+                Segment::OutputName => current = &mut suffix,
+            }
+        }
+
+        FlowStoreLayout { prefix, suffix }
+    }
+
+    /// ID of the flow an artefact key belongs to, `None` if the key does not match the template.
+    pub fn get_flow_id(&self, key: &str) -> Option<usize> {
+        capture_flow_id(&self.segments, key)?
+    }
+}
+
+impl Default for StoreLayout {
+    fn default() -> Self {
+        // SAFETY: The default template is valid
+        StoreLayout::parse(DEFAULT_STORE_LAYOUT).unwrap()
+    }
+}
+
+impl TryFrom<String> for StoreLayout {
+    type Error = StoreLayoutError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        StoreLayout::parse(&template)
+    }
+}
+
+/// Match `key` against `segments`, returns `Some` with the value of `{flow_id}` if the key matches.
+fn capture_flow_id(segments: &[Segment], key: &str) -> Option<Option<usize>> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return key.is_empty().then_some(None);
+    };
+
+    if let Segment::Literal(literal) = segment {
+        return capture_flow_id(rest, key.strip_prefix(literal.as_str())?);
+    }
+
+    let ends = key
+        .char_indices()
+        .map(|(i, _)| i)
+        .skip(1)
+        .chain([key.len()]);
+
+    for end in ends {
+        let (value, remaining) = key.split_at(end);
+
+        if !segment.accepts(value) {
+            // Flow IDs only get longer with more characters that are not digits
+            if *segment == Segment::FlowId {
+                return None;
+            }
+
+            continue;
+        }
+
+        if let Some(flow_id) = capture_flow_id(rest, remaining) {
+            return match segment {
+                Segment::FlowId => Some(value.parse().ok()),
+                _ => Some(flow_id),
+            };
+        }
+    }
+
+    None
+}
+
+/// Layout of keys of a single flow's artefacts, the key of an output is `prefix` followed by the output's name
+/// and `suffix`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowStoreLayout {
+    prefix: String,
+    suffix: String,
+}
+
+impl FlowStoreLayout {
+    /// Layout from parts passed to a task by the server.
+    pub fn from_parts(prefix: String, suffix: String) -> FlowStoreLayout {
+        FlowStoreLayout { prefix, suffix }
+    }
+
+    /// Part of the key before the output's name, all keys of the flow's artefacts start with this.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Part of the key after the output's name.
+    pub fn suffix(&self) -> &str {
+        &self.suffix
+    }
+
+    /// Key of an output in the bucket.
+    pub fn get_store_path(&self, output_name: &str) -> String {
+        format!("{}{}{}", self.prefix, output_name, self.suffix)
+    }
+
+    /// Name of the output whose key is `key`, if the key is of one of the flow's artefacts.
+    pub fn get_output_name<'a>(&self, key: &'a str) -> Option<&'a str> {
+        if !self.matches(key) {
+            return None;
+        }
+
+        Some(&key[self.prefix.len()..key.len() - self.suffix.len()])
+    }
+
+    /// Returns `true` if the key is of one of the flow's artefacts.
+    pub fn matches(&self, key: &str) -> bool {
+        key.len() > self.prefix.len() + self.suffix.len()
+            && key.starts_with(&self.prefix)
+            && key.ends_with(&self.suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_layout() {
+        let default = StoreLayout::default();
+        assert!(!default.needs_flow_details());
+        assert_eq!(
+            default
+                .for_flow(12, "my-flow", "2026-10-17")
+                .get_store_path("report"),
+            "12/report"
+        );
+        assert_eq!(default.get_flow_id("12/report"), Some(12));
+        assert_eq!(default.get_flow_id("stray-object"), None);
+
+        let layout = StoreLayout::parse("{date}/{flow_name}/{flow_id}/{output_name}.bin").unwrap();
+        assert!(layout.needs_flow_details());
+
+        let flow_layout = layout.for_flow(12, "my-flow", "2026-10-17");
+        assert_eq!(
+            flow_layout.get_store_path("report"),
+            "2026-10-17/my-flow/12/report.bin"
+        );
+        assert!(flow_layout.matches("2026-10-17/my-flow/12/report.bin"));
+        assert!(!flow_layout.matches("2026-10-17/my-flow/123/report.bin"));
+        assert_eq!(
+            flow_layout.get_output_name("2026-10-17/my-flow/12/report.bin"),
+            Some("report")
+        );
+        assert_eq!(
+            flow_layout.get_output_name("2026-10-17/my-flow/12/report"),
+            None
+        );
+        assert_eq!(
+            layout.get_flow_id("2026-10-17/my-flow/12/report.bin"),
+            Some(12)
+        );
+        assert_eq!(layout.get_flow_id("2026-10-17/my-flow/12/report"), None);
+
+        assert_eq!(
+            StoreLayout::parse("{flow_id}/{bucket}/{output_name}"),
+            Err(StoreLayoutError::UnknownPlaceholder("bucket".to_owned()))
+        );
+        assert_eq!(
+            StoreLayout::parse("{flow_id}/{output_name"),
+            Err(StoreLayoutError::UnclosedPlaceholder)
+        );
+        assert_eq!(
+            StoreLayout::parse("{flow_name}/{output_name}"),
+            Err(StoreLayoutError::NotUnique)
+        );
+        assert_eq!(
+            StoreLayout::parse("{flow_id}/{output_name}/{output_name}"),
+            Err(StoreLayoutError::NotUnique)
+        );
+
+        for template in [
+            "{flow_id}{output_name}",
+            "{flow_id}0/{output_name}",
+            "{flow_id}{date}/{output_name}",
+            "{flow_name}{flow_id}/{output_name}",
+            "{output_name}{flow_id}",
+        ] {
+            assert_eq!(
+                StoreLayout::parse(template),
+                Err(StoreLayoutError::AmbiguousFlowId)
+            );
+        }
+
+        let layout = StoreLayout::parse("{flow_id}-{output_name}").unwrap();
+        assert_eq!(
+            layout
+                .for_flow(1, "my-flow", "2026-10-17")
+                .get_store_path("2x"),
+            "1-2x"
+        );
+        assert_eq!(layout.get_flow_id("12-x"), Some(12));
+        assert!(StoreLayout::parse("{date}{flow_id}/{output_name}").is_ok());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod bucket;
 pub mod driver;
 pub mod errors;
+pub mod layout;